@@ -0,0 +1,144 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
+
+use chrono::NaiveDate;
+use futures::{Stream, stream};
+use tonik::{BillingPeriodUsage, TeltonikaClient};
+use tracing::{error, warn};
+
+use crate::event::DataUsageEvent;
+
+/// Warns once per billing period as each SIM passes percentages of its data limit.
+pub struct DataUsageWarnings {
+    /// Percentages of the limit, in ascending order.
+    thresholds: Vec<u32>,
+    /// The highest threshold reported for each SIM, with the billing period it
+    /// was reported in.
+    reported: HashMap<(String, u8), (NaiveDate, u32)>,
+}
+
+impl DataUsageWarnings {
+    pub fn new(mut thresholds: Vec<u32>) -> Self {
+        thresholds.sort_unstable();
+        thresholds.dedup();
+
+        DataUsageWarnings {
+            thresholds,
+            reported: HashMap::new(),
+        }
+    }
+
+    /// Events for the SIMs that passed a threshold since the last check. A SIM
+    /// passing several at once is only reported for the highest.
+    pub fn check(&mut self, usage: &[BillingPeriodUsage]) -> Vec<DataUsageEvent> {
+        let mut events = Vec::new();
+
+        for sim in usage {
+            let Some(percent) = sim.percent_used() else {
+                continue;
+            };
+            let Some(threshold) = self
+                .thresholds
+                .iter()
+                .rev()
+                .find(|threshold| percent >= f64::from(**threshold))
+                .copied()
+            else {
+                continue;
+            };
+
+            let key = (sim.modem.clone(), sim.sim);
+            let already_reported = self
+                .reported
+                .get(&key)
+                .is_some_and(|(start, reported)| *start == sim.start && *reported >= threshold);
+            if !already_reported {
+                self.reported.insert(key, (sim.start, threshold));
+                events.push(DataUsageEvent::new(threshold, sim));
+            }
+        }
+
+        events
+    }
+
+    /// Check the router's data usage every `interval`, starting straight away.
+    ///
+    /// Failed checks are logged and retried after the interval.
+    pub fn into_stream(
+        self,
+        client: Arc<TeltonikaClient>,
+        interval: Duration,
+    ) -> impl Stream<Item = DataUsageEvent> {
+        let state = (self, VecDeque::new(), true);
+
+        stream::unfold(state, move |(mut warnings, mut pending, mut first)| {
+            let client = client.clone();
+            async move {
+                loop {
+                    if let Some(event) = pending.pop_front() {
+                        return Some((event, (warnings, pending, first)));
+                    }
+
+                    if !first {
+                        tokio::time::sleep(interval).await;
+                    }
+                    first = false;
+
+                    match BillingPeriodUsage::fetch(&client).await {
+                        Ok(usage) => pending.extend(warnings.check(&usage)),
+                        Err(err) => error!("Failed to check data usage: {}", err),
+                    }
+                }
+            }
+        })
+    }
+}
+
+pub fn report(event: &DataUsageEvent) {
+    warn!(
+        "Passed {}% of the data limit: {}",
+        event.threshold, event.usage
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(start: &str, used: u64) -> BillingPeriodUsage {
+        let start: NaiveDate = start.parse().unwrap();
+        BillingPeriodUsage {
+            modem: "2-1".to_string(),
+            sim: 1,
+            start,
+            end: start + chrono::Months::new(1),
+            used,
+            today: 0,
+            limit: Some(1000),
+            projected: used,
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        let mut warnings = DataUsageWarnings::new(vec![100, 80, 50]);
+        let thresholds = |warnings: &mut DataUsageWarnings, usage| -> Vec<u32> {
+            warnings
+                .check(&[usage])
+                .iter()
+                .map(|event| event.threshold)
+                .collect()
+        };
+
+        assert!(thresholds(&mut warnings, usage("2026-10-01", 400)).is_empty());
+        assert_eq!(thresholds(&mut warnings, usage("2026-10-01", 850)), [80]);
+        assert!(thresholds(&mut warnings, usage("2026-10-01", 900)).is_empty());
+        assert_eq!(thresholds(&mut warnings, usage("2026-10-01", 1000)), [100]);
+
+        // A new billing period starts over.
+        assert_eq!(thresholds(&mut warnings, usage("2026-11-01", 600)), [50]);
+    }
+}
@@ -0,0 +1,252 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use serde::Deserialize;
+use tonik::MacAddress;
+
+/// What to do when a device that isn't in the registry arrives or leaves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum UnknownDevicePolicy {
+    /// Log at info level, like known devices
+    Log,
+    /// Log at warn level
+    #[default]
+    Warn,
+    /// Log at error level so the event stands out
+    Alert,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Device {
+    pub name: String,
+    pub owner: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub macs: Vec<MacAddress>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct DevicesFile {
+    unknown_device: Option<UnknownDevicePolicy>,
+    #[serde(default, rename = "device")]
+    devices: Vec<Device>,
+}
+
+#[derive(Debug)]
+pub enum RegistryError {
+    Io(std::io::Error),
+    Toml(toml::de::Error),
+    Yaml(serde_yaml::Error),
+    DuplicateMac(MacAddress),
+}
+
+impl Display for RegistryError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            RegistryError::Io(err) => write!(f, "{}", err),
+            RegistryError::Toml(err) => write!(f, "{}", err),
+            RegistryError::Yaml(err) => write!(f, "{}", err),
+            RegistryError::DuplicateMac(mac) => {
+                write!(f, "{} is listed for more than one device", mac)
+            }
+        }
+    }
+}
+
+/// Known devices, loaded from a TOML or YAML file and reloaded when it changes.
+///
+/// ```toml
+/// unknown_device = "warn"
+///
+/// [[device]]
+/// name = "Ben's iPhone"
+/// owner = "Ben"
+/// tags = ["phone"]
+/// macs = ["82:87:EE:2A:86:AE"]
+/// ```
+#[derive(Debug, Default)]
+pub struct DeviceRegistry {
+    path: Option<PathBuf>,
+    modified: Option<SystemTime>,
+    devices: Vec<Device>,
+    by_mac: HashMap<MacAddress, usize>,
+    unknown_device: Option<UnknownDevicePolicy>,
+}
+
+impl DeviceRegistry {
+    pub fn load(path: &Path) -> Result<Self, RegistryError> {
+        let mut registry = DeviceRegistry {
+            path: Some(path.to_path_buf()),
+            ..Default::default()
+        };
+        registry.reload()?;
+        Ok(registry)
+    }
+
+    fn parse(path: &Path, contents: &str) -> Result<DevicesFile, RegistryError> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("yaml" | "yml") => serde_yaml::from_str(contents).map_err(RegistryError::Yaml),
+            _ => toml::from_str(contents).map_err(RegistryError::Toml),
+        }
+    }
+
+    fn reload(&mut self) -> Result<(), RegistryError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let modified = fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .map_err(RegistryError::Io)?;
+        let contents = fs::read_to_string(path).map_err(RegistryError::Io)?;
+        let file = Self::parse(path, &contents)?;
+
+        let mut by_mac = HashMap::new();
+        for (index, device) in file.devices.iter().enumerate() {
+            for mac in &device.macs {
+                if by_mac.insert(*mac, index).is_some() {
+                    return Err(RegistryError::DuplicateMac(*mac));
+                }
+            }
+        }
+
+        self.modified = Some(modified);
+        self.devices = file.devices;
+        self.by_mac = by_mac;
+        self.unknown_device = file.unknown_device;
+
+        Ok(())
+    }
+
+    /// Reload the file if it has been modified since it was last loaded.
+    ///
+    /// Returns whether the registry changed. If the new contents are invalid the
+    /// previous registry is kept and the error is returned.
+    pub fn reload_if_changed(&mut self) -> Result<bool, RegistryError> {
+        let Some(path) = &self.path else {
+            return Ok(false);
+        };
+
+        let modified = fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .map_err(RegistryError::Io)?;
+        if Some(modified) == self.modified {
+            return Ok(false);
+        }
+
+        // Don't retry a broken file until it changes again.
+        self.modified = Some(modified);
+        self.reload()?;
+        Ok(true)
+    }
+
+    pub fn get(&self, mac: &MacAddress) -> Option<&Device> {
+        self.by_mac.get(mac).map(|index| &self.devices[*index])
+    }
+
+    /// Find a device by name, ignoring case.
+    pub fn find(&self, name: &str) -> Option<&Device> {
+        self.devices
+            .iter()
+            .find(|device| device.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn len(&self) -> usize {
+        self.devices.len()
+    }
+
+    /// The unknown device policy from the file, if it sets one.
+    pub fn unknown_device_policy(&self) -> Option<UnknownDevicePolicy> {
+        self.unknown_device
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(name: &str, contents: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tonik-activity-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_load_toml() {
+        let path = write(
+            "devices.toml",
+            r#"
+                unknown_device = "alert"
+
+                [[device]]
+                name = "Ben's iPhone"
+                owner = "Ben"
+                tags = ["phone"]
+                macs = ["82:87:EE:2A:86:AE", "82-87-ee-2a-86-af"]
+            "#,
+        );
+
+        let registry = DeviceRegistry::load(&path).unwrap();
+        let device = registry.get(&"82:87:ee:2a:86:af".parse().unwrap()).unwrap();
+
+        assert_eq!(device.name, "Ben's iPhone");
+        assert_eq!(device.owner.as_deref(), Some("Ben"));
+        assert_eq!(device.tags, vec!["phone"]);
+        assert_eq!(
+            registry.unknown_device_policy(),
+            Some(UnknownDevicePolicy::Alert)
+        );
+    }
+
+    #[test]
+    fn test_load_yaml() {
+        let path = write(
+            "devices.yaml",
+            r#"
+device:
+  - name: Shelly H/T
+    tags: [sensor]
+    macs: ["84:F3:EB:E0:12:12"]
+"#,
+        );
+
+        let registry = DeviceRegistry::load(&path).unwrap();
+
+        assert_eq!(registry.len(), 1);
+        assert_eq!(registry.unknown_device_policy(), None);
+        assert!(
+            registry
+                .get(&"84:F3:EB:E0:12:12".parse().unwrap())
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_duplicate_mac() {
+        let path = write(
+            "duplicate.toml",
+            r#"
+                [[device]]
+                name = "One"
+                macs = ["82:87:EE:2A:86:AE"]
+
+                [[device]]
+                name = "Two"
+                macs = ["82:87:ee:2a:86:ae"]
+            "#,
+        );
+
+        assert!(matches!(
+            DeviceRegistry::load(&path),
+            Err(RegistryError::DuplicateMac(_))
+        ));
+    }
+}
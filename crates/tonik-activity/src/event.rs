@@ -0,0 +1,94 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tonik::{BillingPeriodUsage, MacAddress, presence::PresenceEvent};
+
+use crate::devices::DeviceRegistry;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActivityKind {
+    Arrived,
+    Departed,
+}
+
+/// A device joining or leaving the network, as written to the output sinks.
+///
+/// ```json
+/// {"timestamp":"2025-03-19T10:21:53Z","event":"arrived","mac":"82:87:EE:2A:86:AE",
+///  "first_seen":"2025-03-19T10:21:53Z","last_seen":null,"known":true,
+///  "name":"Ben's iPhone","owner":"Ben","tags":["phone"],"vendor":null,"randomized":true}
+/// ```
+#[derive(Debug, Clone, Serialize)]
+pub struct ActivityEvent {
+    pub timestamp: DateTime<Utc>,
+    pub event: ActivityKind,
+    pub mac: MacAddress,
+    pub first_seen: DateTime<Utc>,
+    /// When a departed device was last seen.
+    pub last_seen: Option<DateTime<Utc>>,
+    pub known: bool,
+    pub name: Option<String>,
+    pub owner: Option<String>,
+    pub tags: Vec<String>,
+    pub vendor: Option<String>,
+    pub randomized: bool,
+}
+
+impl ActivityEvent {
+    pub fn new(presence: &PresenceEvent, registry: &DeviceRegistry) -> Self {
+        let (event, mac, first_seen, last_seen) = match *presence {
+            PresenceEvent::Arrived { mac, first_seen } => {
+                (ActivityKind::Arrived, mac, first_seen, None)
+            }
+            PresenceEvent::Departed {
+                mac,
+                first_seen,
+                last_seen,
+            } => (ActivityKind::Departed, mac, first_seen, Some(last_seen)),
+        };
+        let device = registry.get(&mac);
+
+        ActivityEvent {
+            timestamp: Utc::now(),
+            event,
+            mac,
+            first_seen,
+            last_seen,
+            known: device.is_some(),
+            name: device.map(|device| device.name.clone()),
+            owner: device.and_then(|device| device.owner.clone()),
+            tags: device.map(|device| device.tags.clone()).unwrap_or_default(),
+            vendor: mac.vendor().map(str::to_string),
+            randomized: mac.is_locally_administered(),
+        }
+    }
+}
+
+/// A SIM passing a percentage of its data limit, as written to the output sinks.
+///
+/// ```json
+/// {"timestamp":"2026-10-17T09:12:40Z","event":"data_usage_warning","threshold":80,
+///  "modem":"2-1","sim":1,"start":"2026-10-01","end":"2026-11-01","used":17000000000,
+///  "today":1000000000,"limit":20000000000,"projected":31000000000}
+/// ```
+#[derive(Debug, Clone, Serialize)]
+pub struct DataUsageEvent {
+    pub timestamp: DateTime<Utc>,
+    /// Always `data_usage_warning`.
+    pub event: &'static str,
+    /// Percentage of the limit that was passed.
+    pub threshold: u32,
+    #[serde(flatten)]
+    pub usage: BillingPeriodUsage,
+}
+
+impl DataUsageEvent {
+    pub fn new(threshold: u32, usage: &BillingPeriodUsage) -> Self {
+        DataUsageEvent {
+            timestamp: Utc::now(),
+            event: "data_usage_warning",
+            threshold,
+            usage: usage.clone(),
+        }
+    }
+}
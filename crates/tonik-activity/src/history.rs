@@ -0,0 +1,172 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Local, Utc};
+use tonik::{
+    MacAddress,
+    presence::{PresenceEvent, history::PresenceHistory},
+};
+use tracing::error;
+
+use crate::{describe, devices::DeviceRegistry};
+
+/// Parse an age such as `90s`, `30m`, `12h`, `7d` or `2w`.
+pub fn parse_age(age: &str) -> Result<Duration, String> {
+    let split = age.find(|c: char| !c.is_ascii_digit()).unwrap_or(age.len());
+    let (amount, unit) = age.split_at(split);
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| format!("invalid age {:?}, expected e.g. 7d", age))?;
+
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" | "" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(format!("unknown unit {:?}, expected s, m, h, d or w", unit)),
+    };
+
+    amount
+        .checked_mul(seconds)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("age {:?} is too long", age))
+}
+
+pub fn show(
+    history: &PresenceHistory,
+    registry: &DeviceRegistry,
+    device: Option<&str>,
+    since: Option<Duration>,
+) {
+    let macs: Vec<MacAddress> = match device {
+        None => Vec::new(),
+        Some(device) => match (device.parse(), registry.find(device)) {
+            (Ok(mac), _) => vec![mac],
+            (Err(_), Some(device)) => device.macs.clone(),
+            (Err(_), None) => {
+                error!("No device named {:?} in the devices file", device);
+                std::process::exit(1);
+            }
+        },
+    };
+    let since = since.map(|since| {
+        chrono::Duration::from_std(since)
+            .ok()
+            .and_then(|since| Utc::now().checked_sub_signed(since))
+            .unwrap_or_else(|| {
+                error!("--since is too long ago");
+                std::process::exit(1);
+            })
+    });
+
+    let events = history.events(&macs, since).unwrap_or_else(|err| {
+        error!("Failed to read history: {}", err);
+        std::process::exit(1);
+    });
+
+    for event in events {
+        let mac = event.mac();
+        match event {
+            PresenceEvent::Arrived { first_seen, .. } => {
+                println!(
+                    "{}  Arrived   {}  {}",
+                    local(first_seen),
+                    mac,
+                    name(&mac, registry)
+                );
+            }
+            PresenceEvent::Departed {
+                first_seen,
+                last_seen,
+                ..
+            } => {
+                println!(
+                    "{}  Departed  {}  {} (stayed {})",
+                    local(last_seen),
+                    mac,
+                    name(&mac, registry),
+                    stay(last_seen - first_seen)
+                );
+            }
+        }
+    }
+}
+
+pub fn who_is_home(history: &PresenceHistory, registry: &DeviceRegistry) {
+    let (present, last_departure) = history
+        .present()
+        .and_then(|present| Ok((present, history.last_departure()?)))
+        .unwrap_or_else(|err| {
+            error!("Failed to read history: {}", err);
+            std::process::exit(1);
+        });
+
+    if present.is_empty() {
+        println!("Nobody is home");
+        if let Some(PresenceEvent::Departed { mac, last_seen, .. }) = last_departure {
+            println!(
+                "Last to leave: {} ({}) at {}",
+                name(&mac, registry),
+                mac,
+                local(last_seen)
+            );
+        }
+        return;
+    }
+
+    for event in present {
+        if let PresenceEvent::Arrived { mac, first_seen } = event {
+            println!(
+                "{} ({}), home since {}",
+                name(&mac, registry),
+                mac,
+                local(first_seen)
+            );
+        }
+    }
+}
+
+fn name(mac: &MacAddress, registry: &DeviceRegistry) -> String {
+    match registry.get(mac) {
+        Some(device) => device.name.clone(),
+        None => describe(mac),
+    }
+}
+
+fn local(time: DateTime<Utc>) -> String {
+    time.with_timezone(&Local)
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
+
+fn stay(duration: chrono::Duration) -> String {
+    let minutes = duration.num_minutes();
+    match (minutes / (24 * 60), minutes / 60 % 24, minutes % 60) {
+        (0, 0, minutes) => format!("{}m", minutes),
+        (0, hours, minutes) => format!("{}h {}m", hours, minutes),
+        (days, hours, _) => format!("{}d {}h", days, hours),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_age() {
+        assert_eq!(parse_age("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_age("12h"), Ok(Duration::from_secs(12 * 60 * 60)));
+        assert_eq!(parse_age("7d"), Ok(Duration::from_secs(7 * 24 * 60 * 60)));
+        assert_eq!(parse_age("7"), parse_age("7d"));
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("7y").is_err());
+        assert!(parse_age("99999999999999999w").is_err());
+    }
+
+    #[test]
+    fn test_stay() {
+        assert_eq!(stay(chrono::Duration::minutes(42)), "42m");
+        assert_eq!(stay(chrono::Duration::minutes(135)), "2h 15m");
+        assert_eq!(stay(chrono::Duration::hours(50)), "2d 2h");
+    }
+}
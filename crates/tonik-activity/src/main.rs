@@ -1,140 +1,396 @@
-use std::collections::HashMap;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
 use clap::Parser;
-use tonik::{IpNeighborStatusV4, Response, SessionStatus, TeltonikaClient};
-use tracing::{debug, error, info, warn};
+use data_usage::DataUsageWarnings;
+use devices::{DeviceRegistry, UnknownDevicePolicy};
+use event::{ActivityEvent, ActivityKind, DataUsageEvent};
+use futures::{StreamExt, stream};
+use serde::Serialize;
+use sinks::{Sink, SinkError};
+use tonik::{
+    Certificate, MacAddress, Response, Scheme, TeltonikaClient,
+    presence::{PresenceEvent, PresenceTracker, PresenceWatcher, history::PresenceHistory},
+};
+use tracing::{error, info, warn};
+
+mod data_usage;
+mod devices;
+mod event;
+mod history;
+mod sinks;
 
 /// Tonic Activity Water
 ///
-/// Emits events as devices connect/disconnect from the network
+/// Emits events as devices connect/disconnect from the network, and as SIMs use
+/// up their data limits
 #[derive(Debug, Clone, Parser)]
+#[clap(subcommand_negates_reqs = true)]
 struct Args {
+    #[clap(subcommand)]
+    command: Option<Command>,
+
     #[clap(long, default_value = "127.0.0.1")]
     host: String,
 
     #[clap(long, default_value = "admin")]
     username: String,
 
+    #[clap(long, required = true)]
+    password: Option<String>,
+
+    /// API port, if not the scheme's default
+    #[clap(long)]
+    port: Option<u16>,
+
+    /// Connect over plain HTTP instead of HTTPS
+    #[clap(long)]
+    http: bool,
+
+    /// PEM certificate to trust instead of accepting any certificate
+    #[clap(long, value_name = "PATH")]
+    cert: Option<PathBuf>,
+
+    /// TOML or YAML file of known devices, reloaded when it changes
+    #[clap(long, global = true)]
+    devices: Option<PathBuf>,
+
+    /// SQLite database that arrivals and departures are recorded in
+    #[clap(long, value_name = "PATH", global = true)]
+    history: Option<PathBuf>,
+
+    /// How to report devices that aren't in the devices file [default: warn]
+    #[clap(long, value_enum)]
+    unknown_device: Option<UnknownDevicePolicy>,
+
+    /// Write events to stdout as newline-delimited JSON, moving logs to stderr
     #[clap(long)]
-    password: String,
+    ndjson: bool,
+
+    /// Append events as newline-delimited JSON to a file
+    #[clap(long, value_name = "PATH")]
+    output_file: Option<PathBuf>,
+
+    /// Rotate the output file once it reaches this many bytes
+    #[clap(long, value_name = "BYTES", default_value_t = 10 * 1024 * 1024)]
+    output_file_max_size: u64,
+
+    /// Number of rotated output files to keep
+    #[clap(long, value_name = "COUNT", default_value_t = 5)]
+    output_file_keep: usize,
+
+    /// POST each event as JSON to a URL, may be repeated
+    #[clap(long, value_name = "URL")]
+    webhook: Vec<String>,
+
+    /// Publish each event as JSON to an MQTT broker
+    #[clap(long)]
+    mqtt_host: Option<String>,
+
+    #[clap(long, default_value_t = 1883)]
+    mqtt_port: u16,
+
+    #[clap(long, default_value = "tonik/activity")]
+    mqtt_topic: String,
+
+    #[clap(long, requires = "mqtt_password")]
+    mqtt_username: Option<String>,
+
+    #[clap(long, requires = "mqtt_username")]
+    mqtt_password: Option<String>,
+
+    /// Client ID to connect to the broker with, which must be unique among its
+    /// clients [default: tonik-activity-<PID>]
+    #[clap(long, value_name = "ID")]
+    mqtt_client_id: Option<String>,
+
+    /// Seconds a device may go unseen before it is reported as removed
+    #[clap(long, value_name = "SECONDS", default_value_t = 180)]
+    departure_grace: u64,
+
+    /// Seconds to wait instead while the device still has a stale neighbour entry or DHCP lease
+    #[clap(long, value_name = "SECONDS", default_value_t = 600)]
+    lingering_grace: u64,
+
+    /// Warn when a SIM passes this percentage of its data limit, may be repeated
+    #[clap(long, value_name = "PERCENT")]
+    data_usage_warning: Vec<u32>,
+
+    /// Seconds between data usage checks
+    #[clap(long, value_name = "SECONDS", default_value_t = 300)]
+    data_usage_interval: u64,
+}
+
+/// How often the devices file is checked for changes between events.
+const DEVICES_RELOAD_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Something to report, from one of the watchers, or time to check the devices
+/// file.
+enum Update {
+    Presence(Result<PresenceEvent, tonik::Error>),
+    DataUsage(DataUsageEvent),
+    ReloadDevices,
+}
+
+#[derive(Debug, Clone, clap::Subcommand)]
+enum Command {
+    /// Show recorded arrivals and departures
+    History {
+        /// Device name from the devices file, or a MAC address
+        #[clap(long)]
+        device: Option<String>,
+
+        /// Only show events from this long ago, e.g. 30m, 12h or 7d
+        #[clap(long, value_parser = history::parse_age)]
+        since: Option<Duration>,
+    },
+
+    /// List the devices that are currently present
+    WhoIsHome,
 }
 
 #[tokio::main]
 async fn main() {
-    tracing_subscriber::fmt::init();
-
     let args = Args::parse();
 
-    let mut known_devices = HashMap::new();
-    known_devices.insert("82:87:EE:2A:86:AE".to_string(), "Ben's iPhone".to_string());
-    known_devices.insert(
-        "5C:50:D9:C3:4D:F7".to_string(),
-        "Jenny's iPhone".to_string(),
-    );
-    known_devices.insert("96:32:70:B5:C7:8B".to_string(), "iPad".to_string());
-    known_devices.insert("84:F3:EB:E0:12:12".to_string(), "Shelly H/T".to_string());
-    known_devices.insert("68:C6:3A:F9:3A:84".to_string(), "Shelly H/T".to_string());
-    known_devices.insert("84:F3:EB:E0:12:88".to_string(), "Shelly H/T".to_string());
-    known_devices.insert("20:97:27:4E:7A:35".to_string(), "TAP200".to_string());
-    known_devices.insert("84:CC:A8:AF:B1:11".to_string(), "Shelly RGBW".to_string());
-    known_devices.insert("36:95:D8:AD:B3:C4".to_string(), "Ben's MacBook".to_string());
-    known_devices.insert(
-        "B8:27:EB:FB:2A:45".to_string(),
-        "Jack / Raspberry Pi".to_string(),
-    );
-    known_devices.insert("EC:62:60:8F:DD:7C".to_string(), "Spring".to_string());
-    known_devices.insert("10:52:1C:45:6D:27".to_string(), "Shelly 1".to_string());
-    known_devices.insert("F0:A7:31:34:F5:19".to_string(), "P110M Monitor".to_string());
-    known_devices.insert(
-        "E6:DC:A6:AF:39:80".to_string(),
-        "Jenny's MacBook Air".to_string(),
-    );
-
-    let mut client = TeltonikaClient::new(args.host);
-
-    client
-        .authenticate(&args.username, &args.password)
+    // Keep stdout clean for the event stream.
+    if args.ndjson {
+        tracing_subscriber::fmt()
+            .with_writer(std::io::stderr)
+            .init();
+    } else {
+        tracing_subscriber::fmt::init();
+    }
+
+    let registry = match &args.devices {
+        Some(path) => DeviceRegistry::load(path).unwrap_or_else(|err| {
+            error!("Failed to load devices from {}: {}", path.display(), err);
+            std::process::exit(1);
+        }),
+        None => DeviceRegistry::default(),
+    };
+
+    match &args.command {
+        Some(Command::History { device, since }) => {
+            history::show(&open_history(&args), &registry, device.as_deref(), *since)
+        }
+        Some(Command::WhoIsHome) => history::who_is_home(&open_history(&args), &registry),
+        None => watch(args, registry).await,
+    }
+}
+
+fn open_history(args: &Args) -> PresenceHistory {
+    let Some(path) = &args.history else {
+        error!("--history <PATH> is required");
+        std::process::exit(1);
+    };
+
+    PresenceHistory::open(path).unwrap_or_else(|err| {
+        error!("Failed to open history {}: {}", path.display(), err);
+        std::process::exit(1);
+    })
+}
+
+fn read_certificate(path: &Path) -> Certificate {
+    let pem = std::fs::read(path).unwrap_or_else(|err| {
+        error!("Failed to read {}: {}", path.display(), err);
+        std::process::exit(1);
+    });
+    Certificate::from_pem(&pem).unwrap_or_else(|err| {
+        error!("Invalid certificate {}: {}", path.display(), err);
+        std::process::exit(1);
+    })
+}
+
+async fn watch(args: Args, mut registry: DeviceRegistry) {
+    info!("Loaded {} known devices", registry.len());
+
+    let mut sinks = sinks(&args).unwrap_or_else(|err| {
+        error!("Failed to set up event output: {}", err);
+        std::process::exit(1);
+    });
+    let history = args.history.as_ref().map(|_| open_history(&args));
+
+    let password = args.password.as_deref().unwrap_or_default();
+    let mut builder = TeltonikaClient::builder(&args.host).credentials(&args.username, password);
+    if let Some(port) = args.port {
+        builder = builder.port(port);
+    }
+    if args.http {
+        builder = builder.scheme(Scheme::Http);
+    }
+    builder = match &args.cert {
+        Some(path) => builder
+            .add_root_certificate(read_certificate(path))
+            .pin_certificates()
+            .danger_accept_invalid_hostnames(true),
+        None => builder.danger_accept_invalid_certs(true),
+    };
+    let client = Arc::new(builder.build().unwrap_or_else(|err| {
+        error!("Failed to build client: {}", err);
+        std::process::exit(1);
+    }));
+
+    if let Err(err) = client
+        .authenticate(&args.username, password)
         .await
-        .expect("Failed to authenticate");
-
-    let mut last_status: Vec<IpNeighborStatusV4> = Vec::new();
-
-    loop {
-        match client.session_status().await {
-            Ok(Response {
-                data: Some(SessionStatus { active }),
-                success: _,
-                errors: _,
-            }) => {
-                if !active {
-                    warn!("Session is not active, authenticating...");
-                    client
-                        .authenticate(&args.username, &args.password)
-                        .await
-                        .expect("Failed to authenticate");
-                }
-            }
-            Ok(Response {
-                data: None,
-                success: _,
-                errors: _,
-            }) => {
-                warn!("Received unexpected response");
+        .and_then(Response::into_result)
+    {
+        error!("Failed to authenticate: {}", err);
+        std::process::exit(1);
+    }
+
+    let mut tracker = PresenceTracker::new()
+        .departure_grace(Duration::from_secs(args.departure_grace))
+        .lingering_grace(Duration::from_secs(args.lingering_grace));
+    if let Some(history) = &history
+        && let Err(err) = history.restore(&mut tracker)
+    {
+        error!("Failed to restore devices from history: {}", err);
+    }
+    let watcher = PresenceWatcher::new(client.clone())
+        .interval(Duration::from_secs(1))
+        .include_existing(true)
+        .tracker(tracker);
+    let changes = watcher.into_stream().map(Update::Presence);
+
+    let warnings = (!args.data_usage_warning.is_empty()).then(|| {
+        DataUsageWarnings::new(args.data_usage_warning.clone())
+            .into_stream(client, Duration::from_secs(args.data_usage_interval))
+    });
+    let warnings = stream::iter(warnings).flatten().map(Update::DataUsage);
+
+    let reloads = stream::unfold((), |()| async {
+        tokio::time::sleep(DEVICES_RELOAD_INTERVAL).await;
+        Some((Update::ReloadDevices, ()))
+    });
+
+    let mut updates = Box::pin(stream::select(stream::select(changes, warnings), reloads));
+    while let Some(update) = updates.next().await {
+        let change = match update {
+            Update::Presence(Ok(change)) => change,
+            Update::Presence(Err(err)) => {
+                error!("Failed to poll devices: {}", err);
+                continue;
             }
-            Err(err) => {
-                error!("Failed to fetch session status: {}", err);
+            Update::DataUsage(event) => {
+                data_usage::report(&event);
+                publish(&mut sinks, &event).await;
                 continue;
             }
-        }
-
-        let response = match client.ip_neighbors_ipv4_status().await {
-            Ok(response) => response,
-            Err(err) => {
-                error!("Failed to fetch IP neighbor status: {}", err);
+            Update::ReloadDevices => {
+                reload_devices(&mut registry);
                 continue;
             }
         };
 
-        if let Some(new_status) = response.data.clone() {
-            debug!("Received new status");
-            let macs_in_new_status: Vec<String> = new_status
-                .iter()
-                .filter_map(|neighbor| neighbor.mac.clone())
-                .collect();
-            let macs_in_last_status: Vec<String> = last_status
-                .iter()
-                .filter_map(|neighbor| neighbor.mac.clone())
-                .collect();
-
-            let macs_added = macs_in_new_status
-                .iter()
-                .filter(|mac| !macs_in_last_status.contains(mac))
-                .collect::<Vec<&String>>();
-
-            let macs_removed = macs_in_last_status
-                .iter()
-                .filter(|mac| !macs_in_new_status.contains(mac))
-                .collect::<Vec<&String>>();
-
-            for mac in macs_added {
-                if let Some(device_name) = known_devices.get(mac) {
-                    info!("Added: {:?} ({})", mac, device_name);
-                } else {
-                    warn!("Added: {:?}", mac);
-                }
-            }
-            for mac in macs_removed {
-                if let Some(device_name) = known_devices.get(mac) {
-                    info!("Removed: {:?} ({})", mac, device_name);
-                } else {
-                    warn!("Removed: {:?}", mac);
-                }
-            }
+        // Also checked here so an event never uses a file that changed since
+        // the last tick.
+        reload_devices(&mut registry);
+        let unknown_device = args
+            .unknown_device
+            .or(registry.unknown_device_policy())
+            .unwrap_or_default();
 
-            last_status = new_status;
-        } else {
-            warn!("No data received");
+        if let Some(history) = &history
+            && let Err(err) = history.record(&change)
+        {
+            error!("Failed to record event in history: {}", err);
         }
-        std::thread::sleep(std::time::Duration::from_millis(1000));
+
+        let event = ActivityEvent::new(&change, &registry);
+        report(&event, unknown_device);
+        publish(&mut sinks, &event).await;
+    }
+}
+
+fn reload_devices(registry: &mut DeviceRegistry) {
+    match registry.reload_if_changed() {
+        Ok(true) => info!("Reloaded {} known devices", registry.len()),
+        Ok(false) => {}
+        Err(err) => error!("Failed to reload devices: {}", err),
+    }
+}
+
+async fn publish(sinks: &mut [Sink], event: &impl Serialize) {
+    for sink in sinks {
+        if let Err(err) = sink.send(event).await {
+            error!("Failed to write event to {}: {}", sink, err);
+        }
+    }
+}
+
+fn sinks(args: &Args) -> Result<Vec<Sink>, SinkError> {
+    let mut sinks = Vec::new();
+
+    if args.ndjson {
+        sinks.push(Sink::Stdout);
+    }
+    if let Some(path) = &args.output_file {
+        sinks.push(Sink::file(
+            path,
+            args.output_file_max_size,
+            args.output_file_keep,
+        )?);
+    }
+    for url in &args.webhook {
+        sinks.push(Sink::webhook(url)?);
+    }
+    if let Some(host) = &args.mqtt_host {
+        let credentials = args
+            .mqtt_username
+            .as_deref()
+            .zip(args.mqtt_password.as_deref());
+        let client_id = args
+            .mqtt_client_id
+            .clone()
+            .unwrap_or_else(|| format!("tonik-activity-{}", std::process::id()));
+        sinks.push(Sink::mqtt(
+            host,
+            args.mqtt_port,
+            &client_id,
+            credentials,
+            &args.mqtt_topic,
+        ));
+    }
+
+    Ok(sinks)
+}
+
+fn report(event: &ActivityEvent, unknown_device: UnknownDevicePolicy) {
+    let action = match event.event {
+        ActivityKind::Arrived => "Added",
+        ActivityKind::Departed => "Removed",
+    };
+    let mac = &event.mac;
+
+    if let Some(name) = &event.name {
+        match &event.owner {
+            Some(owner) => info!("{}: {} ({}, {})", action, mac, name, owner),
+            None => info!("{}: {} ({})", action, mac, name),
+        }
+        return;
+    }
+
+    match unknown_device {
+        UnknownDevicePolicy::Log => info!("{}: {} ({})", action, mac, describe(mac)),
+        UnknownDevicePolicy::Warn => warn!("{}: {} ({})", action, mac, describe(mac)),
+        UnknownDevicePolicy::Alert => {
+            error!("{} unknown device: {} ({})", action, mac, describe(mac))
+        }
+    }
+}
+
+/// Describe an unknown device by its vendor, e.g. `Apple, Inc.` or `unknown vendor (randomized)`.
+fn describe(mac: &MacAddress) -> String {
+    let vendor = mac.vendor().unwrap_or("unknown vendor");
+
+    if mac.is_locally_administered() {
+        format!("{} (randomized)", vendor)
+    } else {
+        vendor.to_string()
     }
 }
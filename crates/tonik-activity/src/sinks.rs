@@ -0,0 +1,228 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use rumqttc::{AsyncClient, MqttOptions, QoS};
+use serde::Serialize;
+use tracing::warn;
+
+/// Where events are written, in addition to the log.
+pub enum Sink {
+    /// Newline-delimited JSON on stdout.
+    Stdout,
+    /// Newline-delimited JSON appended to a file that is rotated by size.
+    File(RotatingFile),
+    /// Each event POSTed as JSON.
+    Webhook {
+        client: reqwest::Client,
+        url: String,
+    },
+    /// Each event published as JSON to an MQTT topic.
+    Mqtt { client: AsyncClient, topic: String },
+}
+
+#[derive(Debug)]
+pub enum SinkError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Http(reqwest::Error),
+    Mqtt(rumqttc::ClientError),
+}
+
+impl Display for SinkError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            SinkError::Io(err) => write!(f, "{}", err),
+            SinkError::Json(err) => write!(f, "{}", err),
+            SinkError::Http(err) => write!(f, "{}", err),
+            SinkError::Mqtt(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl Sink {
+    pub fn file(path: &Path, max_size: u64, keep: usize) -> Result<Self, SinkError> {
+        RotatingFile::open(path, max_size, keep)
+            .map(Sink::File)
+            .map_err(SinkError::Io)
+    }
+
+    pub fn webhook(url: &str) -> Result<Self, SinkError> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .map_err(SinkError::Http)?;
+
+        Ok(Sink::Webhook {
+            client,
+            url: url.to_string(),
+        })
+    }
+
+    /// Connect to an MQTT broker.
+    ///
+    /// The connection is driven by a background task which reconnects after errors.
+    pub fn mqtt(
+        host: &str,
+        port: u16,
+        client_id: &str,
+        credentials: Option<(&str, &str)>,
+        topic: &str,
+    ) -> Self {
+        let mut options = MqttOptions::new(client_id, host, port);
+        options.set_keep_alive(Duration::from_secs(30));
+        if let Some((username, password)) = credentials {
+            options.set_credentials(username, password);
+        }
+
+        let (client, mut eventloop) = AsyncClient::new(options, 16);
+        tokio::spawn(async move {
+            loop {
+                if let Err(err) = eventloop.poll().await {
+                    warn!("MQTT connection error: {}", err);
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }
+            }
+        });
+
+        Sink::Mqtt {
+            client,
+            topic: topic.to_string(),
+        }
+    }
+
+    pub async fn send(&mut self, event: &impl Serialize) -> Result<(), SinkError> {
+        match self {
+            Sink::Stdout => {
+                let line = serde_json::to_string(event).map_err(SinkError::Json)?;
+                let mut stdout = io::stdout().lock();
+                writeln!(stdout, "{}", line)
+                    .and_then(|_| stdout.flush())
+                    .map_err(SinkError::Io)
+            }
+            Sink::File(file) => {
+                let line = serde_json::to_string(event).map_err(SinkError::Json)?;
+                file.write_line(&line).map_err(SinkError::Io)
+            }
+            Sink::Webhook { client, url } => client
+                .post(url.as_str())
+                .json(event)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .map(|_| ())
+                .map_err(SinkError::Http),
+            Sink::Mqtt { client, topic } => {
+                let payload = serde_json::to_vec(event).map_err(SinkError::Json)?;
+                client
+                    .publish(topic.as_str(), QoS::AtLeastOnce, false, payload)
+                    .await
+                    .map_err(SinkError::Mqtt)
+            }
+        }
+    }
+}
+
+impl Display for Sink {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Sink::Stdout => write!(f, "stdout"),
+            Sink::File(file) => write!(f, "{}", file.path.display()),
+            Sink::Webhook { url, .. } => write!(f, "{}", url),
+            Sink::Mqtt { topic, .. } => write!(f, "MQTT topic {}", topic),
+        }
+    }
+}
+
+/// A file that is renamed to `<path>.1`, `<path>.2`, ... once it reaches `max_size` bytes,
+/// keeping at most `keep` old files.
+pub struct RotatingFile {
+    path: PathBuf,
+    max_size: u64,
+    keep: usize,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    pub fn open(path: &Path, max_size: u64, keep: usize) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+
+        Ok(RotatingFile {
+            path: path.to_path_buf(),
+            max_size,
+            keep,
+            file,
+            size,
+        })
+    }
+
+    fn rotated(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        path.into()
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        if self.keep == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            for index in (1..self.keep).rev() {
+                let from = self.rotated(index);
+                if from.exists() {
+                    fs::rename(&from, self.rotated(index + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.rotated(1))?;
+        }
+
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.size = 0;
+
+        Ok(())
+    }
+
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.size > 0 && self.size + len > self.max_size {
+            self.rotate()?;
+        }
+
+        writeln!(self.file, "{}", line)?;
+        self.file.flush()?;
+        self.size += len;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotating_file() {
+        let dir = std::env::temp_dir().join(format!("tonik-activity-sinks-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("events.ndjson");
+
+        let mut file = RotatingFile::open(&path, 10, 2).unwrap();
+        for line in ["one", "two", "three", "four", "five", "six"] {
+            file.write_line(line).unwrap();
+        }
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "six\n");
+        assert_eq!(fs::read_to_string(file.rotated(1)).unwrap(), "four\nfive\n");
+        assert_eq!(fs::read_to_string(file.rotated(2)).unwrap(), "three\n");
+        assert!(!file.rotated(3).exists());
+    }
+}
@@ -0,0 +1,265 @@
+use std::{
+    fs,
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    process::{Child, Command, Stdio},
+    sync::mpsc,
+    time::Duration,
+};
+
+use tonik_mock::{DEFAULT_PASSWORD, MockRouter};
+
+/// Spawn `tonik-activity` against the mock router and return a function that
+/// waits for the next log line containing a needle.
+fn spawn(router: &MockRouter, extra: &[&str]) -> (Child, impl Fn(&str) -> String + use<>) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_tonik-activity"))
+        .args(["--host", &router.host()])
+        .args(["--port", &router.port().to_string()])
+        .args(["--http", "--password", DEFAULT_PASSWORD])
+        .args(["--departure-grace", "0"])
+        .args(extra)
+        .env("NO_COLOR", "1")
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let (tx, rx) = mpsc::channel();
+    let stdout = child.stdout.take().unwrap();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines() {
+            if tx.send(line.unwrap()).is_err() {
+                break;
+            }
+        }
+    });
+
+    let wait_for = move |needle: &str| loop {
+        let line: String = rx
+            .recv_timeout(Duration::from_secs(10))
+            .unwrap_or_else(|_| panic!("Timed out waiting for {needle:?}"));
+        if line.contains(needle) {
+            break line;
+        }
+    };
+
+    (child, wait_for)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_reports_arrivals_and_departures() {
+    let router = MockRouter::start().await.unwrap();
+    let (mut child, wait_for) = spawn(&router, &[]);
+
+    // The fixture neighbours are reported on the first poll.
+    wait_for("82:87:EE:2A:86:AE");
+
+    router.add_neighbor("AA:BB:CC:DD:EE:FF", "192.168.7.200");
+    assert!(wait_for("AA:BB:CC:DD:EE:FF").contains("Added"));
+
+    router.remove_neighbor("AA:BB:CC:DD:EE:FF");
+    assert!(wait_for("AA:BB:CC:DD:EE:FF").contains("Removed"));
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_names_devices_from_registry() {
+    let router = MockRouter::start().await.unwrap();
+    let dir = std::env::temp_dir().join(format!("tonik-activity-test-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("devices.toml");
+    fs::copy(
+        concat!(env!("CARGO_MANIFEST_DIR"), "/devices.example.toml"),
+        &path,
+    )
+    .unwrap();
+
+    let (mut child, wait_for) = spawn(
+        &router,
+        &[
+            "--devices",
+            path.to_str().unwrap(),
+            "--unknown-device",
+            "alert",
+        ],
+    );
+
+    assert!(wait_for("82:87:EE:2A:86:AE").contains("Ben's iPhone"));
+
+    router.add_neighbor("AA:BB:CC:DD:EE:FF", "192.168.7.200");
+    let line = wait_for("AA:BB:CC:DD:EE:FF");
+    assert!(line.contains("ERROR") && line.contains("unknown device"));
+
+    // The file is picked up again once it changes, even with no events to report.
+    let mut contents = fs::read_to_string(&path).unwrap();
+    contents.push_str("\n[[device]]\nname = \"Guest laptop\"\nmacs = [\"AA:BB:CC:DD:EE:FF\"]\n");
+    std::thread::sleep(Duration::from_millis(50));
+    fs::write(&path, contents).unwrap();
+    wait_for("Reloaded");
+
+    router.remove_neighbor("AA:BB:CC:DD:EE:FF");
+    assert!(wait_for("AA:BB:CC:DD:EE:FF").contains("Guest laptop"));
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_writes_ndjson_events() {
+    let router = MockRouter::start().await.unwrap();
+    let (mut child, wait_for) = spawn(&router, &["--ndjson"]);
+
+    router.add_neighbor("AA:BB:CC:DD:EE:FF", "192.168.7.200");
+    let line = wait_for("AA:BB:CC:DD:EE:FF");
+    let event: serde_json::Value = serde_json::from_str(&line).unwrap();
+
+    assert_eq!(event["event"], "arrived");
+    assert_eq!(event["mac"], "AA:BB:CC:DD:EE:FF");
+    assert_eq!(event["known"], false);
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_warns_of_data_usage() {
+    let router = MockRouter::start().await.unwrap();
+    let (mut child, wait_for) = spawn(
+        &router,
+        &[
+            "--ndjson",
+            "--data-usage-warning",
+            "200",
+            "--data-usage-interval",
+            "1",
+        ],
+    );
+
+    // SIM 1 uses 1 GB a day of its 20 GB, so is under 200% until 40 GB more arrive.
+    wait_for("82:87:EE:2A:86:AE");
+    router.add_data_usage(1, 40_000_000_000);
+    let line = wait_for("data_usage_warning");
+    let event: serde_json::Value = serde_json::from_str(&line).unwrap();
+
+    assert_eq!(event["threshold"], 200);
+    assert_eq!(event["sim"], 1);
+    assert_eq!(event["limit"], 20_000_000_000u64);
+    assert_eq!(event["today"], 41_000_000_000u64);
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_posts_events_to_webhook() {
+    let router = MockRouter::start().await.unwrap();
+
+    // A single-threaded HTTP server which forwards request bodies.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/events", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':')
+                    && name.eq_ignore_ascii_case("content-length")
+                {
+                    length = value.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            stream
+                .write_all(b"HTTP/1.1 204 No Content\r\nconnection: close\r\n\r\n")
+                .unwrap();
+            if tx.send(String::from_utf8(body).unwrap()).is_err() {
+                break;
+            }
+        }
+    });
+
+    let (mut child, _) = spawn(&router, &["--webhook", &url]);
+
+    router.add_neighbor("AA:BB:CC:DD:EE:FF", "192.168.7.200");
+    let event = loop {
+        let body = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        let event: serde_json::Value = serde_json::from_str(&body).unwrap();
+        if event["mac"] == "AA:BB:CC:DD:EE:FF" {
+            break event;
+        }
+    };
+    assert_eq!(event["event"], "arrived");
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_records_history() {
+    let router = MockRouter::start().await.unwrap();
+    let dir = std::env::temp_dir().join(format!("tonik-activity-history-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let history = dir.join("history.db");
+    let history = history.to_str().unwrap();
+    let devices = concat!(env!("CARGO_MANIFEST_DIR"), "/devices.example.toml");
+
+    let (mut child, wait_for) = spawn(&router, &["--history", history]);
+    wait_for("82:87:EE:2A:86:AE");
+    router.add_neighbor("AA:BB:CC:DD:EE:FF", "192.168.7.200");
+    wait_for("AA:BB:CC:DD:EE:FF");
+    router.remove_neighbor("AA:BB:CC:DD:EE:FF");
+    wait_for("AA:BB:CC:DD:EE:FF");
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    let query = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_tonik-activity"))
+            .args(["--history", history, "--devices", devices])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let events = query(&["history", "--device", "aa:bb:cc:dd:ee:ff", "--since", "1h"]);
+    let lines: Vec<&str> = events.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].contains("Arrived"));
+    assert!(lines[1].contains("Departed"));
+
+    let events = query(&["history", "--device", "Ben's iPhone"]);
+    assert_eq!(events.lines().count(), 1);
+
+    let home = query(&["who-is-home"]);
+    assert!(home.contains("Ben's iPhone (82:87:EE:2A:86:AE), home since"));
+    assert!(!home.contains("AA:BB:CC:DD:EE:FF"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_exits_when_authentication_fails() {
+    let router = MockRouter::start().await.unwrap();
+    router.set_credentials("admin", "something-else");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_tonik-activity"))
+        .args(["--host", &router.host()])
+        .args(["--port", &router.port().to_string()])
+        .args(["--http", "--password", DEFAULT_PASSWORD])
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Failed to authenticate"), "{stdout}");
+}
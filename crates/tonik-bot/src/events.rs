@@ -1,7 +1,9 @@
-use tonik::SmsMessage;
+use chrono::{DateTime, Utc};
+use tonik::{MacAddress, SmsMessage};
 
 pub enum Event {
     MessageReceived(SmsMessage),
-    DeviceArrived(String),
-    DeviceLeft(String),
+    DeviceArrived(MacAddress),
+    /// A device left, having last been seen at the given time.
+    DeviceLeft(MacAddress, DateTime<Utc>),
 }
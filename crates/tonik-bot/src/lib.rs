@@ -1,7 +1,5 @@
-use std::collections::HashMap;
-
-pub mod providers;
 pub mod events;
+pub mod providers;
 
 // pub struct Bot {
 //     pub users: HashMap<String, user::User>,
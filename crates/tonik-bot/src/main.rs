@@ -4,23 +4,37 @@ use rs_openai::{
     OpenAI,
     interfaces::chat::{ChatCompletionMessageRequestBuilder, CreateChatRequestBuilder, Role},
 };
-use std::{collections::HashMap, sync::Arc};
-use tonik;
-use tonik_bot::{events::Event, providers::device::DeviceProvider};
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
+use tonik::{MacAddress, presence::history::PresenceHistory, profile::Config};
+use tonik_bot::{
+    events::Event,
+    providers::{device::DeviceProvider, sms::SmsProvider},
+};
 
 #[derive(Debug, Parser)]
 struct App {
     #[clap(long)]
     openai_key: String,
 
-    #[clap(long, default_value = "192.168.7.1")]
-    host: String,
+    /// Profile from ~/.config/tonik/config.toml to connect with
+    #[clap(long, env = "TONIK_PROFILE")]
+    profile: Option<String>,
+
+    /// Router host [default: 192.168.7.1]
+    #[clap(long, env = "TELTONIKA_HOST")]
+    host: Option<String>,
 
-    #[clap(long, default_value = "admin")]
-    username: String,
+    /// Router username [default: admin]
+    #[clap(long, env = "TELTONIKA_USERNAME")]
+    username: Option<String>,
 
-    #[clap(long, default_value = "password")]
-    password: String,
+    /// Router password, unless the profile provides one
+    #[clap(long, env = "TELTONIKA_PASSWORD")]
+    password: Option<String>,
+
+    /// SQLite database that arrivals and departures are recorded in
+    #[clap(long, value_name = "PATH")]
+    history: Option<PathBuf>,
 }
 
 #[tokio::main]
@@ -34,34 +48,92 @@ async fn main() {
         org_id: None,
     });
 
-    let tonik = Arc::new(tonik::TeltonikaClient::new(app.host.clone()));
+    let profile = Config::load_default()
+        .and_then(|config| Ok(config.profile(app.profile.as_deref())?.cloned()))
+        .unwrap_or_else(|err| {
+            eprintln!("Failed to load profile: {}", err);
+            std::process::exit(1);
+        })
+        .unwrap_or_default();
+    let host = app
+        .host
+        .clone()
+        .or(profile.host.clone())
+        .unwrap_or_else(|| "192.168.7.1".to_string());
+    let username = app
+        .username
+        .clone()
+        .or(profile.username.clone())
+        .unwrap_or_else(|| "admin".to_string());
+    let password = match app
+        .password
+        .clone()
+        .map(Ok)
+        .or_else(|| profile.password().transpose())
+    {
+        Some(Ok(password)) => password,
+        Some(Err(err)) => {
+            eprintln!("Failed to get password: {}", err);
+            std::process::exit(1);
+        }
+        None => {
+            eprintln!("No password, use --password or --profile");
+            std::process::exit(1);
+        }
+    };
+
+    let tonik = Arc::new(tonik::TeltonikaClient::new(host).with_credentials(&username, &password));
 
     tonik
-        .authenticate(&app.username, &app.password)
+        .authenticate(&username, &password)
         .await
+        .and_then(tonik::Response::into_result)
         .unwrap();
 
     let (tx, mut rx) = tokio::sync::mpsc::channel::<Event>(100);
 
+    let open_history = |path: &PathBuf| {
+        PresenceHistory::open(path).unwrap_or_else(|err| {
+            eprintln!("Failed to open history {}: {}", path.display(), err);
+            std::process::exit(1);
+        })
+    };
+
     let mut device_watcher = DeviceProvider::new(tonik.clone());
+    if let Some(path) = &app.history {
+        device_watcher = device_watcher.with_history(open_history(path));
+    }
+
+    let mut device_tx = tx.clone();
+    tokio::spawn(async move { device_watcher.hoist(&mut device_tx).await });
 
-    tokio::spawn(async move { device_watcher.hoist(&mut tx.clone()).await });
+    let sms_watcher = SmsProvider::new(tonik.clone());
+    let mut sms_tx = tx;
+    tokio::spawn(async move { sms_watcher.hoist(&mut sms_tx).await });
 
     let mut user_db = UserDb::new();
     user_db.add_user(User {
         name: "Ben".to_string(),
         phone_number: "+447799555832".to_string(),
-        mac_address: "82:87:EE:2A:86:AE".to_string(),
+        mac_address: "82:87:EE:2A:86:AE".parse().unwrap(),
         last_seen: None,
     });
 
+    if let Some(path) = &app.history {
+        let history = open_history(path);
+        for user in user_db.users.values_mut() {
+            user.last_seen = history.last_seen(&user.mac_address).unwrap_or_default();
+        }
+    }
+
     while let Some(event) = rx.recv().await {
         match event {
             Event::DeviceArrived(mac_addr) => {
-                let Some(user) = user_db.get_user_by_mac_address(&mac_addr) else {
+                let Some(user) = user_db.get_user_by_mac_address_mut(&mac_addr) else {
                     println!("Unknown device connected: {}", mac_addr);
                     continue;
                 };
+                user.last_seen = Some(Utc::now());
 
                 println!("Device connected: {} : {}", mac_addr, user.name);
 
@@ -77,13 +149,15 @@ async fn main() {
                 tonik
                     .send_sms_message(&user.phone_number, &message)
                     .await
+                    .and_then(tonik::Response::into_result)
                     .unwrap();
             }
-            Event::DeviceLeft(mac_addr) => {
-                let Some(user) = user_db.get_user_by_mac_address(&mac_addr) else {
+            Event::DeviceLeft(mac_addr, last_seen) => {
+                let Some(user) = user_db.get_user_by_mac_address_mut(&mac_addr) else {
                     println!("Unknown device disconnected: {}", mac_addr);
                     continue;
                 };
+                user.last_seen = Some(last_seen);
 
                 println!("Device disconnected: {} : {}", mac_addr, user.name);
 
@@ -99,10 +173,14 @@ async fn main() {
                 tonik
                     .send_sms_message(&user.phone_number, &message)
                     .await
+                    .and_then(tonik::Response::into_result)
                     .unwrap();
             }
             Event::MessageReceived(sms_message) => {
-                println!("New SMS Message {:?}", sms_message)
+                match user_db.get_user_by_phone_number(&sms_message.sender) {
+                    Some(user) => println!("New SMS Message from {}: {:?}", user.name, sms_message),
+                    None => println!("New SMS Message {:?}", sms_message),
+                }
             }
         }
     }
@@ -135,7 +213,7 @@ async fn generate_a_message(openai: &OpenAI, prompt: &str) -> String {
 pub struct User {
     pub name: String,
     pub phone_number: String,
-    pub mac_address: String,
+    pub mac_address: MacAddress,
     pub last_seen: Option<DateTime<Utc>>,
 }
 
@@ -158,9 +236,9 @@ impl UserDb {
         self.users.get(phone_number)
     }
 
-    pub fn get_user_by_mac_address(&self, mac_address: &str) -> Option<&User> {
+    pub fn get_user_by_mac_address_mut(&mut self, mac_address: &MacAddress) -> Option<&mut User> {
         self.users
-            .values()
-            .find(|user| user.mac_address == mac_address)
+            .values_mut()
+            .find(|user| user.mac_address == *mac_address)
     }
 }
@@ -1,83 +1,86 @@
 use std::{sync::Arc, time::Duration};
-use tracing::{debug, info, warn};
+
+use futures::StreamExt;
+use tonik::presence::{PresenceEvent, PresenceTracker, PresenceWatcher, history::PresenceHistory};
+use tracing::{error, info};
 
 use crate::events::Event;
 
 pub struct DeviceProvider {
-    current_devices: Vec<String>,
-    tonik: Arc<tonik::TeltonikaClient>,
+    watcher: PresenceWatcher,
+    tracker: PresenceTracker,
+    history: Option<PresenceHistory>,
 }
 
 impl DeviceProvider {
     pub fn new(tonik: Arc<tonik::TeltonikaClient>) -> Self {
         DeviceProvider {
-            current_devices: Vec::new(),
-            tonik,
+            watcher: PresenceWatcher::new(tonik),
+            tracker: PresenceTracker::new(),
+            history: None,
         }
     }
 
-    pub async fn list_devices(&self) -> Vec<String> {
-        let response = match self.tonik.ip_neighbors_ipv4_status().await {
-            Ok(res) => res,
-            Err(_) => {
-                warn!("Failed to fetch device list");
-                return Vec::new();
-            }
-        };
-
-        let devices = match response.data {
-            Some(devices) => devices,
-            None => {
-                warn!("Response is empty");
-                return Vec::new();
-            }
-        };
-
-        devices
-            .into_iter()
-            .filter_map(|device| device.mac)
-            .collect()
+    /// How often the device list is polled. Defaults to five seconds.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.watcher = self.watcher.interval(interval);
+        self
     }
 
-    pub async fn hoist(&mut self, tx: &mut tokio::sync::mpsc::Sender<Event>) {
-        info!("Hoisting device watch");
-        self.current_devices = self.list_devices().await;
-        info!(
-            "Initial device list fetched, found {} devices",
-            self.current_devices.len()
-        );
-
-        loop {
-            tokio::time::sleep(Duration::from_secs(5)).await;
-
-            let new_device_list = self.list_devices().await;
-            debug!(
-                "New device list fetched, found {} devices",
-                new_device_list.len()
-            );
+    /// How long a device may go unseen before it has left. Defaults to three minutes.
+    ///
+    /// See [`PresenceTracker::departure_grace`].
+    pub fn with_departure_grace(mut self, grace: Duration) -> Self {
+        self.tracker = self.tracker.departure_grace(grace);
+        self
+    }
 
-            let new_devices: Vec<String> = new_device_list
-                .iter()
-                .filter(|device| !self.current_devices.contains(device))
-                .cloned()
-                .collect();
+    /// How long a device with a stale neighbour entry or DHCP lease is kept. Defaults to
+    /// ten minutes.
+    pub fn with_lingering_grace(mut self, grace: Duration) -> Self {
+        self.tracker = self.tracker.lingering_grace(grace);
+        self
+    }
 
-            let departed_devices: Vec<String> = self
-                .current_devices
-                .iter()
-                .filter(|device| !new_device_list.contains(device))
-                .cloned()
-                .collect();
+    /// Record arrivals and departures, and carry on from the recorded state after a
+    /// restart instead of treating everyone present as newly arrived.
+    pub fn with_history(mut self, history: PresenceHistory) -> Self {
+        self.history = Some(history);
+        self
+    }
 
-            for device in departed_devices {
-                tx.send(Event::DeviceLeft(device.clone())).await.unwrap();
+    pub async fn hoist(mut self, tx: &mut tokio::sync::mpsc::Sender<Event>) {
+        info!("Hoisting device watch");
+        let mut watcher = self.watcher;
+        if let Some(history) = &self.history {
+            match history.restore(&mut self.tracker) {
+                Ok(()) => watcher = watcher.include_existing(true),
+                Err(err) => error!("Failed to restore devices from history: {}", err),
             }
+        }
+        let mut changes = Box::pin(watcher.tracker(self.tracker).into_stream());
 
-            for device in new_devices {
-                tx.send(Event::DeviceArrived(device.clone())).await.unwrap();
+        while let Some(change) = changes.next().await {
+            let change = match change {
+                Ok(change) => change,
+                Err(err) => {
+                    error!("Failed to poll devices: {}", err);
+                    continue;
+                }
+            };
+            if let Some(history) = &self.history
+                && let Err(err) = history.record(&change)
+            {
+                error!("Failed to record device event: {}", err);
             }
 
-            self.current_devices = new_device_list;
+            let event = match change {
+                PresenceEvent::Arrived { mac, .. } => Event::DeviceArrived(mac),
+                PresenceEvent::Departed { mac, last_seen, .. } => Event::DeviceLeft(mac, last_seen),
+            };
+            if tx.send(event).await.is_err() {
+                break;
+            }
         }
     }
 }
@@ -1,49 +1,49 @@
+use std::{sync::Arc, time::Duration};
 
-use std::collections::HashMap;
+use futures::StreamExt;
+use tonik::sms::SmsWatcher;
+use tracing::{error, info};
 
-use tonik::SmsMessage;
+use crate::events::Event;
 
-pub type PhoneNumber = String;
-
-pub struct Sms {
-    history: HashMap<PhoneNumber, Vec<String>>,
-    client: tonik::TeltonikaClient,
+pub struct SmsProvider {
+    watcher: SmsWatcher,
 }
 
-impl Sms {
-    pub fn new(client: tonik::TeltonikaClient) -> Self {
-        Sms {
-            history: HashMap::new(),
-            client,
+impl SmsProvider {
+    pub fn new(tonik: Arc<tonik::TeltonikaClient>) -> Self {
+        SmsProvider {
+            watcher: SmsWatcher::new(tonik),
         }
     }
 
-    pub async fn send_message(
-        &mut self,
-        phone_number: &PhoneNumber,
-        message: &str,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        self.client.send_sms_message(phone_number, message).await?;
-        self.history
-            .entry(phone_number.clone())
-            .or_default()
-            .push(message.to_string());
-        Ok(())
+    /// How often messages are polled. Defaults to five seconds.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.watcher = self.watcher.interval(interval);
+        self
+    }
+
+    /// Delete messages from the router once they have been received.
+    pub fn with_delete_after_read(mut self, delete: bool) -> Self {
+        self.watcher = self.watcher.delete_after_read(delete);
+        self
     }
 
-    pub async fn new_messages(&mut self) -> Result<Vec<SmsMessage>, Box<dyn std::error::Error>> {
-        let new_messages = self
-            .client
-            .list_sms_messages()
-            .await?
-            .data
-            .unwrap_or_default();
-        for message in new_messages {
-            self.history
-                .entry(message.sender)
-                .or_default()
-                .push(message);
+    pub async fn hoist(self, tx: &mut tokio::sync::mpsc::Sender<Event>) {
+        info!("Hoisting SMS watch");
+        let mut messages = Box::pin(self.watcher.into_stream());
+
+        while let Some(message) = messages.next().await {
+            let message = match message {
+                Ok(message) => message,
+                Err(err) => {
+                    error!("Failed to poll SMS messages: {}", err);
+                    continue;
+                }
+            };
+            if tx.send(Event::MessageReceived(message)).await.is_err() {
+                break;
+            }
         }
-        Ok(())
     }
 }
@@ -0,0 +1,47 @@
+use std::{sync::Arc, time::Duration};
+
+use chrono::Utc;
+use tonik::{MacAddress, Scheme, TeltonikaClient};
+use tonik_bot::{events::Event, providers::device::DeviceProvider};
+use tonik_mock::{DEFAULT_PASSWORD, DEFAULT_USERNAME, MockRouter};
+
+#[tokio::test]
+async fn test_device_provider_emits_arrivals_and_departures() {
+    let router = MockRouter::start().await.unwrap();
+    let client = TeltonikaClient::builder(router.host())
+        .scheme(Scheme::Http)
+        .port(router.port())
+        .credentials(DEFAULT_USERNAME, DEFAULT_PASSWORD)
+        .build()
+        .unwrap();
+
+    let (mut tx, mut rx) = tokio::sync::mpsc::channel(10);
+    let provider = DeviceProvider::new(Arc::new(client))
+        .with_interval(Duration::from_millis(50))
+        .with_departure_grace(Duration::ZERO);
+    let hoist = tokio::spawn(async move { provider.hoist(&mut tx).await });
+
+    // Give the provider time to fetch the initial device list.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let mac: MacAddress = "AA:BB:CC:DD:EE:FF".parse().unwrap();
+
+    router.add_neighbor("aa:bb:cc:dd:ee:ff", "192.168.7.200");
+    let event = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(event, Event::DeviceArrived(arrived) if arrived == mac));
+
+    let removed = Utc::now();
+    router.remove_neighbor("AA:BB:CC:DD:EE:FF");
+    let event = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(
+        matches!(event, Event::DeviceLeft(left, last_seen) if left == mac && last_seen <= removed)
+    );
+
+    hoist.abort();
+}
@@ -0,0 +1,34 @@
+use std::{sync::Arc, time::Duration};
+
+use tonik::{Scheme, TeltonikaClient};
+use tonik_bot::{events::Event, providers::sms::SmsProvider};
+use tonik_mock::{DEFAULT_PASSWORD, DEFAULT_USERNAME, MockRouter};
+
+#[tokio::test]
+async fn test_sms_provider_emits_received_messages() {
+    let router = MockRouter::start().await.unwrap();
+    let client = TeltonikaClient::builder(router.host())
+        .scheme(Scheme::Http)
+        .port(router.port())
+        .credentials(DEFAULT_USERNAME, DEFAULT_PASSWORD)
+        .build()
+        .unwrap();
+
+    let (mut tx, mut rx) = tokio::sync::mpsc::channel(10);
+    let provider = SmsProvider::new(Arc::new(client)).with_interval(Duration::from_millis(50));
+    let hoist = tokio::spawn(async move { provider.hoist(&mut tx).await });
+
+    // Give the provider time to fetch the messages already on the router.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    router.receive_sms("+447700900456", "Are you aboard?");
+    let event = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(
+        matches!(event, Event::MessageReceived(message) if message.message == "Are you aboard?")
+    );
+
+    hoist.abort();
+}
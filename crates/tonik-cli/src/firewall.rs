@@ -0,0 +1,232 @@
+use std::str::FromStr;
+
+use tonik::{PortForward, Response, TeltonikaClient, TrafficRule};
+
+use crate::{Failure, Output};
+
+/// Where a port forward goes: a host and, optionally, a port on it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Destination {
+    pub ip: String,
+    pub port: Option<String>,
+}
+
+impl FromStr for Destination {
+    type Err = String;
+
+    fn from_str(destination: &str) -> Result<Self, Self::Err> {
+        let (ip, port) = match destination.split_once(':') {
+            Some((ip, port)) => (ip, Some(port)),
+            None => (destination, None),
+        };
+
+        if ip.parse::<std::net::Ipv4Addr>().is_err() {
+            return Err(format!("invalid IPv4 address {:?}", ip));
+        }
+
+        Ok(Destination {
+            ip: ip.to_string(),
+            port: port.map(parse_port).transpose()?,
+        })
+    }
+}
+
+/// Check a port or range of ports, e.g. `8080` or `8000-8010`.
+pub fn parse_port(port: &str) -> Result<String, String> {
+    let valid = port
+        .split('-')
+        .all(|part| part.parse::<u16>().is_ok_and(|port| port > 0));
+    if port.split('-').count() > 2 || !valid {
+        return Err(format!(
+            "invalid port {:?}, expected e.g. 8080 or 8000-8010",
+            port
+        ));
+    }
+    Ok(port.to_string())
+}
+
+/// The item whose ID or name is `wanted`, failing if none or several are named so.
+fn find<'a, T>(
+    items: &'a [T],
+    wanted: &str,
+    kind: &str,
+    id: impl Fn(&T) -> &str,
+    name: impl Fn(&T) -> Option<&str>,
+) -> Result<&'a T, Failure> {
+    if let Some(item) = items.iter().find(|item| id(item) == wanted) {
+        return Ok(item);
+    }
+
+    let named: Vec<&T> = items
+        .iter()
+        .filter(|item| name(item) == Some(wanted))
+        .collect();
+    match named[..] {
+        [item] => Ok(item),
+        [] => Err(Failure(format!(
+            "no {} with the ID or name {:?}",
+            kind, wanted
+        ))),
+        _ => Err(Failure(format!(
+            "{} {}s are named {:?}, use the ID",
+            named.len(),
+            kind,
+            wanted
+        ))),
+    }
+}
+
+pub async fn port_forwards(client: &TeltonikaClient) -> Result<Output, Failure> {
+    let forwards = client
+        .list_port_forwards()
+        .await
+        .and_then(Response::into_result)?;
+
+    Ok(Output::many(&forwards, String::new()))
+}
+
+pub async fn add_port_forward(
+    client: &TeltonikaClient,
+    forward: &PortForward,
+) -> Result<Output, Failure> {
+    let created = client
+        .create_port_forward(forward)
+        .await
+        .and_then(Response::into_result)?;
+
+    Ok(Output::one(&created, format!("Added {}", created)))
+}
+
+async fn find_port_forward(client: &TeltonikaClient, wanted: &str) -> Result<PortForward, Failure> {
+    let forwards = client
+        .list_port_forwards()
+        .await
+        .and_then(Response::into_result)?;
+    let forward = find(
+        &forwards,
+        wanted,
+        "port forward",
+        |forward| &forward.id,
+        |forward| forward.name.as_deref(),
+    )?;
+
+    Ok(forward.clone())
+}
+
+pub async fn remove_port_forward(
+    client: &TeltonikaClient,
+    wanted: &str,
+) -> Result<Output, Failure> {
+    let forward = find_port_forward(client, wanted).await?;
+    client
+        .delete_port_forward(&forward.id)
+        .await
+        .and_then(Response::into_result)?;
+
+    Ok(Output::one(&forward, format!("Removed {}", forward)))
+}
+
+pub async fn enable_port_forward(
+    client: &TeltonikaClient,
+    wanted: &str,
+    enabled: bool,
+) -> Result<Output, Failure> {
+    let forward = PortForward {
+        enabled,
+        ..find_port_forward(client, wanted).await?
+    };
+    let updated = client
+        .update_port_forward(&forward.id, &forward)
+        .await
+        .and_then(Response::into_result)?;
+
+    Ok(Output::one(&updated, updated.to_string()))
+}
+
+pub async fn traffic_rules(client: &TeltonikaClient) -> Result<Output, Failure> {
+    let rules = client
+        .list_traffic_rules()
+        .await
+        .and_then(Response::into_result)?;
+
+    Ok(Output::many(&rules, String::new()))
+}
+
+async fn find_traffic_rule(client: &TeltonikaClient, wanted: &str) -> Result<TrafficRule, Failure> {
+    let rules = client
+        .list_traffic_rules()
+        .await
+        .and_then(Response::into_result)?;
+    let rule = find(
+        &rules,
+        wanted,
+        "traffic rule",
+        |rule| &rule.id,
+        |rule| rule.name.as_deref(),
+    )?;
+
+    Ok(rule.clone())
+}
+
+pub async fn remove_traffic_rule(
+    client: &TeltonikaClient,
+    wanted: &str,
+) -> Result<Output, Failure> {
+    let rule = find_traffic_rule(client, wanted).await?;
+    client
+        .delete_traffic_rule(&rule.id)
+        .await
+        .and_then(Response::into_result)?;
+
+    Ok(Output::one(&rule, format!("Removed {}", rule)))
+}
+
+pub async fn enable_traffic_rule(
+    client: &TeltonikaClient,
+    wanted: &str,
+    enabled: bool,
+) -> Result<Output, Failure> {
+    let rule = TrafficRule {
+        enabled,
+        ..find_traffic_rule(client, wanted).await?
+    };
+    let updated = client
+        .update_traffic_rule(&rule.id, &rule)
+        .await
+        .and_then(Response::into_result)?;
+
+    Ok(Output::one(&updated, updated.to_string()))
+}
+
+pub async fn zones(client: &TeltonikaClient) -> Result<Output, Failure> {
+    let zones = client.list_zones().await.and_then(Response::into_result)?;
+
+    Ok(Output::many(&zones, String::new()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_destination() {
+        assert_eq!(
+            "192.168.7.20:80".parse(),
+            Ok(Destination {
+                ip: "192.168.7.20".to_string(),
+                port: Some("80".to_string()),
+            })
+        );
+        assert_eq!(
+            "192.168.7.20".parse(),
+            Ok(Destination {
+                ip: "192.168.7.20".to_string(),
+                port: None,
+            })
+        );
+        assert!("192.168.7.20:8000-8010".parse::<Destination>().is_ok());
+        assert!("camera:80".parse::<Destination>().is_err());
+        assert!("192.168.7.20:http".parse::<Destination>().is_err());
+        assert!("192.168.7.20:0".parse::<Destination>().is_err());
+    }
+}
@@ -0,0 +1,185 @@
+use std::{
+    io::{BufRead, Write},
+    path::Path,
+    time::Duration,
+};
+
+use serde_json::json;
+use tonik::{FirmwareProcess, Response, TeltonikaClient};
+
+use crate::{Failure, Output};
+
+/// A local firmware image to upload before upgrading.
+pub struct Image {
+    pub name: String,
+    pub contents: Vec<u8>,
+}
+
+impl Image {
+    pub fn read(path: &Path) -> Result<Self, Failure> {
+        let contents = std::fs::read(path)
+            .map_err(|e| Failure(format!("failed to read {}: {}", path.display(), e)))?;
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "firmware.bin".to_string());
+
+        Ok(Image { name, contents })
+    }
+}
+
+/// Compare the installed firmware with the latest version on the FOTA server.
+pub async fn check(client: &TeltonikaClient) -> Result<Output, Failure> {
+    let current = client
+        .firmware_device_status()
+        .await
+        .and_then(Response::into_result)?;
+    let update = client
+        .firmware_device_updates_status()
+        .await
+        .and_then(Response::into_result)?
+        .device;
+
+    let status = json!({
+        "current": current.version,
+        "available": update.version(),
+        "size": update.size,
+        "update_available": update.differs_from(&current.version),
+    });
+
+    let mut text = format!("Current version:   {}\n", current.version);
+    match update.version() {
+        Some(version) if update.differs_from(&current.version) => match update.size {
+            Some(size) => text += &format!("Available version: {} ({})", version, megabytes(size)),
+            None => text += &format!("Available version: {}", version),
+        },
+        _ => text += "Firmware is up to date",
+    }
+
+    Ok(Output::json(status, text))
+}
+
+/// How long a download may take before giving up on it.
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Idle polls in a row after which the router is taken not to be downloading.
+const MAX_IDLE_POLLS: u32 = 3;
+
+/// Download the latest firmware from the FOTA server, optionally printing progress
+/// until it finishes.
+///
+/// Fails if the download hasn't finished within [`DOWNLOAD_TIMEOUT`], or if the
+/// router reports that it isn't downloading [`MAX_IDLE_POLLS`] times in a row.
+pub async fn download(client: &TeltonikaClient, progress: bool) -> Result<Output, Failure> {
+    client
+        .firmware_actions_fota_download()
+        .await
+        .and_then(Response::ensure_success)?;
+
+    let deadline = tokio::time::Instant::now() + DOWNLOAD_TIMEOUT;
+    let mut idle_polls = 0;
+    let failure = |message: &str| {
+        if progress {
+            println!();
+        }
+        Err(Failure(message.to_string()))
+    };
+
+    loop {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+
+        let status = client
+            .firmware_device_progress_status()
+            .await
+            .and_then(Response::into_result)?;
+
+        match status.process {
+            FirmwareProcess::Succeeded => {
+                if progress {
+                    println!("\rDownloading firmware: 100%");
+                }
+                return Ok(Output::one(
+                    &status,
+                    "Download complete, install it with `tonik firmware upgrade`".to_string(),
+                ));
+            }
+            FirmwareProcess::Failed => return failure("firmware download failed"),
+            FirmwareProcess::Idle => {
+                idle_polls += 1;
+                if idle_polls >= MAX_IDLE_POLLS {
+                    return failure("the router didn't start downloading the firmware");
+                }
+            }
+            FirmwareProcess::Started => idle_polls = 0,
+        }
+
+        if tokio::time::Instant::now() >= deadline {
+            return failure(&format!(
+                "firmware download didn't finish within {} minutes",
+                DOWNLOAD_TIMEOUT.as_secs() / 60
+            ));
+        }
+        if progress {
+            print!("\rDownloading firmware: {}%", status.percents);
+            std::io::stdout().flush().ok();
+        }
+    }
+}
+
+/// Install downloaded firmware, or upload and install `image`.
+pub async fn upgrade(
+    client: &TeltonikaClient,
+    image: Option<&Image>,
+    keep_settings: bool,
+) -> Result<Output, Failure> {
+    if let Some(image) = image {
+        client
+            .upload_device_firmware(&image.name, image.contents.clone())
+            .await
+            .and_then(Response::ensure_success)?;
+    }
+
+    client
+        .firmware_actions_upgrade(keep_settings)
+        .await
+        .and_then(Response::ensure_success)?;
+
+    Ok(Output::json(
+        json!({ "upgrading": true, "keep_settings": keep_settings }),
+        "Upgrade started, the router will reboot when it has finished".to_string(),
+    ))
+}
+
+/// Ask on the terminal before an upgrade reboots `routers`.
+pub fn confirm_upgrade(routers: &str, image: Option<&Image>, keep_settings: bool) -> bool {
+    let settings = if keep_settings {
+        "Settings will be kept"
+    } else {
+        "Settings will be reset to defaults"
+    };
+    let source = match image {
+        Some(image) => format!(
+            "{} ({})",
+            image.name,
+            megabytes(image.contents.len() as u64)
+        ),
+        None => "the downloaded firmware".to_string(),
+    };
+
+    eprint!(
+        "Install {} on {}? {} and the router will reboot. [y/N] ",
+        source, routers, settings
+    );
+    std::io::stderr().flush().ok();
+
+    let mut answer = String::new();
+    if std::io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+fn megabytes(bytes: u64) -> String {
+    format!("{:.1} MB", bytes as f64 / 1_000_000.0)
+}
@@ -0,0 +1,102 @@
+use futures::{stream, StreamExt};
+use serde_json::json;
+
+use crate::{
+    connect,
+    output::{Format, View},
+    session::SessionCache,
+    Connection, Failure, Output, Task,
+};
+
+/// The outcome of a task on one router of the fleet.
+pub struct RouterOutcome {
+    pub name: String,
+    pub result: Result<Output, Failure>,
+}
+
+/// Run a task on every router, `parallel` at a time, keeping inventory order.
+pub async fn run(
+    routers: Vec<(String, Connection)>,
+    task: &Task<'_>,
+    sessions: &SessionCache,
+    parallel: usize,
+) -> Vec<RouterOutcome> {
+    stream::iter(routers)
+        .map(|(name, connection)| async move {
+            let result = match connect(&connection, sessions).await {
+                Ok(client) => task.execute(&client).await,
+                Err(failure) => Err(failure),
+            };
+            RouterOutcome { name, result }
+        })
+        .buffered(parallel.max(1))
+        .collect()
+        .await
+}
+
+/// Print every router's output, returning whether they all succeeded.
+///
+/// JSON and YAML output is an object keyed by router name holding either `result`
+/// or `error`. Tables, CSV and NDJSON gain a `router` column, and text output
+/// prefixes each line with the router name. Errors go to stderr.
+pub fn print(outcomes: &[RouterOutcome], view: &View) -> bool {
+    match view.format() {
+        Format::Json | Format::Yaml => {
+            let routers: serde_json::Map<String, serde_json::Value> = outcomes
+                .iter()
+                .map(|outcome| {
+                    let value = match &outcome.result {
+                        Ok(output) => json!({ "result": view.value(output) }),
+                        Err(failure) => json!({ "error": failure.to_string() }),
+                    };
+                    (outcome.name.clone(), value)
+                })
+                .collect();
+
+            let routers = serde_json::Value::Object(routers);
+            match view.format() {
+                Format::Yaml => print!("{}", serde_yaml::to_string(&routers).unwrap()),
+                _ => println!("{}", serde_json::to_string_pretty(&routers).unwrap()),
+            }
+        }
+        Format::Text => {
+            let width = outcomes
+                .iter()
+                .map(|outcome| outcome.name.len())
+                .max()
+                .unwrap_or_default();
+
+            for outcome in outcomes {
+                match &outcome.result {
+                    Ok(output) => {
+                        for line in view.render(output).lines() {
+                            println!("{:width$}  {}", outcome.name, line);
+                        }
+                    }
+                    Err(failure) => eprintln!("{:width$}  Error: {}", outcome.name, failure),
+                }
+            }
+        }
+        Format::Table | Format::Csv | Format::Ndjson => {
+            let outputs: Vec<(&str, &Output)> = outcomes
+                .iter()
+                .filter_map(|outcome| {
+                    let output = outcome.result.as_ref().ok()?;
+                    Some((outcome.name.as_str(), output))
+                })
+                .collect();
+
+            let rendered = view.render_fleet(&outputs);
+            if !rendered.is_empty() {
+                println!("{}", rendered);
+            }
+            for outcome in outcomes {
+                if let Err(failure) = &outcome.result {
+                    eprintln!("{}: Error: {}", outcome.name, failure);
+                }
+            }
+        }
+    }
+
+    outcomes.iter().all(|outcome| outcome.result.is_ok())
+}
@@ -0,0 +1,217 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use serde::Deserialize;
+
+/// A router in the inventory.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Router {
+    pub name: String,
+    pub host: String,
+    pub port: Option<u16>,
+    /// Connect over plain HTTP instead of HTTPS.
+    pub http: Option<bool>,
+    pub cert: Option<PathBuf>,
+    pub username: Option<String>,
+    /// Profile from the config file to take credentials and anything else unset from.
+    pub profile: Option<String>,
+    /// Environment variable holding the router's password.
+    pub password_env: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl Router {
+    /// The router's password, read from its `password_env` variable.
+    pub fn password(&self) -> Option<String> {
+        self.password_env
+            .as_ref()
+            .and_then(|name| std::env::var(name).ok())
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct InventoryFile {
+    #[serde(default, rename = "router")]
+    routers: Vec<Router>,
+}
+
+#[derive(Debug)]
+pub enum InventoryError {
+    Io(PathBuf, std::io::Error),
+    Toml(PathBuf, toml::de::Error),
+    DuplicateName(String),
+}
+
+impl Display for InventoryError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            InventoryError::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+            InventoryError::Toml(path, err) => write!(f, "{}: {}", path.display(), err),
+            InventoryError::DuplicateName(name) => {
+                write!(f, "{:?} is listed for more than one router", name)
+            }
+        }
+    }
+}
+
+/// Routers that can be addressed with `--target`.
+///
+/// ```toml
+/// [[router]]
+/// name = "boat-1"
+/// host = "10.8.0.11"
+/// profile = "boats"
+/// tags = ["boats"]
+/// ```
+#[derive(Debug, Default)]
+pub struct Inventory {
+    routers: Vec<Router>,
+}
+
+impl Inventory {
+    /// `$XDG_CONFIG_HOME/tonik/inventory.toml`, or `~/.config/tonik/inventory.toml`.
+    pub fn default_path() -> Option<PathBuf> {
+        tonik::profile::config_dir().map(|dir| dir.join("inventory.toml"))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, InventoryError> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|err| InventoryError::Io(path.to_path_buf(), err))?;
+        contents.parse().map_err(|err| match err {
+            InventoryError::Toml(_, err) => InventoryError::Toml(path.to_path_buf(), err),
+            err => err,
+        })
+    }
+
+    /// Routers matching any of the targets, in inventory order.
+    pub fn select(&self, targets: &[Target]) -> Vec<&Router> {
+        self.routers
+            .iter()
+            .filter(|router| targets.iter().any(|target| target.matches(router)))
+            .collect()
+    }
+}
+
+impl FromStr for Inventory {
+    type Err = InventoryError;
+
+    fn from_str(contents: &str) -> Result<Self, Self::Err> {
+        let file: InventoryFile =
+            toml::from_str(contents).map_err(|err| InventoryError::Toml(PathBuf::new(), err))?;
+
+        for (index, router) in file.routers.iter().enumerate() {
+            if file.routers[..index]
+                .iter()
+                .any(|other| other.name == router.name)
+            {
+                return Err(InventoryError::DuplicateName(router.name.clone()));
+            }
+        }
+
+        Ok(Inventory {
+            routers: file.routers,
+        })
+    }
+}
+
+/// Which inventory routers a command runs on: `all`, `tag:<tag>` or a router name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    All,
+    Tag(String),
+    Name(String),
+}
+
+impl Target {
+    pub fn matches(&self, router: &Router) -> bool {
+        match self {
+            Target::All => true,
+            Target::Tag(tag) => router.tags.contains(tag),
+            Target::Name(name) => router.name == *name,
+        }
+    }
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    fn from_str(target: &str) -> Result<Self, Self::Err> {
+        match target.split_once(':') {
+            _ if target == "all" => Ok(Target::All),
+            Some(("tag", tag)) if !tag.is_empty() => Ok(Target::Tag(tag.to_string())),
+            Some(("name", name)) if !name.is_empty() => Ok(Target::Name(name.to_string())),
+            None if !target.is_empty() => Ok(Target::Name(target.to_string())),
+            _ => Err(format!(
+                "invalid target {:?}, expected all, tag:<tag> or a router name",
+                target
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INVENTORY: &str = r#"
+        [[router]]
+        name = "boat-1"
+        host = "10.8.0.11"
+        tags = ["boats", "north"]
+
+        [[router]]
+        name = "boat-2"
+        host = "10.8.0.12"
+        port = 8443
+        tags = ["boats"]
+
+        [[router]]
+        name = "office"
+        host = "192.168.7.1"
+    "#;
+
+    fn names(routers: Vec<&Router>) -> Vec<&str> {
+        routers
+            .into_iter()
+            .map(|router| router.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_select() {
+        let inventory: Inventory = INVENTORY.parse().unwrap();
+
+        let targets = ["tag:boats".parse().unwrap()];
+        assert_eq!(names(inventory.select(&targets)), ["boat-1", "boat-2"]);
+
+        let targets = ["office".parse().unwrap(), "tag:north".parse().unwrap()];
+        assert_eq!(names(inventory.select(&targets)), ["boat-1", "office"]);
+
+        let targets = [Target::All];
+        assert_eq!(inventory.select(&targets).len(), 3);
+        assert!(inventory.select(&[Target::Tag("south".into())]).is_empty());
+    }
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!("all".parse(), Ok(Target::All));
+        assert_eq!("tag:boats".parse(), Ok(Target::Tag("boats".into())));
+        assert_eq!("name:all".parse(), Ok(Target::Name("all".into())));
+        assert_eq!("boat-1".parse(), Ok(Target::Name("boat-1".into())));
+        assert!("tag:".parse::<Target>().is_err());
+        assert!("group:boats".parse::<Target>().is_err());
+    }
+
+    #[test]
+    fn test_duplicate_name() {
+        let contents = format!("{}\n[[router]]\nname = \"office\"\nhost = \"x\"", INVENTORY);
+        assert!(matches!(
+            contents.parse::<Inventory>(),
+            Err(InventoryError::DuplicateName(name)) if name == "office"
+        ));
+    }
+}
@@ -1,26 +1,88 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    path::PathBuf,
+    time::Duration,
+};
+
 use clap::{CommandFactory, Parser};
-use tonik::IpNeighborStatusV4;
+use futures::StreamExt;
+use tonik::presence::{PresenceEvent, PresenceTracker, PresenceWatcher};
+use tonik::profile::{Config, Profile};
+use tonik::{PortForward, Response, TeltonikaClient, UsagePeriod};
+
+use inventory::{Inventory, Target};
+use output::{Format, Output, View};
+use session::SessionCache;
+
+mod firewall;
+mod firmware;
+mod fleet;
+mod inventory;
+mod mobile;
+mod network;
+mod output;
+mod session;
+mod wireless;
 
 #[derive(Debug, clap::Parser)]
 struct App {
-    /// Teltonika host
-    #[clap(long, env = "TELTONIKA_HOST", default_value = "192.168.7.1")]
+    /// Profile from ~/.config/tonik/config.toml to connect with
+    #[clap(long, env = "TONIK_PROFILE")]
+    profile: Option<String>,
+
+    /// Config file with profiles, by default ~/.config/tonik/config.toml
+    #[clap(long, env = "TONIK_CONFIG", value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Teltonika host [default: 192.168.7.1]
+    #[clap(long, env = "TELTONIKA_HOST")]
     host: Option<String>,
 
-    /// Teltonika username
-    #[clap(long, env = "TELTONIKA_USERNAME", default_value = "admin")]
+    /// Teltonika username [default: admin]
+    #[clap(long, env = "TELTONIKA_USERNAME")]
     username: Option<String>,
 
     /// Teltonika password
     #[clap(long, env = "TELTONIKA_PASSWORD")]
     password: Option<String>,
 
+    /// Teltonika API port, if not the scheme's default
+    #[clap(long, env = "TELTONIKA_PORT")]
+    port: Option<u16>,
+
+    /// Connect over plain HTTP instead of HTTPS
+    #[clap(long)]
+    http: bool,
+
+    /// PEM certificate to trust instead of accepting any certificate
+    #[clap(long, env = "TELTONIKA_CERT")]
+    cert: Option<PathBuf>,
+
+    /// Request timeout in seconds, except for firmware uploads
+    #[clap(long, default_value = "30")]
+    timeout: u64,
+
+    /// Run on routers from the inventory instead of --host: all, tag:<tag> or a name
+    #[clap(long, global = true, value_name = "TARGET")]
+    target: Vec<Target>,
+
+    /// Inventory file, by default ~/.config/tonik/inventory.toml
+    #[clap(long, env = "TONIK_INVENTORY", value_name = "PATH")]
+    inventory: Option<PathBuf>,
+
+    /// Number of routers to run on at once with --target
+    #[clap(long, default_value_t = 8)]
+    parallel: usize,
+
+    /// Log in afresh instead of reusing a session token from an earlier run
+    #[clap(long)]
+    no_session_cache: bool,
+
     #[clap(subcommand)]
     command: Option<Command>,
 
-    /// Output in JSON format
-    #[clap(long)]
-    json: bool,
+    #[clap(flatten)]
+    view: View,
 
     /// Generate shell completion
     #[clap(long)]
@@ -29,18 +91,57 @@ struct App {
 
 #[derive(Debug, clap::Subcommand)]
 enum Command {
+    /// Mobile data used per SIM, projected to the end of the billing period
+    #[clap(name = "data-usage")]
+    DataUsage {
+        /// Show totals per calendar day, week or month instead
+        #[clap(long, value_name = "PERIOD", value_parser = mobile::parse_period)]
+        by: Option<UsagePeriod>,
+    },
+
     /// DHCP related commands
     #[clap(name = "dhcp")]
     DhcpCommand(DhcpCommand),
 
+    /// WAN failover priorities and health checks
+    #[clap(subcommand)]
+    Failover(FailoverCommand),
+
+    /// Firewall port forwards, traffic rules and zones
+    #[clap(subcommand)]
+    Firewall(FirewallCommand),
+
     /// Firmware related commands
     #[clap(name = "firmware")]
     Firmware(FirmwareCommand),
 
     Gps(GpsCommand),
 
+    /// Network interfaces, marking the WAN that carries traffic with *
+    #[clap(name = "interfaces")]
+    Interfaces,
+
     #[clap(subcommand)]
     IpNeighbors(IpNeighborsCommand),
+
+    /// Mobile modem related commands
+    #[clap(subcommand)]
+    Modem(ModemCommand),
+
+    /// SIM slots of dual-SIM modems: switching, APNs and PINs
+    #[clap(subcommand)]
+    Sim(SimCommand),
+
+    /// Wi-Fi related commands
+    #[clap(subcommand)]
+    Wireless(WirelessCommand),
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum ModemCommand {
+    /// Get modem status and signal quality
+    #[clap(name = "status")]
+    Status,
 }
 
 /// Global Positioning System related commands
@@ -123,8 +224,30 @@ struct FirmwareCommand {
 enum FirmwareCommandSubcommand {
     /// Get firmware device status
     Device(FirmwareDeviceCommand),
-    // / Actions related to firmware
-    // Actions(FirmwareActionsCommand),
+
+    /// Check the FOTA server for newer firmware
+    #[clap(name = "check")]
+    Check,
+
+    /// Download the latest firmware from the FOTA server
+    #[clap(name = "download")]
+    Download,
+
+    /// Install downloaded firmware, or a local firmware file, and reboot
+    #[clap(name = "upgrade")]
+    Upgrade {
+        /// Keep the current configuration
+        #[clap(long)]
+        keep_settings: bool,
+
+        /// Upload and install this firmware image instead
+        #[clap(long, value_name = "PATH")]
+        file: Option<PathBuf>,
+
+        /// Don't ask for confirmation
+        #[clap(long, short)]
+        yes: bool,
+    },
 }
 
 #[derive(Debug, clap::Args)]
@@ -142,163 +265,827 @@ enum FirmwareDeviceCommandSubcommand {
     Status,
 }
 
-#[derive(Debug, clap::Subcommand)]
-enum FirmwareActionsCommand {
-    /// Download firmware over the air
-    #[clap(name = "fota_download")]
-    FotaDownload,
-}
-
 #[derive(Debug, clap::Subcommand)]
 enum IpNeighborsCommand {
     /// Get firmware device status
     #[clap(name = "status")]
     Status,
 
+    /// Print devices as they arrive and depart
     #[clap(name = "watch-status")]
-    WatchStatus,
+    WatchStatus {
+        /// Seconds a device may go unseen before it is reported as removed
+        #[clap(long, value_name = "SECONDS", default_value_t = 0)]
+        grace: u64,
+    },
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum FailoverCommand {
+    /// Show failover interfaces by priority, marking the one carrying traffic with *
+    #[clap(name = "status")]
+    Status,
+
+    /// Send traffic through an interface whenever it is online
+    #[clap(name = "prefer")]
+    Prefer {
+        /// Network interface, e.g. wan or mob1s1a1
+        interface: String,
+    },
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum FirewallCommand {
+    /// Forward ports from the WAN to hosts on the LAN
+    #[clap(subcommand, name = "port-forward")]
+    PortForward(PortForwardCommand),
+
+    /// Accept, reject or drop traffic
+    #[clap(subcommand, name = "traffic-rule")]
+    TrafficRule(TrafficRuleCommand),
+
+    /// Firewall zones and their policies
+    #[clap(subcommand, name = "zone")]
+    Zone(ZoneCommand),
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum PortForwardCommand {
+    /// List port forwards
+    #[clap(name = "list")]
+    List,
+
+    /// Forward a port to a host
+    #[clap(name = "add")]
+    Add {
+        /// External port or range, e.g. 8080 or 8000-8010
+        #[clap(long, value_name = "PORT", value_parser = firewall::parse_port)]
+        ext: String,
+
+        /// Host to forward to, and the port if not the external one, e.g. 192.168.7.20:80
+        #[clap(long, value_name = "IP[:PORT]")]
+        to: firewall::Destination,
+
+        /// Protocols to forward [default: tcp,udp]
+        #[clap(long, value_delimiter = ',', value_parser = ["tcp", "udp"])]
+        proto: Vec<String>,
+
+        /// Name of the port forward
+        #[clap(long)]
+        name: Option<String>,
+
+        /// Only forward traffic from this address or subnet
+        #[clap(long, value_name = "IP")]
+        source: Option<String>,
+
+        /// Add the port forward disabled
+        #[clap(long)]
+        disabled: bool,
+    },
+
+    /// Remove a port forward
+    #[clap(name = "remove")]
+    Remove {
+        /// ID or name of the port forward
+        rule: String,
+    },
+
+    /// Enable a port forward
+    #[clap(name = "enable")]
+    Enable {
+        /// ID or name of the port forward
+        rule: String,
+    },
+
+    /// Disable a port forward, keeping it for later
+    #[clap(name = "disable")]
+    Disable {
+        /// ID or name of the port forward
+        rule: String,
+    },
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum TrafficRuleCommand {
+    /// List traffic rules
+    #[clap(name = "list")]
+    List,
+
+    /// Remove a traffic rule
+    #[clap(name = "remove")]
+    Remove {
+        /// ID or name of the traffic rule
+        rule: String,
+    },
+
+    /// Enable a traffic rule
+    #[clap(name = "enable")]
+    Enable {
+        /// ID or name of the traffic rule
+        rule: String,
+    },
+
+    /// Disable a traffic rule, keeping it for later
+    #[clap(name = "disable")]
+    Disable {
+        /// ID or name of the traffic rule
+        rule: String,
+    },
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum ZoneCommand {
+    /// List firewall zones
+    #[clap(name = "list")]
+    List,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum SimCommand {
+    /// Show SIM slots, marking the active ones with *, and the rules for switching
+    #[clap(name = "status")]
+    Status,
+
+    /// Switch a modem to another SIM slot
+    #[clap(name = "switch")]
+    Switch {
+        /// Slot to switch to [default: the modem's other SIM]
+        sim: Option<u8>,
+
+        /// Modem to switch [default: the primary modem]
+        #[clap(long)]
+        modem: Option<String>,
+    },
+
+    /// Show the APN of each SIM slot, or set one
+    #[clap(name = "apn")]
+    Apn {
+        /// APN to set, or "" to use the operator's
+        #[clap(requires = "sim")]
+        apn: Option<String>,
+
+        /// Slot to set the APN of
+        #[clap(long)]
+        sim: Option<u8>,
+
+        /// Modem the slot belongs to [default: the primary modem]
+        #[clap(long, requires = "apn")]
+        modem: Option<String>,
+
+        /// How to authenticate with the APN
+        #[clap(long, requires = "apn", value_parser = ["none", "pap", "chap"])]
+        auth: Option<String>,
+
+        #[clap(long, requires = "apn")]
+        username: Option<String>,
+
+        #[clap(long, requires = "apn")]
+        password: Option<String>,
+    },
+
+    /// Enter, change or store the PIN of a SIM. PINs are prompted for, or read a
+    /// line each from stdin
+    #[clap(name = "pin", subcommand)]
+    Pin(SimPinCommand),
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum SimPinCommand {
+    /// Enter the PIN of a SIM that is waiting for one
+    #[clap(name = "unlock")]
+    Unlock {
+        /// Slot to unlock [default: the one waiting for a PIN]
+        #[clap(long)]
+        sim: Option<u8>,
+
+        /// Modem the slot belongs to [default: the primary modem]
+        #[clap(long)]
+        modem: Option<String>,
+    },
+
+    /// Change the PIN on a SIM card, and the stored PIN if it was the old one
+    #[clap(name = "change")]
+    Change {
+        /// Slot of the SIM [default: the active one]
+        #[clap(long)]
+        sim: Option<u8>,
+
+        /// Modem the slot belongs to [default: the primary modem]
+        #[clap(long)]
+        modem: Option<String>,
+    },
+
+    /// Store the PIN the router enters when the modem starts, or none if left empty
+    #[clap(name = "set")]
+    Set {
+        /// Slot of the SIM [default: the active one]
+        #[clap(long)]
+        sim: Option<u8>,
+
+        /// Modem the slot belongs to [default: the primary modem]
+        #[clap(long)]
+        modem: Option<String>,
+    },
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum WirelessCommand {
+    /// List wireless interfaces
+    #[clap(name = "interfaces")]
+    Interfaces,
+
+    /// List connected clients with their signal strength and DHCP hostname
+    #[clap(name = "clients")]
+    Clients,
+
+    /// Print clients as they connect and disconnect, and as their signal changes
+    #[clap(name = "watch")]
+    Watch {
+        /// Seconds between polls
+        #[clap(long, value_name = "SECONDS", default_value_t = 5)]
+        interval: u64,
+
+        /// Smallest change in signal strength to print, in dB
+        #[clap(long, value_name = "DB", default_value_t = 5)]
+        threshold: i64,
+    },
 }
 
 #[tokio::main]
 async fn main() {
-    let _app = App::parse();
+    let app = App::parse();
 
-    if let Some(shell) = _app.completion {
-        let mut app = App::command();
-        let app_name = app.get_name().to_string();
-        clap_complete::generate(shell, &mut app, app_name, &mut std::io::stdout());
+    if let Some(shell) = app.completion {
+        let mut command = App::command();
+        let app_name = command.get_name().to_string();
+        clap_complete::generate(shell, &mut command, app_name, &mut std::io::stdout());
         return;
     }
 
-    let client = async {
-        let mut teltonika = tonik::TeltonikaClient::new(_app.host.expect("Host Required"));
+    let Some(command) = &app.command else {
+        // Print help
+        let mut command = App::command();
+        command.print_help().unwrap();
+        std::process::exit(1);
+    };
 
-        let authentication_response = teltonika
-            .authenticate(
-                _app.username.expect("Username Required").as_str(),
-                _app.password.expect("Password Required").as_str(),
-            )
-            .await;
+    let config = match &app.config {
+        Some(path) => Config::load(path),
+        None => Config::load_default(),
+    };
+    let config = exit_on_error(config.map_err(|e| Failure(e.to_string())));
+    let sessions = if app.no_session_cache {
+        SessionCache::disabled()
+    } else {
+        SessionCache::load()
+    };
 
-        if let Err(e) = authentication_response {
-            eprintln!("Error: {}", e);
+    if let Command::IpNeighbors(IpNeighborsCommand::WatchStatus { grace }) = command {
+        if !app.target.is_empty() {
+            eprintln!("Error: watch-status can't be run with --target");
+            std::process::exit(1);
+        }
+        let connection = exit_on_error(app.connection(&config));
+        let client = exit_on_error(connect(&connection, &sessions).await);
+        sessions.save().ok();
+        watch_status(client, Duration::from_secs(*grace)).await;
+        return;
+    }
+
+    if let Command::Wireless(WirelessCommand::Watch {
+        interval,
+        threshold,
+    }) = command
+    {
+        if !app.target.is_empty() {
+            eprintln!("Error: wireless watch can't be run with --target");
             std::process::exit(1);
         }
+        let connection = exit_on_error(app.connection(&config));
+        let client = exit_on_error(connect(&connection, &sessions).await);
+        sessions.save().ok();
+        wireless::watch(client, Duration::from_secs((*interval).max(1)), *threshold).await;
+        return;
+    }
 
-        teltonika
+    let routers = if app.target.is_empty() {
+        None
+    } else {
+        Some(exit_on_error(app.routers(&config)))
     };
 
-    let client = client.await;
-
-    match _app.command {
-        Some(Command::DhcpCommand(dhcp_command)) => match dhcp_command.command {
-            DhcpCommandSubcommand::DhcpCommandIpv4(dhcp_ipv4_command) => {
-                match dhcp_ipv4_command.command {
-                    DhcpCommandIpv4Subcommand::Status => {
-                        let response = client.dhcp_leases_ipv4_status().await.unwrap();
-                        if _app.json {
-                            println!("{}", serde_json::to_string_pretty(&response.data).unwrap());
-                        } else {
-                            for lease in response.data.unwrap() {
-                                println!("{}", lease);
-                            }
+    let image = match command {
+        Command::Firmware(FirmwareCommand {
+            command:
+                FirmwareCommandSubcommand::Upgrade {
+                    keep_settings,
+                    file,
+                    yes,
+                },
+        }) => {
+            let image = file
+                .as_deref()
+                .map(firmware::Image::read)
+                .map(exit_on_error);
+            let description = match &routers {
+                None => exit_on_error(app.connection(&config)).host,
+                Some(routers) => format!("{} routers", routers.len()),
+            };
+            if !yes && !firmware::confirm_upgrade(&description, image.as_ref(), *keep_settings) {
+                eprintln!("Aborted");
+                std::process::exit(1);
+            }
+            image
+        }
+        _ => None,
+    };
+
+    let pins = match command {
+        Command::Sim(SimCommand::Pin(SimPinCommand::Unlock { .. })) => {
+            exit_on_error(mobile::read_pins(&["PIN"]))
+        }
+        Command::Sim(SimCommand::Pin(SimPinCommand::Change { .. })) => {
+            exit_on_error(mobile::read_pins(&["Current PIN", "New PIN"]))
+        }
+        Command::Sim(SimCommand::Pin(SimPinCommand::Set { .. })) => {
+            exit_on_error(mobile::read_pins(&["PIN (empty for none)"]))
+        }
+        _ => Vec::new(),
+    };
+
+    let task = Task {
+        command,
+        image,
+        pins,
+        progress: routers.is_none() && app.view.format() == Format::Text,
+    };
+
+    match routers {
+        None => {
+            let connection = exit_on_error(app.connection(&config));
+            let result = match connect(&connection, &sessions).await {
+                Ok(client) => task.execute(&client).await,
+                Err(failure) => Err(failure),
+            };
+            sessions.save().ok();
+
+            let output = exit_on_error(result);
+            let rendered = app.view.render(&output);
+            if !rendered.is_empty() {
+                println!("{}", rendered);
+            }
+        }
+        Some(routers) => {
+            let outcomes = fleet::run(routers, &task, &sessions, app.parallel).await;
+            sessions.save().ok();
+            if !fleet::print(&outcomes, &app.view) {
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+impl App {
+    /// The router given with --host and friends, filling in anything not given from
+    /// the --profile.
+    fn connection(&self, config: &Config) -> Result<Connection, Failure> {
+        let profile = config
+            .profile(self.profile.as_deref())
+            .map_err(|e| Failure(e.to_string()))?;
+
+        let mut connection = Connection::default().timeout(Duration::from_secs(self.timeout));
+        if let Some(profile) = profile {
+            connection = connection.profile(profile);
+        }
+        connection.host = self.host.clone().unwrap_or(connection.host);
+        connection.port = self.port.or(connection.port);
+        connection.http |= self.http;
+        connection.cert = self.cert.clone().or(connection.cert);
+        connection.username = self.username.clone().unwrap_or(connection.username);
+        if let Some(password) = &self.password {
+            connection.password = Password::Given(password.clone());
+        }
+
+        Ok(connection)
+    }
+
+    /// The inventory routers selected with --target. Anything a router doesn't set
+    /// comes from its profile, then from the global options.
+    fn routers(&self, config: &Config) -> Result<Vec<(String, Connection)>, Failure> {
+        let path = self
+            .inventory
+            .clone()
+            .or_else(Inventory::default_path)
+            .ok_or_else(|| Failure("no inventory file, use --inventory".to_string()))?;
+        let inventory = Inventory::load(&path).map_err(|e| Failure(e.to_string()))?;
+
+        let routers = inventory.select(&self.target);
+        if routers.is_empty() {
+            return Err(Failure(format!(
+                "no routers in {} match the target",
+                path.display()
+            )));
+        }
+
+        let defaults = self.connection(config)?;
+        routers
+            .into_iter()
+            .map(|router| {
+                let mut connection = defaults.clone();
+                if let Some(name) = &router.profile {
+                    let profile = config
+                        .profile(Some(name))
+                        .map_err(|e| Failure(format!("{}: {}", router.name, e)))?;
+                    connection = connection.profile(profile.unwrap());
+                }
+
+                connection.host = router.host.clone();
+                connection.port = router.port.or(connection.port);
+                connection.http = router.http.unwrap_or(connection.http);
+                connection.cert = router.cert.clone().or(connection.cert);
+                connection.username = router.username.clone().unwrap_or(connection.username);
+                if let Some(password) = router.password() {
+                    connection.password = Password::Given(password);
+                }
+
+                Ok((router.name.clone(), connection))
+            })
+            .collect()
+    }
+}
+
+/// How to reach and log in to a router.
+#[derive(Clone)]
+struct Connection {
+    host: String,
+    port: Option<u16>,
+    http: bool,
+    cert: Option<PathBuf>,
+    timeout: Duration,
+    username: String,
+    password: Password,
+}
+
+/// Where a router's password comes from. Profiles are only asked for it when there
+/// is no cached session, as that may run a command or prompt for a passphrase.
+#[derive(Clone)]
+enum Password {
+    Given(String),
+    Profile(Profile),
+    Missing,
+}
+
+impl Default for Connection {
+    fn default() -> Self {
+        Connection {
+            host: "192.168.7.1".to_string(),
+            port: None,
+            http: false,
+            cert: None,
+            timeout: Duration::from_secs(30),
+            username: "admin".to_string(),
+            password: Password::Missing,
+        }
+    }
+}
+
+impl Connection {
+    fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Take everything the profile sets from it.
+    fn profile(mut self, profile: &Profile) -> Self {
+        self.host = profile.host.clone().unwrap_or(self.host);
+        self.port = profile.port.or(self.port);
+        self.http = profile.http.unwrap_or(self.http);
+        self.cert = profile.cert.clone().or(self.cert);
+        self.username = profile.username.clone().unwrap_or(self.username);
+        if profile.password.is_some()
+            || profile.password_cmd.is_some()
+            || profile.password_file.is_some()
+        {
+            self.password = Password::Profile(profile.clone());
+        }
+        self
+    }
+
+    fn password(&self) -> Result<String, Failure> {
+        let missing = || {
+            Failure(format!(
+                "no password for {}, use --password or --profile",
+                self.host
+            ))
+        };
+
+        match &self.password {
+            Password::Given(password) => Ok(password.clone()),
+            Password::Profile(profile) => profile
+                .password()
+                .map_err(|e| Failure(e.to_string()))?
+                .ok_or_else(missing),
+            Password::Missing => Err(missing()),
+        }
+    }
+}
+
+/// Build a client for the router and reuse its cached session, or log in.
+async fn connect(
+    connection: &Connection,
+    sessions: &SessionCache,
+) -> Result<TeltonikaClient, Failure> {
+    let mut builder = TeltonikaClient::builder(connection.host.clone()).timeout(connection.timeout);
+
+    if let Some(port) = connection.port {
+        builder = builder.port(port);
+    }
+    if connection.http {
+        builder = builder.scheme(tonik::Scheme::Http);
+    }
+    builder = match &connection.cert {
+        Some(path) => {
+            let pem = std::fs::read(path)
+                .map_err(|e| Failure(format!("failed to read {}: {}", path.display(), e)))?;
+            let certificate = tonik::Certificate::from_pem(&pem)
+                .map_err(|e| Failure(format!("invalid certificate {}: {}", path.display(), e)))?;
+            builder
+                .add_root_certificate(certificate)
+                .pin_certificates()
+                .danger_accept_invalid_hostnames(true)
+        }
+        None => builder.danger_accept_invalid_certs(true),
+    };
+
+    let teltonika = builder.build()?;
+
+    if let Some((token, expires_in)) = sessions.get(&connection.username, &teltonika) {
+        teltonika.restore_session(token, expires_in).await;
+        match teltonika
+            .session_status()
+            .await
+            .and_then(Response::into_result)
+        {
+            Ok(_) => return Ok(teltonika),
+            Err(e) if e.is_unauthorized() => sessions.remove(&connection.username, &teltonika),
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    teltonika
+        .authenticate(&connection.username, &connection.password()?)
+        .await
+        .and_then(Response::into_result)?;
+    sessions.insert(&connection.username, &teltonika).await;
+
+    Ok(teltonika)
+}
+
+/// A command, ready to run on one or more routers.
+struct Task<'a> {
+    command: &'a Command,
+    image: Option<firmware::Image>,
+    /// PINs for `sim pin`, read once rather than for every router.
+    pins: Vec<String>,
+    /// Print progress of long running commands.
+    progress: bool,
+}
+
+impl Task<'_> {
+    async fn execute(&self, client: &TeltonikaClient) -> Result<Output, Failure> {
+        match self.command {
+            Command::DataUsage { by: None } => mobile::data_usage(client).await,
+            Command::DataUsage { by: Some(period) } => mobile::data_usage_by(client, *period).await,
+            Command::DhcpCommand(dhcp_command) => match &dhcp_command.command {
+                DhcpCommandSubcommand::DhcpCommandIpv4(dhcp_ipv4_command) => {
+                    match dhcp_ipv4_command.command {
+                        DhcpCommandIpv4Subcommand::Status => {
+                            let response = client
+                                .dhcp_leases_ipv4_status()
+                                .await
+                                .and_then(Response::into_result)?;
+                            Ok(Output::lines(&response))
                         }
                     }
                 }
-            }
-            DhcpCommandSubcommand::DhcpCommandIpv6(dhcp_ipv6_command) => {
-                match dhcp_ipv6_command.command {
-                    DhcpCommandIpv6Subcommand::Status => {
-                        let response = client.dhcp_leases_ipv6_status().await.unwrap();
-                        if _app.json {
-                            println!("{}", serde_json::to_string_pretty(&response.data).unwrap());
-                        } else {
-                            for lease in response.data.unwrap() {
-                                println!("{}", lease);
-                            }
+                DhcpCommandSubcommand::DhcpCommandIpv6(dhcp_ipv6_command) => {
+                    match dhcp_ipv6_command.command {
+                        DhcpCommandIpv6Subcommand::Status => {
+                            let response = client
+                                .dhcp_leases_ipv6_status()
+                                .await
+                                .and_then(Response::into_result)?;
+                            Ok(Output::lines(&response))
                         }
                     }
                 }
-            }
-        },
-        Some(Command::Firmware(firmware_command)) => match firmware_command.command {
-            FirmwareCommandSubcommand::Device(firmware_device_command) => {
-                match firmware_device_command.command {
-                    FirmwareDeviceCommandSubcommand::Status => {
-                        let response = client.firmware_device_status().await.unwrap();
-                        if _app.json {
-                            println!("{}", serde_json::to_string_pretty(&response.data).unwrap());
-                        } else {
-                            println!("{}", response.data.unwrap());
-                        }
+            },
+            Command::Failover(failover_command) => match failover_command {
+                FailoverCommand::Status => network::failover_status(client).await,
+                FailoverCommand::Prefer { interface } => {
+                    network::failover_prefer(client, interface).await
+                }
+            },
+            Command::Firewall(FirewallCommand::PortForward(command)) => match command {
+                PortForwardCommand::List => firewall::port_forwards(client).await,
+                PortForwardCommand::Add {
+                    ext,
+                    to,
+                    proto,
+                    name,
+                    source,
+                    disabled,
+                } => {
+                    let mut forward = PortForward::new(ext, &to.ip);
+                    if let Some(port) = &to.port {
+                        forward = forward.dest_port(port);
+                    }
+                    if let Some(name) = name {
+                        forward = forward.name(name);
+                    }
+                    if !proto.is_empty() {
+                        forward.proto = proto.clone();
                     }
+                    forward.src_ip = source.clone();
+                    forward.enabled = !disabled;
+                    firewall::add_port_forward(client, &forward).await
                 }
-            }
-        },
-        Some(Command::Gps(gps_command)) => match gps_command.command {
-            GpsCommandSubcommand::Position => {
-                let response = client.gps_position_status().await.unwrap();
-                if _app.json {
-                    println!("{}", serde_json::to_string_pretty(&response.data).unwrap());
-                } else {
-                    println!("{}", response.data.unwrap());
+                PortForwardCommand::Remove { rule } => {
+                    firewall::remove_port_forward(client, rule).await
                 }
-            }
-        },
-        Some(Command::IpNeighbors(ip_neighbors_command)) => match ip_neighbors_command {
-            IpNeighborsCommand::Status => {
-                let response = client.ip_neighbors_ipv4_status().await.unwrap();
-                if _app.json {
-                    println!("{}", serde_json::to_string_pretty(&response.data).unwrap());
-                } else {
-                    println!("{:?}", response.data.unwrap());
+                PortForwardCommand::Enable { rule } => {
+                    firewall::enable_port_forward(client, rule, true).await
+                }
+                PortForwardCommand::Disable { rule } => {
+                    firewall::enable_port_forward(client, rule, false).await
                 }
+            },
+            Command::Firewall(FirewallCommand::TrafficRule(command)) => match command {
+                TrafficRuleCommand::List => firewall::traffic_rules(client).await,
+                TrafficRuleCommand::Remove { rule } => {
+                    firewall::remove_traffic_rule(client, rule).await
+                }
+                TrafficRuleCommand::Enable { rule } => {
+                    firewall::enable_traffic_rule(client, rule, true).await
+                }
+                TrafficRuleCommand::Disable { rule } => {
+                    firewall::enable_traffic_rule(client, rule, false).await
+                }
+            },
+            Command::Firewall(FirewallCommand::Zone(ZoneCommand::List)) => {
+                firewall::zones(client).await
             }
-            IpNeighborsCommand::WatchStatus => {
-                let mut last_status: Vec<IpNeighborStatusV4> = Vec::new();
-                loop {
-                    let response = client.ip_neighbors_ipv4_status().await.unwrap();
-
-                    if let Some(new_status) = response.data.clone() {
-                        println!("Scanned");
-                        let macs_in_new_status: Vec<String> = new_status
-                            .iter()
-                            .filter_map(|neighbor| neighbor.mac.clone())
-                            .collect();
-                        let macs_in_last_status: Vec<String> = last_status
-                            .iter()
-                            .filter_map(|neighbor| neighbor.mac.clone())
-                            .collect();
-
-                        let macs_added = macs_in_new_status
-                            .iter()
-                            .filter(|mac| !macs_in_last_status.contains(mac))
-                            .collect::<Vec<&String>>();
-
-                        let macs_removed = macs_in_last_status
-                            .iter()
-                            .filter(|mac| !macs_in_new_status.contains(mac))
-                            .collect::<Vec<&String>>();
-
-                        for mac in macs_added {
-                            println!("Added: {:?}", mac);
-                        }
-                        for mac in macs_removed {
-                            println!("Removed: {:?}", mac);
+            Command::Firmware(firmware_command) => match &firmware_command.command {
+                FirmwareCommandSubcommand::Device(firmware_device_command) => {
+                    match firmware_device_command.command {
+                        FirmwareDeviceCommandSubcommand::Status => {
+                            let response = client
+                                .firmware_device_status()
+                                .await
+                                .and_then(Response::into_result)?;
+                            Ok(Output::one(&response, response.to_string()))
                         }
-
-                        last_status = new_status;
-                    } else {
-                        println!("{:?}", response.data.unwrap());
                     }
-                    std::thread::sleep(std::time::Duration::from_millis(1000));
                 }
-            }
-        },
-        None => {
-            // Print help
-            let mut app = App::command();
-            app.print_help().unwrap();
+                FirmwareCommandSubcommand::Check => firmware::check(client).await,
+                FirmwareCommandSubcommand::Download => {
+                    firmware::download(client, self.progress).await
+                }
+                FirmwareCommandSubcommand::Upgrade { keep_settings, .. } => {
+                    firmware::upgrade(client, self.image.as_ref(), *keep_settings).await
+                }
+            },
+            Command::Gps(gps_command) => match gps_command.command {
+                GpsCommandSubcommand::Position => {
+                    let response = client
+                        .gps_position_status()
+                        .await
+                        .and_then(Response::into_result)?;
+                    Ok(Output::one(&response, response.to_string()))
+                }
+            },
+            Command::Interfaces => network::interfaces(client).await,
+            Command::IpNeighbors(ip_neighbors_command) => match ip_neighbors_command {
+                IpNeighborsCommand::Status => {
+                    let response = client
+                        .ip_neighbors_ipv4_status()
+                        .await
+                        .and_then(Response::into_result)?;
+                    Ok(Output::many(&response, String::new()))
+                }
+                IpNeighborsCommand::WatchStatus { .. } => Err(Failure(
+                    "watch-status can only be run on a single router".to_string(),
+                )),
+            },
+            Command::Modem(modem_command) => match modem_command {
+                ModemCommand::Status => {
+                    let response = client
+                        .modems_status()
+                        .await
+                        .and_then(Response::into_result)?;
+                    let text = response
+                        .iter()
+                        .map(|modem| format!("{}\n", modem))
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    Ok(Output::many(&response, text))
+                }
+            },
+            Command::Sim(sim_command) => match sim_command {
+                SimCommand::Status => mobile::sim_status(client).await,
+                SimCommand::Switch { sim, modem } => {
+                    mobile::switch_sim(client, modem.as_deref(), *sim).await
+                }
+                SimCommand::Apn { apn: None, .. } => mobile::sim_apns(client).await,
+                SimCommand::Apn {
+                    apn: Some(apn),
+                    sim: Some(sim),
+                    modem,
+                    auth,
+                    username,
+                    password,
+                } => {
+                    let apn = mobile::Apn {
+                        name: apn.clone(),
+                        auth: auth.clone(),
+                        username: username.clone(),
+                        password: password.clone(),
+                    };
+                    mobile::set_sim_apn(client, modem.as_deref(), *sim, apn).await
+                }
+                SimCommand::Apn { sim: None, .. } => {
+                    unreachable!("clap requires --sim with an APN")
+                }
+                SimCommand::Pin(SimPinCommand::Unlock { sim, modem }) => {
+                    mobile::unlock_sim(client, modem.as_deref(), *sim, &self.pins[0]).await
+                }
+                SimCommand::Pin(SimPinCommand::Change { sim, modem }) => {
+                    mobile::change_sim_pin(
+                        client,
+                        modem.as_deref(),
+                        *sim,
+                        &self.pins[0],
+                        &self.pins[1],
+                    )
+                    .await
+                }
+                SimCommand::Pin(SimPinCommand::Set { sim, modem }) => {
+                    mobile::set_sim_pin(client, modem.as_deref(), *sim, &self.pins[0]).await
+                }
+            },
+            Command::Wireless(wireless_command) => match wireless_command {
+                WirelessCommand::Interfaces => wireless::interfaces(client).await,
+                WirelessCommand::Clients => wireless::clients(client).await,
+                WirelessCommand::Watch { .. } => Err(Failure(
+                    "wireless watch can only be run on a single router".to_string(),
+                )),
+            },
+        }
+    }
+}
+
+/// Print devices as they arrive and depart, forever.
+async fn watch_status(client: TeltonikaClient, grace: Duration) {
+    let tracker = PresenceTracker::new()
+        .departure_grace(grace)
+        .lingering_grace(grace);
+    let watcher = PresenceWatcher::new(std::sync::Arc::new(client))
+        .interval(Duration::from_secs(1))
+        .include_existing(true)
+        .tracker(tracker);
+    let mut changes = Box::pin(watcher.into_stream());
+
+    while let Some(change) = changes.next().await {
+        match change {
+            Ok(PresenceEvent::Arrived { mac, .. }) => println!("Added: {}", mac),
+            Ok(PresenceEvent::Departed { mac, .. }) => println!("Removed: {}", mac),
+            Err(err) => eprintln!("Error: {}", err),
+        }
+    }
+}
+
+/// Why a command failed.
+#[derive(Debug)]
+struct Failure(String);
+
+impl From<tonik::Error> for Failure {
+    fn from(error: tonik::Error) -> Self {
+        Failure(error.to_string())
+    }
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Unwrap a result, printing the error and exiting on failure.
+fn exit_on_error<T>(result: Result<T, Failure>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(e) => {
+            eprintln!("Error: {}", e);
             std::process::exit(1);
         }
     }
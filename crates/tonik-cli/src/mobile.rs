@@ -0,0 +1,315 @@
+use std::io::{BufRead, IsTerminal};
+
+use serde_json::{json, Value};
+use tonik::{
+    BillingPeriodUsage, Error, Response, SimCard, SimSlot, TeltonikaClient, UsagePeriod, UsageTotal,
+};
+
+use crate::{Failure, Output};
+
+pub fn parse_period(period: &str) -> Result<UsagePeriod, String> {
+    match period {
+        "day" => Ok(UsagePeriod::Day),
+        "week" => Ok(UsagePeriod::Week),
+        "month" => Ok(UsagePeriod::Month),
+        other => Err(format!(
+            "invalid period {:?}, expected day, week or month",
+            other
+        )),
+    }
+}
+
+/// Prompt for each PIN on a terminal, or read them a line each from stdin, so they
+/// don't end up in the shell history or process list.
+pub fn read_pins(prompts: &[&str]) -> Result<Vec<String>, Failure> {
+    let failed = |err: std::io::Error| Failure(format!("failed to read PIN: {}", err));
+
+    if std::io::stdin().is_terminal() {
+        return prompts
+            .iter()
+            .map(|prompt| rpassword::prompt_password(format!("{}: ", prompt)).map_err(failed))
+            .collect();
+    }
+
+    let mut lines = std::io::stdin().lock().lines();
+    prompts
+        .iter()
+        .map(|prompt| match lines.next() {
+            Some(line) => line.map(|line| line.trim().to_string()).map_err(failed),
+            None => Err(Failure(format!("no {} on stdin", prompt.to_lowercase()))),
+        })
+        .collect()
+}
+
+/// Each SIM's usage in its billing period so far, against its data limit.
+pub async fn data_usage(client: &TeltonikaClient) -> Result<Output, Failure> {
+    let usage = BillingPeriodUsage::fetch(client).await?;
+
+    Ok(Output::lines(&usage))
+}
+
+/// Each SIM's usage per calendar day, week or month, most recent first.
+/// Weeks start on Monday.
+pub async fn data_usage_by(
+    client: &TeltonikaClient,
+    period: UsagePeriod,
+) -> Result<Output, Failure> {
+    let records = client.data_usage().await.and_then(Response::into_result)?;
+    let totals = UsageTotal::by_period(&records, period, 1);
+
+    Ok(Output::lines(&totals))
+}
+
+/// Each SIM slot, marking the active ones with *, followed by the rules for
+/// switching between them.
+pub async fn sim_status(client: &TeltonikaClient) -> Result<Output, Failure> {
+    let (slots, rules) = futures::join!(client.sim_status(), client.list_sim_switch_rules());
+    let slots = slots.and_then(Response::into_result)?;
+    let rules = rules.and_then(Response::into_result)?;
+
+    let json = slots
+        .iter()
+        .map(|slot| {
+            let mut value = json!(slot);
+            value["rules"] = json!(rules
+                .iter()
+                .filter(|rule| rule.modem == slot.modem && rule.sim == slot.sim)
+                .collect::<Vec<_>>());
+            value
+        })
+        .collect();
+
+    let mut text: Vec<String> = slots
+        .iter()
+        .map(|slot| {
+            let marker = if slot.active { "*" } else { " " };
+            format!("{} {}", marker, slot)
+        })
+        .collect();
+    if !rules.is_empty() {
+        text.push(String::new());
+        text.extend(rules.iter().map(ToString::to_string));
+    }
+
+    Ok(Output::json(Value::Array(json), text.join("\n")).columns(&[
+        "modem",
+        "sim",
+        "active",
+        "inserted",
+        "pin_state",
+        "operator",
+        "iccid",
+    ]))
+}
+
+/// Switch `modem`, by default the primary one, to the SIM in slot `sim`, by
+/// default its other inserted SIM.
+pub async fn switch_sim(
+    client: &TeltonikaClient,
+    modem: Option<&str>,
+    sim: Option<u8>,
+) -> Result<Output, Failure> {
+    let modem = match modem {
+        Some(modem) => modem.to_string(),
+        None => client.primary_modem().await?,
+    };
+    let slots = client.sim_status().await.and_then(Response::into_result)?;
+    let slots: Vec<&SimSlot> = slots.iter().filter(|slot| slot.modem == modem).collect();
+
+    let target = match sim {
+        Some(sim) => slots
+            .iter()
+            .find(|slot| slot.sim == sim)
+            .ok_or_else(|| Failure(format!("{} has no SIM {}", modem, sim)))?,
+        None => slots
+            .iter()
+            .find(|slot| !slot.active && slot.inserted)
+            .ok_or_else(|| Failure(format!("{} has no other SIM to switch to", modem)))?,
+    };
+    if !target.inserted {
+        return Err(Failure(format!(
+            "SIM {} of {} is not inserted",
+            target.sim, modem
+        )));
+    }
+    if target.active {
+        return Ok(Output::one(*target, format!("Already using {}", target)));
+    }
+
+    let active = client
+        .switch_sim(&modem, target.sim)
+        .await
+        .and_then(Response::into_result)?;
+
+    Ok(Output::one(&active, format!("Switched to {}", active)))
+}
+
+/// An APN and how to authenticate with it.
+pub struct Apn {
+    /// Empty to use the operator's.
+    pub name: String,
+    pub auth: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+/// The APN of each SIM slot.
+pub async fn sim_apns(client: &TeltonikaClient) -> Result<Output, Failure> {
+    let cards = client
+        .list_sim_cards()
+        .await
+        .and_then(Response::into_result)?;
+
+    Ok(Output::lines(&cards))
+}
+
+/// Set the APN of a SIM slot of `modem`, by default the primary one.
+pub async fn set_sim_apn(
+    client: &TeltonikaClient,
+    modem: Option<&str>,
+    sim: u8,
+    apn: Apn,
+) -> Result<Output, Failure> {
+    let modem = match modem {
+        Some(modem) => modem.to_string(),
+        None => client.primary_modem().await?,
+    };
+    let cards = client
+        .list_sim_cards()
+        .await
+        .and_then(Response::into_result)?;
+    let card = cards
+        .into_iter()
+        .find(|card| card.modem == modem && card.sim == sim)
+        .ok_or_else(|| Failure(format!("{} has no SIM {}", modem, sim)))?;
+
+    let auth = apn.auth.or(card.auth);
+    // Without authentication any username and password are cleared.
+    let (username, password) = match auth.as_deref() {
+        Some("none") => (None, None),
+        _ => (
+            apn.username.or(card.username),
+            apn.password.or(card.password),
+        ),
+    };
+    let card = SimCard {
+        apn: apn.name,
+        auth,
+        username,
+        password,
+        ..card
+    };
+    let updated = client
+        .update_sim_card(&card.id, &card)
+        .await
+        .and_then(Response::into_result)?;
+
+    Ok(Output::one(&updated, updated.to_string()))
+}
+
+/// Enter the PIN of a SIM of `modem`, by default the primary one, that is
+/// waiting for it. `sim` defaults to the slot that is waiting.
+pub async fn unlock_sim(
+    client: &TeltonikaClient,
+    modem: Option<&str>,
+    sim: Option<u8>,
+    pin: &str,
+) -> Result<Output, Failure> {
+    let modem = match modem {
+        Some(modem) => modem.to_string(),
+        None => client.primary_modem().await?,
+    };
+    let sim = match sim {
+        Some(sim) => sim,
+        None => {
+            let slots = client.sim_status().await.and_then(Response::into_result)?;
+            slots
+                .iter()
+                .find(|slot| slot.modem == modem && slot.pin_state.as_deref() == Some("sim pin"))
+                .map(|slot| slot.sim)
+                .ok_or_else(|| Failure(format!("{} has no SIM waiting for a PIN", modem)))?
+        }
+    };
+
+    let slot = client
+        .unlock_sim(&modem, sim, pin)
+        .await
+        .and_then(Response::into_result)?;
+
+    Ok(Output::one(&slot, format!("Unlocked {}", slot)))
+}
+
+/// Change the PIN on a SIM card of `modem`, by default the primary one, and the
+/// PIN the router enters at startup if it was the old one. `sim` defaults to the
+/// active slot.
+pub async fn change_sim_pin(
+    client: &TeltonikaClient,
+    modem: Option<&str>,
+    sim: Option<u8>,
+    current: &str,
+    new: &str,
+) -> Result<Output, Failure> {
+    let modem = match modem {
+        Some(modem) => modem.to_string(),
+        None => client.primary_modem().await?,
+    };
+    let sim = match sim {
+        Some(sim) => sim,
+        None => client.active_sim(&modem).await?,
+    };
+
+    let slot = client
+        .change_sim_pin(&modem, sim, current, new)
+        .await
+        .and_then(Response::into_result)?;
+
+    let cards = client
+        .list_sim_cards()
+        .await
+        .and_then(Response::into_result)?;
+    let stored = cards
+        .iter()
+        .any(|card| card.modem == modem && card.sim == sim && card.pin.as_deref() == Some(current));
+    if stored {
+        client
+            .set_sim_pin(&modem, sim, Some(new))
+            .await
+            .and_then(Response::into_result)?;
+    }
+
+    Ok(Output::one(
+        &slot,
+        format!("Changed the PIN of SIM {} ({})", slot.sim, slot.modem),
+    ))
+}
+
+/// Set the PIN the router enters when `modem`, by default the primary one,
+/// starts with a SIM, or stop entering one if `pin` is empty. `sim` defaults to
+/// the active slot.
+pub async fn set_sim_pin(
+    client: &TeltonikaClient,
+    modem: Option<&str>,
+    sim: Option<u8>,
+    pin: &str,
+) -> Result<Output, Failure> {
+    let modem = match modem {
+        Some(modem) => modem.to_string(),
+        None => client.primary_modem().await?,
+    };
+    let sim = match sim {
+        Some(sim) => sim,
+        None => client.active_sim(&modem).await?,
+    };
+
+    let pin = Some(pin).filter(|pin| !pin.is_empty());
+    let card = client
+        .set_sim_pin(&modem, sim, pin)
+        .await
+        .and_then(Response::into_result)
+        .map_err(|err| match err {
+            Error::MissingData => Failure(format!("{} has no SIM {}", modem, sim)),
+            err => err.into(),
+        })?;
+
+    Ok(Output::one(&card, card.to_string()))
+}
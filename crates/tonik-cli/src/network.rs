@@ -0,0 +1,145 @@
+use serde_json::{json, Value};
+use tonik::{
+    FailoverMember, FailoverState, FailoverStatus, NetworkInterfaceStatus, Response,
+    TeltonikaClient,
+};
+
+use crate::{Failure, Output};
+
+/// Every network interface, marking the WAN that currently carries traffic.
+pub async fn interfaces(client: &TeltonikaClient) -> Result<Output, Failure> {
+    let interfaces = client
+        .interfaces_status()
+        .await
+        .and_then(Response::into_result)?;
+    let active = NetworkInterfaceStatus::active_wan(&interfaces).map(|active| active.id.clone());
+    let is_active = |interface: &NetworkInterfaceStatus| Some(&interface.id) == active.as_ref();
+
+    let json = interfaces
+        .iter()
+        .map(|interface| {
+            let mut value = json!(interface);
+            value["active"] = json!(is_active(interface));
+            value
+        })
+        .collect();
+
+    let mut text: Vec<String> = interfaces
+        .iter()
+        .map(|interface| {
+            let marker = if is_active(interface) { "*" } else { " " };
+            format!("{} {}", marker, interface)
+        })
+        .collect();
+    text.push(match &active {
+        Some(active) => format!("\nTraffic goes out through {}", active),
+        None => "\nNo WAN is up".to_string(),
+    });
+
+    Ok(Output::json(Value::Array(json), text.join("\n")).columns(&[
+        "active",
+        "id",
+        "proto",
+        "up",
+        "device",
+        "ipv4_addresses",
+        "gateway",
+        "metric",
+    ]))
+}
+
+/// Failover members in priority order with their state, marking the one traffic
+/// goes through.
+pub async fn failover_status(client: &TeltonikaClient) -> Result<Output, Failure> {
+    let (members, statuses) =
+        futures::join!(client.list_failover_members(), client.failover_status());
+    let mut members = members.and_then(Response::into_result)?;
+    let statuses = statuses.and_then(Response::into_result)?;
+    members.sort_by_key(|member| member.metric.unwrap_or(u32::MAX));
+
+    Ok(failover_output(&members, &statuses))
+}
+
+/// Make `interface` the failover member traffic goes through whenever it is online.
+pub async fn failover_prefer(client: &TeltonikaClient, interface: &str) -> Result<Output, Failure> {
+    let members = client
+        .list_failover_members()
+        .await
+        .and_then(Response::into_result)?;
+    let changed = FailoverMember::prefer(&members, interface)
+        .ok_or_else(|| Failure(format!("{} is not a failover interface", interface)))?;
+
+    for member in &changed {
+        client
+            .update_failover_member(&member.id, member)
+            .await
+            .and_then(Response::into_result)?;
+    }
+
+    let mut output = failover_status(client).await?;
+    output.text = format!("Preferring {}\n\n{}", interface, output.text);
+    Ok(output)
+}
+
+fn failover_output(members: &[FailoverMember], statuses: &[FailoverStatus]) -> Output {
+    let status = |member: &FailoverMember| {
+        statuses
+            .iter()
+            .find(|status| status.interface == member.interface)
+    };
+    let state = |member: &FailoverMember| match status(member) {
+        _ if !member.enabled => FailoverState::Disabled,
+        Some(status) => status.status,
+        None => FailoverState::Unknown,
+    };
+    let active = members
+        .iter()
+        .find(|member| state(member) == FailoverState::Online)
+        .map(|member| member.id.as_str());
+
+    let json = members
+        .iter()
+        .map(|member| {
+            let mut value = json!(member);
+            value["active"] = json!(Some(member.id.as_str()) == active);
+            value["status"] = json!(state(member));
+            value["checks"] = json!(status(member).map(|status| &status.track_ip));
+            value
+        })
+        .collect();
+
+    let text = members
+        .iter()
+        .map(|member| {
+            let marker = if Some(member.id.as_str()) == active {
+                "*"
+            } else {
+                " "
+            };
+            let metric = member
+                .metric
+                .map(|metric| metric.to_string())
+                .unwrap_or_else(|| "-".to_string());
+            match status(member) {
+                Some(status) if member.enabled => format!("{} {} {}", marker, metric, status),
+                _ => format!(
+                    "{} {} {}: {}",
+                    marker,
+                    metric,
+                    member.interface,
+                    state(member)
+                ),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Output::json(Value::Array(json), text).columns(&[
+        "active",
+        "metric",
+        "interface",
+        "enabled",
+        "status",
+        "track_ip",
+    ])
+}
@@ -0,0 +1,527 @@
+use std::{cmp::Ordering, fmt::Display, net::IpAddr, str::FromStr};
+
+use serde_json::{Map, Value};
+use tonik::table::{flatten, Row, Tabular};
+
+/// What a command produced, printed as text or as rows in one of the other formats.
+pub struct Output {
+    pub json: Value,
+    /// Human readable output. Empty if the command has none, in which case a table
+    /// is shown instead.
+    pub text: String,
+    /// One row per item of `json`, or a single row if it isn't a list.
+    rows: Vec<Row>,
+    /// Columns shown unless others are chosen. Empty to show them all.
+    columns: &'static [&'static str],
+}
+
+impl Output {
+    pub fn one<T: Tabular>(value: &T, text: String) -> Self {
+        Output {
+            json: serde_json::to_value(value).unwrap(),
+            text,
+            rows: vec![value.row()],
+            columns: T::COLUMNS,
+        }
+    }
+
+    pub fn many<T: Tabular>(items: &[T], text: String) -> Self {
+        Output {
+            json: serde_json::to_value(items).unwrap(),
+            text,
+            rows: items.iter().map(Tabular::row).collect(),
+            columns: T::COLUMNS,
+        }
+    }
+
+    /// A list, printed one item per line.
+    pub fn lines<T: Tabular + Display>(items: &[T]) -> Self {
+        let text = items
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n");
+        Output::many(items, text)
+    }
+
+    /// Output that isn't a `tonik` type, shown with every column unless
+    /// [`Output::columns`] chooses some.
+    pub fn json(json: Value, text: String) -> Self {
+        let rows = match &json {
+            Value::Array(items) => items.iter().map(flatten).collect(),
+            value => vec![flatten(value)],
+        };
+        Output {
+            json,
+            text,
+            rows,
+            columns: &[],
+        }
+    }
+
+    /// The columns shown unless others are chosen.
+    pub fn columns(mut self, columns: &'static [&'static str]) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    fn is_list(&self) -> bool {
+        self.json.is_array()
+    }
+
+    fn item(&self, index: usize) -> &Value {
+        match &self.json {
+            Value::Array(items) => &items[index],
+            value => value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    /// Human readable text
+    Text,
+    /// Aligned columns
+    Table,
+    Json,
+    Yaml,
+    Csv,
+    /// One JSON object per line
+    Ndjson,
+}
+
+/// Only show rows where a column matches, e.g. `hostname~phone`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filter {
+    column: String,
+    operator: Operator,
+    value: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Equals,
+    NotEquals,
+    Contains,
+}
+
+impl Filter {
+    fn matches(&self, row: &Row) -> bool {
+        let cell = row.get(&self.column).map(cell).unwrap_or_default();
+        match self.operator {
+            Operator::Equals => cell.eq_ignore_ascii_case(&self.value),
+            Operator::NotEquals => !cell.eq_ignore_ascii_case(&self.value),
+            Operator::Contains => cell.to_lowercase().contains(&self.value.to_lowercase()),
+        }
+    }
+}
+
+impl FromStr for Filter {
+    type Err = String;
+
+    fn from_str(filter: &str) -> Result<Self, Self::Err> {
+        let (column, operator, value) = [
+            ("!=", Operator::NotEquals),
+            ("=", Operator::Equals),
+            ("~", Operator::Contains),
+        ]
+        .into_iter()
+        .find_map(|(separator, operator)| {
+            filter
+                .split_once(separator)
+                .map(|(column, value)| (column, operator, value))
+        })
+        .filter(|(column, _, _)| !column.is_empty())
+        .ok_or_else(|| {
+            format!(
+                "invalid filter {:?}, expected column=value, column!=value or column~text",
+                filter
+            )
+        })?;
+
+        Ok(Filter {
+            column: column.to_string(),
+            operator,
+            value: value.to_string(),
+        })
+    }
+}
+
+// How to print outputs: the format, and which rows and columns to show. Not a doc
+// comment, as clap would take it as the description of the whole program.
+#[derive(Debug, clap::Args)]
+pub struct View {
+    /// Output format [default: text, or table with --columns, --sort or --filter]
+    #[clap(long, global = true, value_enum)]
+    output: Option<Format>,
+
+    /// Output in JSON format, same as --output json
+    #[clap(long, global = true)]
+    json: bool,
+
+    /// Columns to show, e.g. macaddr,ipaddr, or all of them with `all`
+    #[clap(long, global = true, value_delimiter = ',')]
+    columns: Vec<String>,
+
+    /// Sort rows by these columns, descending if prefixed with `-`
+    #[clap(long, global = true, value_delimiter = ',', allow_hyphen_values = true)]
+    sort: Vec<String>,
+
+    /// Only show rows where column=value, column!=value or column~text
+    #[clap(long, global = true)]
+    filter: Vec<Filter>,
+}
+
+impl View {
+    pub fn format(&self) -> Format {
+        match self.output {
+            Some(format) => format,
+            None if self.json => Format::Json,
+            None if self.is_customised() => Format::Table,
+            None => Format::Text,
+        }
+    }
+
+    fn is_customised(&self) -> bool {
+        !self.columns.is_empty() || !self.sort.is_empty() || !self.filter.is_empty()
+    }
+
+    /// Indices of the rows to show, filtered and sorted.
+    fn select(&self, output: &Output) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..output.rows.len())
+            .filter(|&index| {
+                self.filter
+                    .iter()
+                    .all(|filter| filter.matches(&output.rows[index]))
+            })
+            .collect();
+
+        indices.sort_by(|&a, &b| {
+            self.sort
+                .iter()
+                .map(|column| {
+                    let (column, descending) = match column.strip_prefix('-') {
+                        Some(column) => (column, true),
+                        None => (column.as_str(), false),
+                    };
+                    let ordering = compare(output.rows[a].get(column), output.rows[b].get(column));
+                    if descending {
+                        ordering.reverse()
+                    } else {
+                        ordering
+                    }
+                })
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+
+        indices
+    }
+
+    /// The columns to show: those chosen, the output's defaults, or all of them.
+    fn columns(&self, output: &Output) -> Vec<String> {
+        match self.columns.as_slice() {
+            [all] if all == "all" => all_columns(output),
+            [] if !output.columns.is_empty() => output
+                .columns
+                .iter()
+                .map(|column| column.to_string())
+                .collect(),
+            [] => all_columns(output),
+            columns => columns.to_vec(),
+        }
+    }
+
+    /// The output as JSON, with only the chosen rows and columns if any were chosen.
+    pub fn value(&self, output: &Output) -> Value {
+        if !self.is_customised() {
+            return output.json.clone();
+        }
+
+        let items: Vec<Value> = self
+            .select(output)
+            .into_iter()
+            .map(|index| self.item(output, index))
+            .collect();
+
+        match output.is_list() {
+            true => Value::Array(items),
+            false => items.into_iter().next().unwrap_or(Value::Null),
+        }
+    }
+
+    /// An item, reduced to the chosen columns if any were chosen.
+    fn item(&self, output: &Output, index: usize) -> Value {
+        if self.columns.is_empty() || self.columns == ["all"] {
+            return output.item(index).clone();
+        }
+
+        let row = &output.rows[index];
+        Value::Object(
+            self.columns
+                .iter()
+                .map(|column| (column.clone(), row.get(column).cloned().unwrap_or_default()))
+                .collect(),
+        )
+    }
+
+    /// Render the output of a single router.
+    pub fn render(&self, output: &Output) -> String {
+        match self.format() {
+            Format::Text if !output.text.is_empty() => output.text.clone(),
+            Format::Text | Format::Table => {
+                let columns = self.columns(output);
+                let rows = self.cells(output, &columns, None);
+                table(&columns, &rows)
+            }
+            Format::Csv => {
+                let columns = self.columns(output);
+                let rows = self.cells(output, &columns, None);
+                csv(&columns, &rows)
+            }
+            Format::Json => serde_json::to_string_pretty(&self.value(output)).unwrap(),
+            Format::Yaml => yaml(&self.value(output)),
+            Format::Ndjson => self
+                .select(output)
+                .into_iter()
+                .map(|index| self.item(output, index).to_string())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+
+    /// Render the outputs of several routers as one, with a `router` column.
+    pub fn render_fleet(&self, outputs: &[(&str, &Output)]) -> String {
+        let Some((_, first)) = outputs.first() else {
+            return String::new();
+        };
+
+        match self.format() {
+            Format::Table | Format::Csv => {
+                let columns = self.columns(first);
+                let rows: Vec<Vec<String>> = outputs
+                    .iter()
+                    .flat_map(|(router, output)| self.cells(output, &columns, Some(router)))
+                    .collect();
+                let columns: Vec<String> = std::iter::once("router".to_string())
+                    .chain(columns)
+                    .collect();
+
+                match self.format() {
+                    Format::Csv => csv(&columns, &rows),
+                    _ => table(&columns, &rows),
+                }
+            }
+            Format::Ndjson => outputs
+                .iter()
+                .flat_map(|(router, output)| {
+                    self.select(output).into_iter().map(|index| {
+                        let mut line = Map::new();
+                        line.insert("router".to_string(), Value::from(*router));
+                        match self.item(output, index) {
+                            Value::Object(fields) => line.extend(fields),
+                            value => {
+                                line.insert("value".to_string(), value);
+                            }
+                        }
+                        Value::Object(line).to_string()
+                    })
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Format::Text | Format::Json | Format::Yaml => {
+                unreachable!("rendered per router")
+            }
+        }
+    }
+
+    fn cells(&self, output: &Output, columns: &[String], router: Option<&str>) -> Vec<Vec<String>> {
+        self.select(output)
+            .into_iter()
+            .map(|index| {
+                let row = &output.rows[index];
+                router
+                    .map(str::to_string)
+                    .into_iter()
+                    .chain(
+                        columns
+                            .iter()
+                            .map(|column| row.get(column).map(cell).unwrap_or_default()),
+                    )
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Every column of the output, in alphabetical order.
+fn all_columns(output: &Output) -> Vec<String> {
+    let mut columns: Vec<String> = output
+        .rows
+        .iter()
+        .flat_map(|row| row.keys().cloned())
+        .collect();
+    columns.sort();
+    columns.dedup();
+    columns
+}
+
+/// Compare cells as numbers or IP addresses where possible, otherwise as text.
+fn compare(a: Option<&Value>, b: Option<&Value>) -> Ordering {
+    let (a, b) = (
+        a.map(cell).unwrap_or_default(),
+        b.map(cell).unwrap_or_default(),
+    );
+
+    if let (Ok(a), Ok(b)) = (a.parse::<f64>(), b.parse::<f64>()) {
+        return a.total_cmp(&b);
+    }
+    if let (Ok(a), Ok(b)) = (a.parse::<IpAddr>(), b.parse::<IpAddr>()) {
+        return a.cmp(&b);
+    }
+
+    a.cmp(&b)
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        Value::Array(items) => items.iter().map(cell).collect::<Vec<_>>().join(", "),
+        value => value.to_string(),
+    }
+}
+
+fn table(columns: &[String], rows: &[Vec<String>]) -> String {
+    let widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(index, column)| {
+            rows.iter()
+                .map(|row| row[index].chars().count())
+                .chain([column.len()])
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+
+    std::iter::once(columns)
+        .chain(rows.iter().map(Vec::as_slice))
+        .map(|row| {
+            row.iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn csv(columns: &[String], rows: &[Vec<String>]) -> String {
+    let line = |row: &[String]| {
+        row.iter()
+            .map(|cell| {
+                if cell.contains([',', '"', '\n', '\r']) {
+                    format!("\"{}\"", cell.replace('"', "\"\""))
+                } else {
+                    cell.clone()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+
+    std::iter::once(line(columns))
+        .chain(rows.iter().map(|row| line(row)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn yaml(value: &Value) -> String {
+    serde_yaml::to_string(value).unwrap().trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn leases() -> Output {
+        Output::json(
+            json!([
+                { "macaddr": "82:87:EE:2A:86:AE", "ipaddr": "192.168.7.10", "hostname": "phone" },
+                { "macaddr": "36:95:D8:AD:B3:C4", "ipaddr": "192.168.7.9", "hostname": "laptop, work" },
+            ]),
+            String::new(),
+        )
+    }
+
+    fn parse(args: &[&str]) -> View {
+        #[derive(clap::Parser)]
+        struct App {
+            #[clap(flatten)]
+            view: View,
+        }
+
+        <App as clap::Parser>::parse_from(std::iter::once("tonik").chain(args.iter().copied())).view
+    }
+
+    #[test]
+    fn test_table() {
+        let view = parse(&["--columns", "ipaddr,hostname", "--sort", "ipaddr"]);
+        assert_eq!(view.format(), Format::Table);
+        assert_eq!(
+            view.render(&leases()),
+            "ipaddr        hostname\n192.168.7.9   laptop, work\n192.168.7.10  phone"
+        );
+    }
+
+    #[test]
+    fn test_csv() {
+        let view = parse(&[
+            "--output",
+            "csv",
+            "--columns",
+            "hostname",
+            "--sort",
+            "-hostname",
+        ]);
+        assert_eq!(view.render(&leases()), "hostname\nphone\n\"laptop, work\"");
+    }
+
+    #[test]
+    fn test_filter() {
+        let view = parse(&[
+            "--output",
+            "ndjson",
+            "--filter",
+            "hostname~LAPTOP",
+            "--columns",
+            "ipaddr",
+        ]);
+        assert_eq!(view.render(&leases()), r#"{"ipaddr":"192.168.7.9"}"#);
+
+        let view = parse(&["--json", "--filter", "hostname!=phone"]);
+        assert_eq!(view.value(&leases())[0]["macaddr"], "36:95:D8:AD:B3:C4");
+
+        assert!("hostname".parse::<Filter>().is_err());
+        assert!("=phone".parse::<Filter>().is_err());
+    }
+
+    #[test]
+    fn test_yaml() {
+        let view = parse(&[
+            "--output",
+            "yaml",
+            "--columns",
+            "hostname",
+            "--filter",
+            "ipaddr=192.168.7.10",
+        ]);
+        assert_eq!(view.render(&leases()), "- hostname: phone");
+    }
+}
@@ -0,0 +1,127 @@
+use std::{
+    collections::HashMap,
+    io::Write,
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use tonik::TeltonikaClient;
+
+/// Cached tokens closer to expiry than this are not reused, so they don't run out
+/// halfway through a command.
+const MINIMUM_LIFETIME: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct CachedSession {
+    token: String,
+    /// Seconds since the Unix epoch.
+    expires_at: u64,
+}
+
+/// Session tokens kept between runs, so each command doesn't log in again.
+///
+/// Tokens are keyed by username and API URL and stored in
+/// `$XDG_CACHE_HOME/tonik/sessions.json`, or `~/.cache/tonik/sessions.json`.
+#[derive(Debug, Default)]
+pub struct SessionCache {
+    path: Option<PathBuf>,
+    sessions: Mutex<HashMap<String, CachedSession>>,
+}
+
+impl SessionCache {
+    /// Load the cache, starting afresh if it is missing or unreadable.
+    pub fn load() -> Self {
+        let path = cache_dir().map(|dir| dir.join("sessions.json"));
+        let sessions = path
+            .as_ref()
+            .and_then(|path| std::fs::read(path).ok())
+            .and_then(|contents| serde_json::from_slice(&contents).ok())
+            .unwrap_or_default();
+
+        SessionCache {
+            path,
+            sessions: Mutex::new(sessions),
+        }
+    }
+
+    /// A cache that neither reads nor writes the file.
+    pub fn disabled() -> Self {
+        SessionCache::default()
+    }
+
+    /// A token for `username` on the client's router that is still valid for a while,
+    /// with its remaining lifetime.
+    pub fn get(&self, username: &str, client: &TeltonikaClient) -> Option<(String, Duration)> {
+        let sessions = self.sessions.lock().unwrap();
+        let session = sessions.get(&key(username, client))?;
+        let expires_in = Duration::from_secs(session.expires_at.saturating_sub(now()));
+
+        (expires_in > MINIMUM_LIFETIME).then(|| (session.token.clone(), expires_in))
+    }
+
+    /// Remember the client's current session.
+    pub async fn insert(&self, username: &str, client: &TeltonikaClient) {
+        let (Some(token), Some(expires_in)) = (
+            client.session_token().await,
+            client.session_expires_in().await,
+        ) else {
+            return;
+        };
+
+        self.sessions.lock().unwrap().insert(
+            key(username, client),
+            CachedSession {
+                token,
+                expires_at: now() + expires_in.as_secs(),
+            },
+        );
+    }
+
+    pub fn remove(&self, username: &str, client: &TeltonikaClient) {
+        self.sessions.lock().unwrap().remove(&key(username, client));
+    }
+
+    /// Write unexpired sessions back to the cache file, readable only by the user.
+    pub fn save(&self) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let mut sessions = self.sessions.lock().unwrap().clone();
+        let now = now();
+        sessions.retain(|_, session| session.expires_at > now);
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        let mut file = options.open(path)?;
+        file.write_all(&serde_json::to_vec(&sessions)?)
+    }
+}
+
+fn key(username: &str, client: &TeltonikaClient) -> String {
+    format!("{}@{}", username, client.base_url())
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// `$XDG_CACHE_HOME/tonik`, or `~/.cache/tonik`.
+fn cache_dir() -> Option<PathBuf> {
+    match std::env::var_os("XDG_CACHE_HOME") {
+        Some(dir) if !dir.is_empty() => Some(PathBuf::from(dir).join("tonik")),
+        _ => std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache/tonik")),
+    }
+}
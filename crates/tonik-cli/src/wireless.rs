@@ -0,0 +1,58 @@
+use std::{collections::HashMap, time::Duration};
+
+use tonik::{MacAddress, Response, TeltonikaClient, WirelessClient};
+
+use crate::{Failure, Output};
+
+pub async fn interfaces(client: &TeltonikaClient) -> Result<Output, Failure> {
+    let interfaces = client
+        .wireless_interfaces_status()
+        .await
+        .and_then(Response::into_result)?;
+
+    Ok(Output::many(&interfaces, String::new()))
+}
+
+/// Associated clients with their signal strength, named from DHCP leases.
+pub async fn clients(client: &TeltonikaClient) -> Result<Output, Failure> {
+    let clients = WirelessClient::fetch(client).await?;
+
+    Ok(Output::many(&clients, String::new()))
+}
+
+/// Print clients as they connect and disconnect, and whenever their signal moves by
+/// at least `threshold` dB from the last one printed, forever.
+pub async fn watch(client: TeltonikaClient, interval: Duration, threshold: i64) {
+    let mut known: HashMap<MacAddress, WirelessClient> = HashMap::new();
+    let mut ticker = tokio::time::interval(interval);
+
+    loop {
+        ticker.tick().await;
+
+        let clients = match WirelessClient::fetch(&client).await {
+            Ok(clients) => clients,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                continue;
+            }
+        };
+
+        let mut current = HashMap::new();
+        for wireless_client in clients {
+            match known.remove(&wireless_client.mac) {
+                None => println!("Connected: {}", wireless_client),
+                Some(last) if (wireless_client.signal - last.signal).abs() < threshold => {
+                    current.insert(last.mac, last);
+                    continue;
+                }
+                Some(_) => println!("Signal: {}", wireless_client),
+            }
+            current.insert(wireless_client.mac, wireless_client);
+        }
+
+        for departed in known.values() {
+            println!("Disconnected: {}", departed);
+        }
+        known = current;
+    }
+}
@@ -0,0 +1,712 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    process::{Command, Output, Stdio},
+};
+
+use tonik_mock::{MockRouter, DEFAULT_PASSWORD, GPS_POSITION_STATUS, SIM_CARDS};
+
+/// The binary, with config and cached sessions kept in `home` rather than the user's.
+fn command(home: &str) -> Command {
+    let home = std::env::temp_dir().join(format!("tonik-cli-{}-{}", home, std::process::id()));
+    let mut command = Command::new(env!("CARGO_BIN_EXE_tonik"));
+    command
+        .env("XDG_CONFIG_HOME", home.join("config"))
+        .env("XDG_CACHE_HOME", home.join("cache"))
+        .env_remove("TELTONIKA_HOST")
+        .env_remove("TELTONIKA_USERNAME")
+        .env_remove("TELTONIKA_PASSWORD")
+        .env_remove("TONIK_PROFILE")
+        .env_remove("TONIK_CONFIG")
+        .env_remove("TONIK_INVENTORY");
+    command
+}
+
+fn tonik(router: &MockRouter, args: &[&str]) -> Output {
+    command(&router.port().to_string())
+        .args(["--host", &router.host()])
+        .args(["--port", &router.port().to_string()])
+        .args(["--http", "--password", DEFAULT_PASSWORD])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// Run the binary with `stdin` piped in, for commands that read secrets from it.
+fn tonik_with_stdin(router: &MockRouter, args: &[&str], stdin: &str) -> Output {
+    let mut child = command(&router.port().to_string())
+        .args(["--host", &router.host()])
+        .args(["--port", &router.port().to_string()])
+        .args(["--http", "--password", DEFAULT_PASSWORD])
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let mut pipe = child.stdin.take().unwrap();
+    pipe.write_all(stdin.as_bytes()).unwrap();
+    drop(pipe);
+
+    child.wait_with_output().unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_dhcp_ipv4_status_json() {
+    let router = MockRouter::start().await.unwrap();
+
+    let output = tonik(&router, &["--json", "dhcp", "ipv4", "status"]);
+
+    assert!(output.status.success());
+    let leases: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(leases[0]["macaddr"], "82:87:EE:2A:86:AE");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_firmware_device_status() {
+    let router = MockRouter::start().await.unwrap();
+
+    let output = tonik(&router, &["firmware", "device", "status"]);
+
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("RUTX_R_00.07.06.3"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_firmware_check_and_download() {
+    let router = MockRouter::start().await.unwrap();
+
+    let output = tonik(&router, &["firmware", "check"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Available version: RUTX_R_00.07.07.1 (21.5 MB)"));
+
+    let output = tonik(&router, &["firmware", "download"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Download complete"));
+    assert!(router.has_staged_firmware());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_firmware_download_that_never_starts() {
+    let router = MockRouter::start().await.unwrap();
+    router.stall_firmware_downloads();
+
+    let output = tonik(&router, &["firmware", "download"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("didn't start downloading"));
+    assert!(!router.has_staged_firmware());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_firmware_upgrade() {
+    let router = MockRouter::start().await.unwrap();
+    let path = std::env::temp_dir().join(format!("tonik-cli-firmware-{}.bin", std::process::id()));
+    std::fs::write(&path, b"firmware image").unwrap();
+    let file = path.to_str().unwrap();
+
+    // Without --yes the upgrade needs confirmation, and stdin is empty.
+    let output = tonik(&router, &["firmware", "upgrade", "--file", file]);
+    assert!(!output.status.success());
+    assert!(router.firmware_upgrades().is_empty());
+
+    let output = tonik(
+        &router,
+        &[
+            "firmware",
+            "upgrade",
+            "--file",
+            file,
+            "--keep-settings",
+            "--yes",
+        ],
+    );
+    std::fs::remove_file(&path).ok();
+    assert!(output.status.success());
+    assert_eq!(
+        router.firmware_upgrades(),
+        vec![serde_json::json!({ "data": { "keep_settings": "1" } })]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_wrong_password_exits_with_error() {
+    let router = MockRouter::start().await.unwrap();
+    router.set_credentials("admin", "something-else");
+
+    let output = tonik(&router, &["gps", "position"]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("Error: "));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_output_formats() {
+    let router = MockRouter::start().await.unwrap();
+
+    let output = tonik(&router, &["ip-neighbors", "status"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("dest "));
+    assert!(stdout.contains("192.168.7.120  82:87:EE:2A:86:AE  br-lan  true"));
+
+    let output = tonik(
+        &router,
+        &[
+            "dhcp",
+            "ipv4",
+            "status",
+            "--output",
+            "csv",
+            "--columns",
+            "ipaddr,hostname",
+            "--sort",
+            "-expires",
+        ],
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "ipaddr,hostname\n192.168.7.120,Bens-iPhone\n192.168.7.154,raspberrypi\n"
+    );
+
+    let output = tonik(
+        &router,
+        &[
+            "dhcp",
+            "ipv4",
+            "status",
+            "--output",
+            "ndjson",
+            "--filter",
+            "hostname~rasp",
+        ],
+    );
+    let lines: Vec<serde_json::Value> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0]["macaddr"], "B8:27:EB:FB:2A:45");
+
+    let output = tonik(&router, &["gps", "position", "--output", "yaml"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("satellites: "));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_gps_position_without_fix() {
+    let router = MockRouter::start().await.unwrap();
+    router.set_fixture(
+        GPS_POSITION_STATUS,
+        serde_json::json!({
+            "accuracy": "",
+            "fix_status": "0",
+            "altitude": "",
+            "timestamp": "",
+            "satellites": "0",
+            "longitude": "",
+            "latitude": "",
+            "angle": "",
+            "utc_timestamp": ""
+        }),
+    );
+
+    let output = tonik(&router, &["gps", "position"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "No GPS fix (fix status 0)"
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_modem_status() {
+    let router = MockRouter::start().await.unwrap();
+
+    let output = tonik(&router, &["modem", "status"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Vodafone UK"));
+    assert!(stdout.contains("-95 dBm"));
+
+    let output = tonik(&router, &["modem", "status", "--json"]);
+    assert!(output.status.success());
+    let modems: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(modems[1]["conntype"], "5G-NSA");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_interfaces() {
+    let router = MockRouter::start().await.unwrap();
+
+    let output = tonik(&router, &["interfaces"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("* wan (dhcp on eth1): up 3h 12m, 100.64.0.2/10 via 100.64.0.1"));
+    assert!(stdout.contains("  wwan (dhcp on wlan1)"));
+    assert!(stdout.ends_with("Traffic goes out through wan\n"));
+
+    router.set_interface_up("wan", false);
+    let output = tonik(
+        &router,
+        &["interfaces", "--filter", "active=true", "--output", "csv"],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout.lines().nth(1).unwrap(),
+        "true,wwan,dhcp,true,wlan1,10.20.0.45/16,10.20.0.1,2"
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_failover() {
+    let router = MockRouter::start().await.unwrap();
+
+    let output = tonik(&router, &["failover", "status"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(
+        lines[0],
+        "* 1 wan: online, 1.1.1.1 up 38 ms, 8.8.8.8 up 41 ms"
+    );
+    assert!(lines[1].starts_with("  2 wwan: online"));
+
+    let output = tonik(&router, &["failover", "prefer", "wwan"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("Preferring wwan\n\n* 1 wwan: online"));
+    assert!(stdout.contains("  2 wan: online"));
+
+    router.set_failover_status("wwan", "offline");
+    let output = tonik(&router, &["failover", "status", "--json"]);
+    let members: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(members[0]["interface"], "wwan");
+    assert_eq!(members[0]["status"], "offline");
+    assert_eq!(members[1]["active"], true);
+
+    let output = tonik(&router, &["failover", "prefer", "lan"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("lan is not a failover interface"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_data_usage() {
+    let router = MockRouter::start().await.unwrap();
+
+    // The mock uses 1 GB a day on SIM 1, so it is always heading over its 20 GB.
+    let output = tonik(&router, &["data-usage"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert!(lines[0].starts_with("SIM 1 (2-1): "));
+    assert!(lines[0].contains(" of 20.0 GB "));
+    assert!(lines[0].contains(", 1.0 GB today, projected "));
+    assert!(lines[0].ends_with(" (over the limit)"));
+    assert!(lines[1].starts_with("SIM 2 (2-1): "));
+    assert!(lines[1].contains(", 50.0 MB today, "));
+
+    let output = tonik(&router, &["data-usage", "--json"]);
+    let usage: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(usage[0]["limit"], 20_000_000_000u64);
+    assert_eq!(usage[0]["today"], 1_000_000_000u64);
+    assert_eq!(usage[1]["limit"], serde_json::Value::Null);
+
+    let output = tonik(&router, &["data-usage", "--by", "day"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.lines().count(), 120);
+    assert!(stdout
+        .lines()
+        .next()
+        .unwrap()
+        .ends_with(": 1.0 GB, rx 800.0 MB, tx 200.0 MB"));
+
+    let output = tonik(&router, &["data-usage", "--by", "year"]);
+    assert!(!output.status.success());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_sim() {
+    let router = MockRouter::start().await.unwrap();
+
+    let output = tonik(&router, &["sim", "status"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(
+        lines[0],
+        "* SIM 1 (2-1): inserted, Vodafone UK, ICCID 8944110068123456789"
+    );
+    assert!(lines[1].starts_with("  SIM 2 (2-1): inserted, EE"));
+    assert_eq!(
+        lines[3],
+        "Switch from SIM 1 (2-1) when roaming, with no network or below -110 dBm"
+    );
+
+    let output = tonik(&router, &["sim", "switch"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("Switched to SIM 2 (2-1)"));
+
+    let output = tonik(&router, &["sim", "switch", "2"]);
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("Already using SIM 2 (2-1)"));
+
+    let output = tonik(&router, &["sim", "status", "--json"]);
+    let slots: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(slots[1]["active"], true);
+    assert_eq!(slots[0]["rules"][0]["roaming"], true);
+
+    let output = tonik(&router, &["sim", "switch", "3"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("2-1 has no SIM 3"));
+
+    let output = tonik(&router, &["sim", "apn", "internet", "--sim", "1"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "SIM 1 (2-1): APN internet"
+    );
+
+    let output = tonik(&router, &["sim", "apn"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout.lines().collect::<Vec<_>>(),
+        [
+            "SIM 1 (2-1): APN internet",
+            "SIM 2 (2-1): APN everywhere, pap as eesecure, PIN set"
+        ]
+    );
+
+    // Secrets stay out of the output.
+    let output = tonik(&router, &["sim", "apn", "--json"]);
+    let cards: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(cards[1]["username"], "eesecure");
+    assert!(cards[1].get("pincode").is_none());
+    assert!(cards[1].get("password").is_none());
+
+    let output = tonik(
+        &router,
+        &["sim", "apn", "everywhere", "--sim", "2", "--auth", "none"],
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "SIM 2 (2-1): APN everywhere, PIN set"
+    );
+    let cards = router.fixture(SIM_CARDS).unwrap();
+    assert_eq!(cards[1]["username"], "");
+    assert_eq!(cards[1]["password"], "");
+
+    let output = tonik(&router, &["sim", "apn", "internet"]);
+    assert!(!output.status.success());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_sim_pin() {
+    let router = MockRouter::start().await.unwrap();
+
+    let output = tonik_with_stdin(&router, &["sim", "pin", "unlock"], "1234\n");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("2-1 has no SIM waiting for a PIN"));
+
+    router.lock_sim(2, "1234");
+    let output = tonik_with_stdin(&router, &["sim", "pin", "unlock"], "1234\n");
+    assert!(output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stdout).starts_with("Unlocked SIM 2 (2-1): inserted, EE")
+    );
+
+    // The router enters 1234 for SIM 2, so that changes along with the card.
+    let output = tonik_with_stdin(
+        &router,
+        &["sim", "pin", "change", "--sim", "2"],
+        "1234\n4321\n",
+    );
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "Changed the PIN of SIM 2 (2-1)"
+    );
+    let cards = router.fixture(SIM_CARDS).unwrap();
+    assert_eq!(cards[1]["pincode"], "4321");
+
+    let output = tonik_with_stdin(&router, &["sim", "pin", "set", "--sim", "2"], "\n");
+    assert!(output.status.success());
+    let cards = router.fixture(SIM_CARDS).unwrap();
+    assert_eq!(cards[1]["pincode"], "");
+
+    let output = tonik_with_stdin(&router, &["sim", "pin", "set", "--sim", "3"], "0000\n");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("2-1 has no SIM 3"));
+
+    // The new PIN is missing, so nothing is sent to the router.
+    let output = tonik_with_stdin(&router, &["sim", "pin", "change", "--sim", "2"], "0000\n");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no new pin on stdin"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_ip_neighbors_watch_status() {
+    let router = MockRouter::start().await.unwrap();
+
+    let mut child = command("watch")
+        .args(["--host", &router.host()])
+        .args(["--port", &router.port().to_string()])
+        .args(["--http", "--password", DEFAULT_PASSWORD])
+        .args(["ip-neighbors", "watch-status"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+
+    // Reachable fixture neighbours are reported straight away.
+    assert_eq!(lines.next().unwrap().unwrap(), "Added: 82:87:EE:2A:86:AE");
+
+    router.add_neighbor("AA:BB:CC:DD:EE:FF", "192.168.7.200");
+    assert_eq!(lines.next().unwrap().unwrap(), "Added: AA:BB:CC:DD:EE:FF");
+
+    router.remove_neighbor("AA:BB:CC:DD:EE:FF");
+    assert_eq!(lines.next().unwrap().unwrap(), "Removed: AA:BB:CC:DD:EE:FF");
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_wireless_clients() {
+    let router = MockRouter::start().await.unwrap();
+    router.set_wireless_signal("B8:27:EB:FB:2A:45", -72);
+
+    let output = tonik(&router, &["wireless", "clients"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[1].contains("Bens-iPhone") && lines[1].contains("excellent"));
+    assert!(lines[2].contains("raspberrypi") && lines[2].contains("poor"));
+
+    let output = tonik(&router, &["wireless", "interfaces", "--json"]);
+    let interfaces: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(interfaces[0]["ssid"], "Boat");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_wireless_watch() {
+    let router = MockRouter::start().await.unwrap();
+
+    let mut child = command("wireless-watch")
+        .args(["--host", &router.host()])
+        .args(["--port", &router.port().to_string()])
+        .args(["--http", "--password", DEFAULT_PASSWORD])
+        .args(["wireless", "watch", "--interval", "1"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+
+    assert_eq!(
+        lines.next().unwrap().unwrap(),
+        "Connected: 82:87:EE:2A:86:AE Bens-iPhone -48 dBm (excellent) on Boat"
+    );
+
+    // Small changes are ignored.
+    router.set_wireless_signal("82:87:EE:2A:86:AE", -50);
+    std::thread::sleep(std::time::Duration::from_millis(1500));
+    router.set_wireless_signal("82:87:EE:2A:86:AE", -63);
+    assert_eq!(
+        lines.next().unwrap().unwrap(),
+        "Signal: 82:87:EE:2A:86:AE Bens-iPhone -63 dBm (fair) on Boat"
+    );
+
+    router.disassociate("82:87:EE:2A:86:AE");
+    assert_eq!(
+        lines.next().unwrap().unwrap(),
+        "Disconnected: 82:87:EE:2A:86:AE Bens-iPhone -63 dBm (fair) on Boat"
+    );
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_firewall_port_forward() {
+    let router = MockRouter::start().await.unwrap();
+
+    let output = tonik(
+        &router,
+        &[
+            "firewall",
+            "port-forward",
+            "add",
+            "--ext",
+            "8080",
+            "--to",
+            "192.168.7.20:80",
+            "--name",
+            "contractor",
+        ],
+    );
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("Added cfg"));
+    assert!(stdout.contains("contractor: tcp/udp 8080 -> 192.168.7.20:80"));
+
+    let output = tonik(&router, &["firewall", "port-forward", "list", "--json"]);
+    let forwards: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(forwards[1]["name"], "contractor");
+    assert_eq!(forwards[1]["dest_port"], "80");
+
+    let output = tonik(&router, &["firewall", "port-forward", "disable", "camera"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).ends_with("(disabled)\n"));
+
+    let output = tonik(
+        &router,
+        &["firewall", "port-forward", "remove", "contractor"],
+    );
+    assert!(output.status.success());
+    let output = tonik(
+        &router,
+        &["firewall", "port-forward", "remove", "contractor"],
+    );
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no port forward"));
+
+    let output = tonik(
+        &router,
+        &[
+            "firewall",
+            "port-forward",
+            "add",
+            "--ext",
+            "80",
+            "--to",
+            "camera:80",
+        ],
+    );
+    assert!(!output.status.success());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_fleet_target() {
+    let boat_1 = MockRouter::start().await.unwrap();
+    let boat_2 = MockRouter::start().await.unwrap();
+    boat_2.set_credentials("admin", "something-else");
+    let office = MockRouter::start().await.unwrap();
+
+    let path =
+        std::env::temp_dir().join(format!("tonik-cli-inventory-{}.toml", std::process::id()));
+    let router = |name: &str, router: &MockRouter, tags: &str| {
+        format!(
+            "[[router]]\nname = \"{}\"\nhost = \"{}\"\nport = {}\nhttp = true\ntags = [{}]\n\n",
+            name,
+            router.host(),
+            router.port(),
+            tags
+        )
+    };
+    std::fs::write(
+        &path,
+        router("boat-1", &boat_1, "\"boats\"")
+            + &router("boat-2", &boat_2, "\"boats\"")
+            + &router("office", &office, ""),
+    )
+    .unwrap();
+
+    let fleet = |args: &[&str]| {
+        command("fleet")
+            .args(["--inventory", path.to_str().unwrap()])
+            .args(["--password", DEFAULT_PASSWORD])
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    let output = fleet(&[
+        "--json",
+        "firmware",
+        "device",
+        "status",
+        "--target",
+        "tag:boats",
+    ]);
+    assert!(!output.status.success());
+    let routers: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(routers["boat-1"]["result"]["version"], "RUTX_R_00.07.06.3");
+    assert!(routers["boat-2"]["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("unauthorized"));
+    assert!(routers.get("office").is_none());
+
+    let output = fleet(&[
+        "firmware",
+        "device",
+        "status",
+        "--target",
+        "tag:boats",
+        "--output",
+        "csv",
+        "--columns",
+        "version",
+    ]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "router,version\nboat-1,RUTX_R_00.07.06.3\n"
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("boat-2: Error: unauthorized"));
+
+    let output = fleet(&[
+        "firmware", "device", "status", "--target", "boat-1", "--target", "office",
+    ]);
+    std::fs::remove_file(&path).ok();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.lines().any(|line| line.starts_with("boat-1  ")));
+    assert!(stdout.lines().any(|line| line.starts_with("office  ")));
+    assert!(stdout.contains("RUTX_R_00.07.06.3"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_profile_and_session_cache() {
+    let router = MockRouter::start().await.unwrap();
+
+    let home = std::env::temp_dir().join(format!("tonik-cli-profile-{}", std::process::id()));
+    std::fs::create_dir_all(home.join("config/tonik")).unwrap();
+    std::fs::write(
+        home.join("config/tonik/config.toml"),
+        format!(
+            "[profile.mock]\nhost = \"{}\"\nport = {}\nhttp = true\npassword_cmd = \"echo {}\"\n",
+            router.host(),
+            router.port(),
+            DEFAULT_PASSWORD
+        ),
+    )
+    .unwrap();
+
+    let run = || {
+        command("profile")
+            .args(["--profile", "mock", "firmware", "device", "status"])
+            .output()
+            .unwrap()
+    };
+
+    let output = run();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("RUTX_R_00.07.06.3"));
+
+    // The second run reuses the cached session instead of logging in.
+    assert!(run().status.success());
+    assert_eq!(router.login_count(), 1);
+
+    // An expired session is replaced by logging in again.
+    router.expire_tokens();
+    assert!(run().status.success());
+    assert_eq!(router.login_count(), 2);
+
+    let output = command("profile")
+        .args(["--profile", "office", "gps", "position"])
+        .output()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no profile named \"office\""));
+    std::fs::remove_dir_all(&home).ok();
+}
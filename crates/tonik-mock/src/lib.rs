@@ -0,0 +1,1095 @@
+//! An offline mock of the Teltonika RutOS API.
+//!
+//! [`MockRouter`] serves the API over plain HTTP on a random local port from
+//! fixture JSON. Its state can be scripted from tests: devices can appear and
+//! disappear, SMS messages can arrive and session tokens can expire.
+
+use std::{
+    collections::HashMap,
+    io,
+    net::SocketAddr,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    body::Bytes,
+    extract::{Path, State as AxumState},
+    http::{header, HeaderMap, StatusCode, Uri},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::{Days, Local, NaiveDate};
+use serde_json::{json, Value};
+use tokio::{net::TcpListener, task::JoinHandle};
+
+pub const DEFAULT_USERNAME: &str = "admin";
+pub const DEFAULT_PASSWORD: &str = "admin01";
+
+pub const DATA_USAGE_LIMITS: &str = "/data_usage/limits/config";
+pub const DATA_USAGE_STATUS: &str = "/data_usage/status";
+pub const DHCP_LEASES_IPV4: &str = "/dhcp/leases/ipv4/status";
+pub const DHCP_LEASES_IPV6: &str = "/dhcp/leases/ipv6/status";
+pub const FAILOVER_INTERFACES: &str = "/failover/interfaces/config";
+pub const FAILOVER_STATUS: &str = "/failover/status";
+pub const FIREWALL_PORT_FORWARDS: &str = "/firewall/port_forwards/config";
+pub const FIREWALL_TRAFFIC_RULES: &str = "/firewall/traffic_rules/config";
+pub const FIREWALL_ZONES: &str = "/firewall/zones/config";
+pub const FIRMWARE_DEVICE_STATUS: &str = "/firmware/device/status";
+pub const FIRMWARE_DEVICE_UPDATES_STATUS: &str = "/firmware/device/updates/status";
+pub const GPS_POSITION_STATUS: &str = "/gps/position/status";
+pub const INTERFACES_STATUS: &str = "/interfaces/status";
+pub const IP_NEIGHBORS_IPV4: &str = "/ip_neighbors/ipv4/status";
+pub const MESSAGES_STATUS: &str = "/messages/status";
+pub const MODEMS_STATUS: &str = "/modems/status";
+pub const SIM_CARDS: &str = "/sim/cards/config";
+pub const SIM_RULES: &str = "/sim/rules/config";
+pub const SIM_STATUS: &str = "/sim/status";
+pub const WIRELESS_DEVICES_STATUS: &str = "/wireless/devices/status";
+pub const WIRELESS_INTERFACES_STATUS: &str = "/wireless/interfaces/status";
+
+const FIXTURES: &[(&str, &str)] = &[
+    (
+        DATA_USAGE_LIMITS,
+        include_str!("../fixtures/data_usage_limits_config.json"),
+    ),
+    (
+        DHCP_LEASES_IPV4,
+        include_str!("../fixtures/dhcp_leases_ipv4.json"),
+    ),
+    (
+        DHCP_LEASES_IPV6,
+        include_str!("../fixtures/dhcp_leases_ipv6.json"),
+    ),
+    (
+        FAILOVER_INTERFACES,
+        include_str!("../fixtures/failover_interfaces_config.json"),
+    ),
+    (
+        FAILOVER_STATUS,
+        include_str!("../fixtures/failover_status.json"),
+    ),
+    (
+        FIREWALL_PORT_FORWARDS,
+        include_str!("../fixtures/firewall_port_forwards.json"),
+    ),
+    (
+        FIREWALL_TRAFFIC_RULES,
+        include_str!("../fixtures/firewall_traffic_rules.json"),
+    ),
+    (
+        FIREWALL_ZONES,
+        include_str!("../fixtures/firewall_zones.json"),
+    ),
+    (
+        FIRMWARE_DEVICE_STATUS,
+        include_str!("../fixtures/firmware_device_status.json"),
+    ),
+    (
+        FIRMWARE_DEVICE_UPDATES_STATUS,
+        include_str!("../fixtures/firmware_device_updates_status.json"),
+    ),
+    (
+        GPS_POSITION_STATUS,
+        include_str!("../fixtures/gps_position_status.json"),
+    ),
+    (
+        INTERFACES_STATUS,
+        include_str!("../fixtures/interfaces_status.json"),
+    ),
+    (
+        IP_NEIGHBORS_IPV4,
+        include_str!("../fixtures/ip_neighbors_ipv4.json"),
+    ),
+    (MESSAGES_STATUS, include_str!("../fixtures/messages.json")),
+    (
+        MODEMS_STATUS,
+        include_str!("../fixtures/modems_status.json"),
+    ),
+    (SIM_CARDS, include_str!("../fixtures/sim_cards_config.json")),
+    (SIM_RULES, include_str!("../fixtures/sim_rules_config.json")),
+    (SIM_STATUS, include_str!("../fixtures/sim_status.json")),
+    (
+        WIRELESS_DEVICES_STATUS,
+        include_str!("../fixtures/wireless_devices_status.json"),
+    ),
+    (
+        WIRELESS_INTERFACES_STATUS,
+        include_str!("../fixtures/wireless_interfaces_status.json"),
+    ),
+];
+
+/// A running mock router. The server is stopped when this is dropped.
+pub struct MockRouter {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    server: JoinHandle<()>,
+}
+
+struct State {
+    username: String,
+    password: String,
+    token_lifetime: Duration,
+    tokens: HashMap<String, Instant>,
+    logins: usize,
+    fixtures: HashMap<String, Value>,
+    sent_messages: Vec<Value>,
+    next_message_id: u64,
+    next_section_id: u64,
+    firmware_download: Option<u8>,
+    firmware_download_stalled: bool,
+    firmware_upload_delay: Option<Duration>,
+    /// Modems whose messages can't be deleted.
+    undeletable_messages: Vec<String>,
+    staged_firmware: bool,
+    firmware_upgrades: Vec<Value>,
+    /// PINs of SIM cards by modem and slot.
+    sim_pins: HashMap<(String, String), String>,
+}
+
+type SharedState = Arc<Mutex<State>>;
+
+impl MockRouter {
+    /// Start a mock router on a random port of `127.0.0.1`.
+    pub async fn start() -> io::Result<Self> {
+        let mut fixtures: HashMap<String, Value> = FIXTURES
+            .iter()
+            .map(|(path, fixture)| {
+                let value = serde_json::from_str(fixture).expect("Invalid fixture JSON");
+                (path.to_string(), value)
+            })
+            .collect();
+        fixtures.insert(
+            DATA_USAGE_STATUS.to_string(),
+            data_usage(Local::now().date_naive()),
+        );
+
+        let state = Arc::new(Mutex::new(State {
+            username: DEFAULT_USERNAME.to_string(),
+            password: DEFAULT_PASSWORD.to_string(),
+            token_lifetime: Duration::from_secs(299),
+            tokens: HashMap::new(),
+            logins: 0,
+            fixtures,
+            sent_messages: Vec::new(),
+            next_message_id: 100,
+            next_section_id: 0x1092bd,
+            firmware_download: None,
+            firmware_download_stalled: false,
+            firmware_upload_delay: None,
+            undeletable_messages: Vec::new(),
+            staged_firmware: false,
+            firmware_upgrades: Vec::new(),
+            sim_pins: HashMap::new(),
+        }));
+
+        let app = Router::new()
+            .route("/api/login", post(login))
+            .route("/api/session/status", get(session_status))
+            .route(
+                "/api/firmware/actions/fota_download",
+                post(firmware_actions_fota_download),
+            )
+            .route(
+                "/api/firmware/device/progress/status",
+                get(firmware_device_progress_status),
+            )
+            .route(
+                "/api/firmware/actions/upload_device_firmware",
+                post(firmware_actions_upload_device_firmware),
+            )
+            .route(
+                "/api/firmware/actions/upgrade",
+                post(firmware_actions_upgrade),
+            )
+            .route(
+                "/api/{module}/{section}/config",
+                get(fixture).post(config_create),
+            )
+            .route(
+                "/api/{module}/{section}/config/{id}",
+                get(config_get).put(config_update).delete(config_delete),
+            )
+            .route("/api/interfaces/status/{id}", get(interface_status))
+            .route("/api/messages/actions/send", post(messages_send))
+            .route(
+                "/api/messages/actions/remove_messages",
+                post(messages_remove),
+            )
+            .route("/api/messages/actions/read_messages", post(messages_read))
+            .route("/api/sim/actions/switch", post(sim_switch))
+            .route("/api/sim/actions/unlock", post(sim_unlock))
+            .route("/api/sim/actions/change_pin", post(sim_change_pin))
+            .fallback(get(fixture))
+            .with_state(state.clone());
+
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let server = tokio::spawn(async move {
+            axum::serve(listener, app).await.ok();
+        });
+
+        Ok(MockRouter {
+            addr,
+            state,
+            server,
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn host(&self) -> String {
+        self.addr.ip().to_string()
+    }
+
+    pub fn port(&self) -> u16 {
+        self.addr.port()
+    }
+
+    /// The API base URL, e.g. `http://127.0.0.1:41234/api`.
+    pub fn base_url(&self) -> String {
+        format!("http://{}/api", self.addr)
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+
+    /// Change the username and password accepted by `/login`.
+    pub fn set_credentials(&self, username: &str, password: &str) {
+        let mut state = self.state();
+        state.username = username.to_string();
+        state.password = password.to_string();
+    }
+
+    /// Lifetime of tokens issued by subsequent logins.
+    pub fn set_token_lifetime(&self, lifetime: Duration) {
+        self.state().token_lifetime = lifetime;
+    }
+
+    /// Invalidate every token issued so far.
+    pub fn expire_tokens(&self) {
+        self.state().tokens.clear();
+    }
+
+    /// Number of successful logins.
+    pub fn login_count(&self) -> usize {
+        self.state().logins
+    }
+
+    /// Replace the data served for a GET endpoint, e.g. [`GPS_POSITION_STATUS`].
+    pub fn set_fixture(&self, path: &str, data: Value) {
+        self.state().fixtures.insert(path.to_string(), data);
+    }
+
+    /// The data currently served for a GET endpoint.
+    pub fn fixture(&self, path: &str) -> Option<Value> {
+        self.state().fixtures.get(path).cloned()
+    }
+
+    fn push(&self, path: &str, item: Value) {
+        let mut state = self.state();
+        let list = state
+            .fixtures
+            .entry(path.to_string())
+            .or_insert_with(|| json!([]));
+        list.as_array_mut()
+            .expect("Fixture is not a list")
+            .push(item);
+    }
+
+    fn retain(&self, path: &str, keep: impl Fn(&Value) -> bool) {
+        if let Some(list) = self
+            .state()
+            .fixtures
+            .get_mut(path)
+            .and_then(Value::as_array_mut)
+        {
+            list.retain(keep);
+        }
+    }
+
+    /// A reachable device appears in the IPv4 neighbour table.
+    pub fn add_neighbor(&self, mac: &str, ip: &str) {
+        self.push(
+            IP_NEIGHBORS_IPV4,
+            json!({
+                "proxy": "0",
+                "stale": "0",
+                "noarp": "0",
+                "incomplete": "0",
+                "delay": "0",
+                "family": "ipv4",
+                "reachable": "1",
+                "mac": mac,
+                "dev": "br-lan",
+                "router": "0",
+                "dest": ip,
+                "probe": "0",
+                "failed": "0",
+                "permanent": "0",
+            }),
+        );
+    }
+
+    /// A device disappears from the IPv4 neighbour table.
+    pub fn remove_neighbor(&self, mac: &str) {
+        self.retain(IP_NEIGHBORS_IPV4, |neighbor| {
+            !neighbor["mac"]
+                .as_str()
+                .is_some_and(|m| m.eq_ignore_ascii_case(mac))
+        });
+    }
+
+    pub fn add_dhcp_lease(&self, mac: &str, ip: &str, hostname: Option<&str>) {
+        self.push(
+            DHCP_LEASES_IPV4,
+            json!({
+                "expires": 43200,
+                "macaddr": mac,
+                "ipaddr": ip,
+                "hostname": hostname,
+            }),
+        );
+    }
+
+    pub fn remove_dhcp_lease(&self, mac: &str) {
+        self.retain(DHCP_LEASES_IPV4, |lease| {
+            !lease["macaddr"]
+                .as_str()
+                .is_some_and(|m| m.eq_ignore_ascii_case(mac))
+        });
+    }
+
+    /// A network interface, e.g. `wan`, goes up or down.
+    pub fn set_interface_up(&self, id: &str, up: bool) {
+        let mut state = self.state();
+        let interfaces = state
+            .fixtures
+            .get_mut(INTERFACES_STATUS)
+            .and_then(Value::as_array_mut);
+        for interface in interfaces.into_iter().flatten() {
+            if interface["interface"] == id {
+                interface["up"] = json!(up);
+            }
+        }
+    }
+
+    /// A failover interface's health checks mark it `online` or `offline`.
+    pub fn set_failover_status(&self, interface: &str, status: &str) {
+        let mut state = self.state();
+        let statuses = state
+            .fixtures
+            .get_mut(FAILOVER_STATUS)
+            .and_then(Value::as_array_mut);
+        for entry in statuses.into_iter().flatten() {
+            if entry["interface"] == interface {
+                entry["status"] = json!(status);
+            }
+        }
+    }
+
+    /// A SIM of the `2-1` modem waits for `pin` to be entered, as after the modem
+    /// restarts.
+    pub fn lock_sim(&self, sim: u8, pin: &str) {
+        let mut state = self.state();
+        let State {
+            fixtures, sim_pins, ..
+        } = &mut *state;
+        if let Some(slot) = sim_slot(fixtures, &json!("2-1"), &json!(sim.to_string())) {
+            slot["pin_state"] = json!("sim pin");
+        }
+        sim_pins.insert(("2-1".to_string(), sim.to_string()), pin.to_string());
+    }
+
+    /// A SIM of the `2-1` modem receives `bytes` more data today.
+    pub fn add_data_usage(&self, sim: u8, bytes: u64) {
+        let today = Local::now().date_naive().to_string();
+        let mut state = self.state();
+        let records = state
+            .fixtures
+            .get_mut(DATA_USAGE_STATUS)
+            .and_then(Value::as_array_mut);
+        for record in records.into_iter().flatten() {
+            if record["sim"] == sim && record["date"] == today.as_str() {
+                let rx_bytes = record["rx_bytes"].as_u64().unwrap_or_default();
+                record["rx_bytes"] = json!(rx_bytes + bytes);
+            }
+        }
+    }
+
+    /// A device associates to the first wireless interface, or its signal changes.
+    pub fn set_wireless_signal(&self, mac: &str, signal: i64) {
+        let mut state = self.state();
+        if let Some(assoclist) = state
+            .fixtures
+            .get_mut(WIRELESS_INTERFACES_STATUS)
+            .and_then(|interfaces| interfaces.get_mut(0))
+            .and_then(|interface| interface.get_mut("assoclist"))
+            .and_then(Value::as_object_mut)
+        {
+            assoclist.insert(mac.to_string(), json!({ "signal": signal }));
+        }
+    }
+
+    /// A device leaves every wireless interface.
+    pub fn disassociate(&self, mac: &str) {
+        let mut state = self.state();
+        let interfaces = state
+            .fixtures
+            .get_mut(WIRELESS_INTERFACES_STATUS)
+            .and_then(Value::as_array_mut);
+        for interface in interfaces.into_iter().flatten() {
+            if let Some(assoclist) = interface["assoclist"].as_object_mut() {
+                assoclist.retain(|m, _| !m.eq_ignore_ascii_case(mac));
+            }
+        }
+    }
+
+    /// An SMS arrives on the primary modem. Returns the message ID.
+    pub fn receive_sms(&self, sender: &str, message: &str) -> String {
+        let id = {
+            let mut state = self.state();
+            state.next_message_id += 1;
+            state.next_message_id.to_string()
+        };
+
+        self.push(
+            MESSAGES_STATUS,
+            json!({
+                "message": message,
+                "sender": sender,
+                "id": id,
+                "modem_id": "2-1",
+                "status": "unread",
+                "date": "Wed Mar 19 10:21:53 2025",
+            }),
+        );
+
+        id
+    }
+
+    /// Fail requests to delete messages stored on `modem`.
+    pub fn fail_message_deletes(&self, modem: &str) {
+        self.state().undeletable_messages.push(modem.to_string());
+    }
+
+    /// Bodies of every `/messages/actions/send` request received.
+    pub fn sent_messages(&self) -> Vec<Value> {
+        self.state().sent_messages.clone()
+    }
+
+    /// Accept FOTA download requests without starting a download, like a router
+    /// that can't reach the FOTA server.
+    pub fn stall_firmware_downloads(&self) {
+        self.state().firmware_download_stalled = true;
+    }
+
+    /// Take this long to respond to firmware uploads, like a slow link.
+    pub fn delay_firmware_uploads(&self, delay: Duration) {
+        self.state().firmware_upload_delay = Some(delay);
+    }
+
+    /// Whether a firmware image is waiting to be installed, because a FOTA download
+    /// has finished or an image has been uploaded.
+    pub fn has_staged_firmware(&self) -> bool {
+        self.state().staged_firmware
+    }
+
+    /// Bodies of every successful `/firmware/actions/upgrade` request received.
+    pub fn firmware_upgrades(&self) -> Vec<Value> {
+        self.state().firmware_upgrades.clone()
+    }
+}
+
+impl Drop for MockRouter {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+/// Daily usage of the `2-1` modem's SIMs over the 60 days up to `today`: 1 GB a
+/// day on SIM 1 and 50 MB a day on SIM 2.
+fn data_usage(today: NaiveDate) -> Value {
+    let records = (0..60)
+        .rev()
+        .filter_map(|days| today.checked_sub_days(Days::new(days)))
+        .flat_map(|date| {
+            [(1, 800_000_000, 200_000_000), (2, 40_000_000, 10_000_000)].map(
+                |(sim, rx_bytes, tx_bytes)| {
+                    json!({
+                        "modem": "2-1",
+                        "sim": sim,
+                        "date": date.to_string(),
+                        "rx_bytes": rx_bytes,
+                        "tx_bytes": tx_bytes,
+                    })
+                },
+            )
+        })
+        .collect();
+
+    Value::Array(records)
+}
+
+fn success(data: Value) -> Response {
+    Json(json!({ "success": true, "data": data })).into_response()
+}
+
+fn failure(status: StatusCode, code: i32, error: &str, source: &str) -> Response {
+    let body = json!({
+        "success": false,
+        "errors": [{
+            "code": code,
+            "error": error,
+            "source": source,
+            "section": null,
+        }],
+    });
+    (status, Json(body)).into_response()
+}
+
+fn unauthorized() -> Response {
+    failure(
+        StatusCode::UNAUTHORIZED,
+        118,
+        "Unauthorized access",
+        "Unauthorized access",
+    )
+}
+
+/// Whether the request carries a valid bearer token.
+fn authorized(state: &State, headers: &HeaderMap) -> bool {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match token.and_then(|token| state.tokens.get(token)) {
+        Some(expires_at) => *expires_at > Instant::now(),
+        None => false,
+    }
+}
+
+async fn login(AxumState(state): AxumState<SharedState>, Json(body): Json<Value>) -> Response {
+    let mut state = state.lock().unwrap();
+
+    if body["username"] != state.username.as_str() || body["password"] != state.password.as_str() {
+        return failure(
+            StatusCode::UNAUTHORIZED,
+            119,
+            "Login failed for any reason",
+            "Login failed for any reason",
+        );
+    }
+
+    state.logins += 1;
+    let token = format!("mock-token-{}", state.logins);
+    let lifetime = state.token_lifetime;
+    state
+        .tokens
+        .insert(token.clone(), Instant::now() + lifetime);
+
+    success(json!({
+        "username": state.username,
+        "token": token,
+        "expires": lifetime.as_secs(),
+    }))
+}
+
+async fn session_status(AxumState(state): AxumState<SharedState>, headers: HeaderMap) -> Response {
+    let state = state.lock().unwrap();
+    if !authorized(&state, &headers) {
+        return unauthorized();
+    }
+
+    success(json!({ "active": true }))
+}
+
+async fn firmware_actions_fota_download(
+    AxumState(state): AxumState<SharedState>,
+    headers: HeaderMap,
+) -> Response {
+    let mut state = state.lock().unwrap();
+    if !authorized(&state, &headers) {
+        return unauthorized();
+    }
+
+    if !state.firmware_download_stalled {
+        state.firmware_download = Some(0);
+    }
+    success(Value::Null)
+}
+
+/// Each poll advances a started download by half, so it finishes on the second.
+async fn firmware_device_progress_status(
+    AxumState(state): AxumState<SharedState>,
+    headers: HeaderMap,
+) -> Response {
+    let mut state = state.lock().unwrap();
+    if !authorized(&state, &headers) {
+        return unauthorized();
+    }
+
+    let Some(percents) = state.firmware_download else {
+        return success(json!({ "process": "", "percents": "0" }));
+    };
+
+    let percents = (percents + 50).min(100);
+    state.firmware_download = Some(percents);
+    if percents < 100 {
+        return success(json!({ "process": "started", "percents": percents.to_string() }));
+    }
+
+    state.staged_firmware = true;
+    success(json!({ "process": "succeeded", "percents": "100" }))
+}
+
+async fn firmware_actions_upload_device_firmware(
+    AxumState(state): AxumState<SharedState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let delay = {
+        let state = state.lock().unwrap();
+        if !authorized(&state, &headers) {
+            return unauthorized();
+        }
+
+        if body.is_empty() {
+            return failure(
+                StatusCode::BAD_REQUEST,
+                103,
+                "Firmware image is empty",
+                "file",
+            );
+        }
+
+        state.firmware_upload_delay
+    };
+
+    if let Some(delay) = delay {
+        tokio::time::sleep(delay).await;
+    }
+
+    state.lock().unwrap().staged_firmware = true;
+    success(Value::Null)
+}
+
+async fn firmware_actions_upgrade(
+    AxumState(state): AxumState<SharedState>,
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+) -> Response {
+    let mut state = state.lock().unwrap();
+    if !authorized(&state, &headers) {
+        return unauthorized();
+    }
+
+    if !std::mem::take(&mut state.staged_firmware) {
+        return failure(
+            StatusCode::BAD_REQUEST,
+            121,
+            "No firmware image to install",
+            "firmware",
+        );
+    }
+
+    state.firmware_upgrades.push(body);
+    success(Value::Null)
+}
+
+async fn messages_send(
+    AxumState(state): AxumState<SharedState>,
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+) -> Response {
+    let mut state = state.lock().unwrap();
+    if !authorized(&state, &headers) {
+        return unauthorized();
+    }
+
+    let modem = &body["data"]["modem"];
+    let known_modem = state.fixtures[MODEMS_STATUS]
+        .as_array()
+        .is_some_and(|modems| modems.iter().any(|m| m["id"] == *modem));
+    if !known_modem {
+        return failure(StatusCode::BAD_REQUEST, 103, "Invalid options", "modem");
+    }
+
+    state.sent_messages.push(body);
+    let sms_used = state.sent_messages.len();
+
+    success(json!({ "sms_used": sms_used }))
+}
+
+/// Apply `update` to the messages named by an action body, failing if any are unknown.
+fn update_messages(
+    state: &mut State,
+    body: &Value,
+    update: impl Fn(&mut Vec<Value>, &str, &str),
+) -> Response {
+    let modem = body["data"]["modem_id"].as_str().unwrap_or_default();
+    let ids: Vec<&str> = body["data"]["sms_id"]
+        .as_array()
+        .map(|ids| ids.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+
+    let messages = state
+        .fixtures
+        .entry(MESSAGES_STATUS.to_string())
+        .or_insert_with(|| json!([]))
+        .as_array_mut()
+        .expect("Fixture is not a list");
+
+    let exists = |id: &str| {
+        messages
+            .iter()
+            .any(|message| message["id"] == id && message["modem_id"] == modem)
+    };
+    if ids.is_empty() || !ids.iter().all(|id| exists(id)) {
+        return failure(StatusCode::BAD_REQUEST, 103, "Invalid options", "sms_id");
+    }
+
+    for id in ids {
+        update(messages, modem, id);
+    }
+
+    success(Value::Null)
+}
+
+async fn messages_remove(
+    AxumState(state): AxumState<SharedState>,
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+) -> Response {
+    let mut state = state.lock().unwrap();
+    if !authorized(&state, &headers) {
+        return unauthorized();
+    }
+    if state
+        .undeletable_messages
+        .iter()
+        .any(|modem| body["data"]["modem_id"] == modem.as_str())
+    {
+        return failure(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            100,
+            "Failed to delete messages",
+            "sms_id",
+        );
+    }
+
+    update_messages(&mut state, &body, |messages, modem, id| {
+        messages.retain(|message| message["id"] != id || message["modem_id"] != modem)
+    })
+}
+
+async fn messages_read(
+    AxumState(state): AxumState<SharedState>,
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+) -> Response {
+    let mut state = state.lock().unwrap();
+    if !authorized(&state, &headers) {
+        return unauthorized();
+    }
+
+    update_messages(&mut state, &body, |messages, modem, id| {
+        for message in messages
+            .iter_mut()
+            .filter(|message| message["id"] == id && message["modem_id"] == modem)
+        {
+            message["status"] = json!("read");
+        }
+    })
+}
+
+async fn interface_status(
+    AxumState(state): AxumState<SharedState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    let state = state.lock().unwrap();
+    if !authorized(&state, &headers) {
+        return unauthorized();
+    }
+
+    let interface = state.fixtures[INTERFACES_STATUS]
+        .as_array()
+        .and_then(|interfaces| interfaces.iter().find(|i| i["interface"] == id.as_str()));
+    match interface {
+        Some(interface) => success(interface.clone()),
+        None => failure(StatusCode::NOT_FOUND, 103, "Interface not found", &id),
+    }
+}
+
+/// Make the requested slot the modem's active one.
+async fn sim_switch(
+    AxumState(state): AxumState<SharedState>,
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+) -> Response {
+    let mut state = state.lock().unwrap();
+    if !authorized(&state, &headers) {
+        return unauthorized();
+    }
+
+    let (modem, sim) = (&body["data"]["modem"], &body["data"]["sim"]);
+    let Some(slots) = state
+        .fixtures
+        .get_mut(SIM_STATUS)
+        .and_then(Value::as_array_mut)
+    else {
+        return failure(
+            StatusCode::NOT_FOUND,
+            100,
+            "Response not implemented",
+            "sim",
+        );
+    };
+    let exists = slots
+        .iter()
+        .any(|slot| slot["modem"] == *modem && slot["sim"] == *sim && slot["inserted"] == "1");
+    if !exists {
+        return failure(StatusCode::BAD_REQUEST, 103, "Invalid options", "sim");
+    }
+
+    let mut active = Value::Null;
+    for slot in slots.iter_mut().filter(|slot| slot["modem"] == *modem) {
+        let is_active = slot["sim"] == *sim;
+        slot["active"] = json!(if is_active { "1" } else { "0" });
+        if is_active {
+            active = slot.clone();
+        }
+    }
+
+    success(active)
+}
+
+/// Enter the PIN of a slot that is waiting for one.
+async fn sim_unlock(
+    AxumState(state): AxumState<SharedState>,
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+) -> Response {
+    let mut state = state.lock().unwrap();
+    if !authorized(&state, &headers) {
+        return unauthorized();
+    }
+
+    let State {
+        fixtures, sim_pins, ..
+    } = &mut *state;
+    let data = &body["data"];
+    let Some(slot) = sim_slot(fixtures, &data["modem"], &data["sim"]) else {
+        return failure(StatusCode::BAD_REQUEST, 103, "Invalid options", "sim");
+    };
+    if slot["pin_state"] != "sim pin" {
+        return failure(
+            StatusCode::BAD_REQUEST,
+            103,
+            "SIM is not waiting for a PIN",
+            "sim",
+        );
+    }
+    if !pin_matches(sim_pins, data) {
+        return failure(StatusCode::BAD_REQUEST, 103, "Wrong PIN", "pin");
+    }
+
+    slot["pin_state"] = json!("ready");
+    success(slot.clone())
+}
+
+/// Change the PIN of an unlocked SIM.
+async fn sim_change_pin(
+    AxumState(state): AxumState<SharedState>,
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+) -> Response {
+    let mut state = state.lock().unwrap();
+    if !authorized(&state, &headers) {
+        return unauthorized();
+    }
+
+    let State {
+        fixtures, sim_pins, ..
+    } = &mut *state;
+    let data = &body["data"];
+    let Some(slot) = sim_slot(fixtures, &data["modem"], &data["sim"]) else {
+        return failure(StatusCode::BAD_REQUEST, 103, "Invalid options", "sim");
+    };
+    if slot["pin_state"] != "ready" {
+        return failure(StatusCode::BAD_REQUEST, 103, "SIM is locked", "sim");
+    }
+    if !pin_matches(sim_pins, data) {
+        return failure(StatusCode::BAD_REQUEST, 103, "Wrong PIN", "pin");
+    }
+    let Some(new_pin) = data["new_pin"].as_str() else {
+        return failure(StatusCode::BAD_REQUEST, 103, "Invalid options", "new_pin");
+    };
+
+    let key = (
+        data["modem"].as_str().unwrap_or_default().to_string(),
+        data["sim"].as_str().unwrap_or_default().to_string(),
+    );
+    sim_pins.insert(key, new_pin.to_string());
+    success(slot.clone())
+}
+
+/// The inserted slot `sim` of `modem` in [`SIM_STATUS`].
+fn sim_slot<'a>(
+    fixtures: &'a mut HashMap<String, Value>,
+    modem: &Value,
+    sim: &Value,
+) -> Option<&'a mut Value> {
+    fixtures
+        .get_mut(SIM_STATUS)
+        .and_then(Value::as_array_mut)?
+        .iter_mut()
+        .find(|slot| slot["modem"] == *modem && slot["sim"] == *sim && slot["inserted"] == "1")
+}
+
+/// Whether a request gives the PIN of the SIM it names.
+fn pin_matches(sim_pins: &HashMap<(String, String), String>, data: &Value) -> bool {
+    let key = (
+        data["modem"].as_str().unwrap_or_default().to_string(),
+        data["sim"].as_str().unwrap_or_default().to_string(),
+    );
+    sim_pins
+        .get(&key)
+        .is_some_and(|pin| data["pin"] == pin.as_str())
+}
+
+/// The config sections of an endpoint, e.g. [`FIREWALL_PORT_FORWARDS`].
+fn config_sections<'a>(
+    state: &'a mut State,
+    module: &str,
+    section: &str,
+) -> Option<&'a mut Vec<Value>> {
+    state
+        .fixtures
+        .get_mut(&format!("/{}/{}/config", module, section))
+        .and_then(Value::as_array_mut)
+}
+
+fn section_not_found(id: &str) -> Response {
+    failure(StatusCode::NOT_FOUND, 103, "Section not found", id)
+}
+
+async fn config_create(
+    AxumState(state): AxumState<SharedState>,
+    Path((module, section)): Path<(String, String)>,
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+) -> Response {
+    let mut state = state.lock().unwrap();
+    if !authorized(&state, &headers) {
+        return unauthorized();
+    }
+
+    let Value::Object(mut data) = body["data"].clone() else {
+        return failure(StatusCode::BAD_REQUEST, 103, "Invalid options", "data");
+    };
+    state.next_section_id += 1;
+    let id = format!("cfg{:06x}", state.next_section_id);
+    data.insert("id".to_string(), json!(id));
+
+    let Some(sections) = config_sections(&mut state, &module, &section) else {
+        return failure(
+            StatusCode::NOT_FOUND,
+            100,
+            "Response not implemented",
+            &section,
+        );
+    };
+    sections.push(Value::Object(data.clone()));
+
+    success(Value::Object(data))
+}
+
+async fn config_get(
+    AxumState(state): AxumState<SharedState>,
+    Path((module, section, id)): Path<(String, String, String)>,
+    headers: HeaderMap,
+) -> Response {
+    let mut state = state.lock().unwrap();
+    if !authorized(&state, &headers) {
+        return unauthorized();
+    }
+
+    let found = config_sections(&mut state, &module, &section)
+        .and_then(|sections| sections.iter().find(|s| s["id"] == id.as_str()).cloned());
+    match found {
+        Some(data) => success(data),
+        None => section_not_found(&id),
+    }
+}
+
+/// Set the fields given in the body, keeping the others.
+async fn config_update(
+    AxumState(state): AxumState<SharedState>,
+    Path((module, section, id)): Path<(String, String, String)>,
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+) -> Response {
+    let mut state = state.lock().unwrap();
+    if !authorized(&state, &headers) {
+        return unauthorized();
+    }
+
+    let Some(existing) = config_sections(&mut state, &module, &section)
+        .and_then(|sections| sections.iter_mut().find(|s| s["id"] == id.as_str()))
+    else {
+        return section_not_found(&id);
+    };
+    if let (Some(existing), Some(fields)) = (existing.as_object_mut(), body["data"].as_object()) {
+        for (name, value) in fields {
+            existing.insert(name.clone(), value.clone());
+        }
+    }
+
+    success(existing.clone())
+}
+
+async fn config_delete(
+    AxumState(state): AxumState<SharedState>,
+    Path((module, section, id)): Path<(String, String, String)>,
+    headers: HeaderMap,
+) -> Response {
+    let mut state = state.lock().unwrap();
+    if !authorized(&state, &headers) {
+        return unauthorized();
+    }
+
+    let Some(sections) = config_sections(&mut state, &module, &section) else {
+        return section_not_found(&id);
+    };
+    let count = sections.len();
+    sections.retain(|s| s["id"] != id.as_str());
+    if sections.len() == count {
+        return section_not_found(&id);
+    }
+
+    success(json!({ "id": id }))
+}
+
+async fn fixture(
+    AxumState(state): AxumState<SharedState>,
+    headers: HeaderMap,
+    uri: Uri,
+) -> Response {
+    let state = state.lock().unwrap();
+    if !authorized(&state, &headers) {
+        return unauthorized();
+    }
+
+    let path = uri.path().strip_prefix("/api").unwrap_or(uri.path());
+    match state.fixtures.get(path) {
+        Some(data) => success(data.clone()),
+        None => failure(StatusCode::NOT_FOUND, 100, "Response not implemented", path),
+    }
+}
@@ -1,57 +1,315 @@
+use std::{io::Read as _, path::PathBuf};
+
+use chrono::NaiveDate;
 use clap::Parser;
-use tonik;
+use tonik::{
+    Certificate, Response, Scheme, SmsMessage, SmsSendRequest, TeltonikaClient, profile::Config,
+};
 
 #[derive(Debug, Parser)]
 struct App {
     #[clap(subcommand)]
     command: Commands,
 
-    #[clap(long, default_value = "192.168.7.1")]
-    host: String,
+    /// Profile from ~/.config/tonik/config.toml to connect with
+    #[clap(long, env = "TONIK_PROFILE")]
+    profile: Option<String>,
+
+    /// Router host [default: 192.168.7.1]
+    #[clap(long, env = "TELTONIKA_HOST")]
+    host: Option<String>,
+
+    /// Router username [default: admin]
+    #[clap(long, env = "TELTONIKA_USERNAME")]
+    username: Option<String>,
+
+    /// Router password, unless the profile provides one
+    #[clap(long, env = "TELTONIKA_PASSWORD")]
+    password: Option<String>,
 
-    #[clap(long, default_value = "admin")]
-    username: String,
+    /// API port, if not the scheme's default
+    #[clap(long)]
+    port: Option<u16>,
 
-    #[clap(long, default_value = "password")]
-    password: String,
+    /// Connect over plain HTTP instead of HTTPS
+    #[clap(long)]
+    http: bool,
+
+    /// PEM certificate to trust instead of accepting any certificate
+    #[clap(long, env = "TELTONIKA_CERT")]
+    cert: Option<PathBuf>,
+
+    /// Output in JSON format
+    #[clap(long, global = true)]
+    json: bool,
 }
 
 #[derive(Debug, clap::Subcommand)]
 enum Commands {
-    List,
-    Read,
-    Send,
+    /// List messages stored on the router
+    List {
+        /// Only list unread messages
+        #[clap(long)]
+        unread: bool,
+
+        /// Only list messages from this number
+        #[clap(long)]
+        from: Option<String>,
+
+        /// Only list messages received on or after this date (YYYY-MM-DD)
+        #[clap(long)]
+        since: Option<NaiveDate>,
+    },
+    /// Show a message and mark it as read
+    Read {
+        id: String,
+
+        /// Modem the message is stored on, needed if its ID is used on several
+        #[clap(long)]
+        modem: Option<String>,
+    },
+    /// Delete messages
+    Delete {
+        #[clap(required = true)]
+        ids: Vec<String>,
+
+        /// Modem the messages are stored on, needed if their IDs are used on several
+        #[clap(long)]
+        modem: Option<String>,
+    },
+    /// Send a message
+    Send {
+        /// Recipient phone number
+        #[clap(long)]
+        to: String,
+
+        /// Message text, or `-` to read it from stdin
+        #[clap(long)]
+        message: String,
+
+        /// Modem to send from, e.g. `2-1`. Defaults to the router's primary modem.
+        #[clap(long)]
+        modem: Option<String>,
+    },
 }
 
 #[tokio::main]
 async fn main() {
     let args = App::parse();
 
-    let mut client = tonik::TeltonikaClient::new(args.host);
-    let auth_response = client
-        .authenticate(&args.username, &args.password)
-        .await
-        .unwrap();
-    println!("Authentication response: {:?}", auth_response);
+    let config = exit_on_error(Config::load_default());
+    let profile = exit_on_error(config.profile(args.profile.as_deref()))
+        .cloned()
+        .unwrap_or_default();
+    let host = args
+        .host
+        .or(profile.host.clone())
+        .unwrap_or_else(|| "192.168.7.1".to_string());
+    let username = args
+        .username
+        .or(profile.username.clone())
+        .unwrap_or_else(|| "admin".to_string());
+    let Some(password) = args.password.or_else(|| exit_on_error(profile.password())) else {
+        eprintln!("Error: no password, use --password or --profile");
+        std::process::exit(1);
+    };
+
+    let mut builder = TeltonikaClient::builder(host).credentials(&username, &password);
+    if let Some(port) = args.port.or(profile.port) {
+        builder = builder.port(port);
+    }
+    if args.http || profile.http.unwrap_or_default() {
+        builder = builder.scheme(Scheme::Http);
+    }
+    builder = match args.cert.or(profile.cert.clone()) {
+        Some(path) => {
+            let pem = exit_on_error(
+                std::fs::read(&path)
+                    .map_err(|e| format!("failed to read {}: {}", path.display(), e)),
+            );
+            let certificate = exit_on_error(
+                Certificate::from_pem(&pem)
+                    .map_err(|e| format!("invalid certificate {}: {}", path.display(), e)),
+            );
+            builder
+                .add_root_certificate(certificate)
+                .pin_certificates()
+                .danger_accept_invalid_hostnames(true)
+        }
+        None => builder.danger_accept_invalid_certs(true),
+    };
+    let client = exit_on_error(builder.build());
 
     match args.command {
-        Commands::List => {
-            println!("Listing SMS messages...");
-            let messages = client.list_sms_messages().await.unwrap().data.unwrap();
-            for message in messages {
-                println!("{:?}", message);
+        Commands::List {
+            unread,
+            from,
+            since,
+        } => {
+            let since = since.map(|date| date.and_hms_opt(0, 0, 0).unwrap().and_utc());
+            let messages: Vec<SmsMessage> = list_messages(&client)
+                .await
+                .into_iter()
+                .filter(|message| !unread || message.is_unread())
+                .filter(|message| from.as_ref().is_none_or(|from| message.sender == *from))
+                .filter(|message| since.is_none_or(|since| message.date >= since))
+                .collect();
+
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&messages).unwrap());
+            } else {
+                for message in messages {
+                    println!("{}\n", message);
+                }
             }
         }
-        Commands::Read => {
-            println!("Reading SMS messages...");
+        Commands::Read { id, modem } => {
+            let ids = std::slice::from_ref(&id);
+            let messages = find_messages(&client, ids, modem.as_deref()).await;
+            let modem = &messages[0].modem_id;
+
+            exit_on_error(
+                client
+                    .mark_sms_messages_read(modem, ids)
+                    .await
+                    .and_then(Response::ensure_success),
+            );
+
+            // Fetch it again so it's printed as read.
+            let messages = find_messages(&client, ids, Some(modem)).await;
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&messages).unwrap());
+            } else {
+                for message in messages {
+                    println!("{}\n", message);
+                }
+            }
         }
-        Commands::Send => {
-            println!("Sending SMS messages...");
-            let response = client
-                .send_sms_message("+447799555832", "Testing")
-                .await
-                .unwrap();
-            println!("Response: {:?}", response);
+        Commands::Delete { ids, modem } => {
+            let messages = find_messages(&client, &ids, modem.as_deref()).await;
+
+            let mut modems: Vec<&str> = messages
+                .iter()
+                .map(|message| message.modem_id.as_str())
+                .collect();
+            modems.sort();
+            modems.dedup();
+
+            for modem in modems {
+                let ids: Vec<String> = messages
+                    .iter()
+                    .filter(|message| message.modem_id == modem)
+                    .map(|message| message.id.clone())
+                    .collect();
+                exit_on_error(
+                    client
+                        .delete_sms_messages(modem, &ids)
+                        .await
+                        .and_then(Response::ensure_success),
+                );
+            }
+
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&messages).unwrap());
+            } else {
+                for message in messages {
+                    println!("Deleted message {} from {}", message.id, message.sender);
+                }
+            }
+        }
+        Commands::Send { to, message, modem } => {
+            let message = if message == "-" {
+                let mut message = String::new();
+                if let Err(e) = std::io::stdin().read_to_string(&mut message) {
+                    eprintln!("Error: failed to read message from stdin: {}", e);
+                    std::process::exit(1);
+                }
+                message.trim_end().to_string()
+            } else {
+                message
+            };
+
+            let mut request = SmsSendRequest::new(&to, &message);
+            if let Some(modem) = &modem {
+                request = request.modem(modem);
+            }
+            let response = exit_on_error(
+                client
+                    .send_sms(&request)
+                    .await
+                    .and_then(Response::into_result),
+            );
+
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&response).unwrap());
+            } else {
+                println!("Sent message to {} ({} SMS used)", to, response.sms_used);
+            }
+        }
+    }
+}
+
+async fn list_messages(client: &TeltonikaClient) -> Vec<SmsMessage> {
+    exit_on_error(
+        client
+            .list_sms_messages()
+            .await
+            .and_then(Response::into_result),
+    )
+}
+
+/// Look up messages by ID, optionally only on one modem, exiting if any of them
+/// don't exist or, as IDs are only unique per modem, exist on several modems.
+async fn find_messages(
+    client: &TeltonikaClient,
+    ids: &[String],
+    modem: Option<&str>,
+) -> Vec<SmsMessage> {
+    let messages: Vec<SmsMessage> = list_messages(client)
+        .await
+        .into_iter()
+        .filter(|message| modem.is_none_or(|modem| message.modem_id == modem))
+        .collect();
+
+    for id in ids {
+        let mut modems: Vec<&str> = messages
+            .iter()
+            .filter(|message| message.id == *id)
+            .map(|message| message.modem_id.as_str())
+            .collect();
+        modems.sort();
+        modems.dedup();
+
+        match modems.as_slice() {
+            [] => {
+                eprintln!("Error: no message with ID {}", id);
+                std::process::exit(1);
+            }
+            [_] => {}
+            modems => {
+                eprintln!(
+                    "Error: message ID {} is used on modems {}, pick one with --modem",
+                    id,
+                    modems.join(", ")
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+
+    messages
+        .into_iter()
+        .filter(|message| ids.contains(&message.id))
+        .collect()
+}
+
+/// Unwrap a library result, printing the error and exiting on failure.
+fn exit_on_error<T, E: std::fmt::Display>(result: Result<T, E>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
         }
     }
 }
@@ -0,0 +1,168 @@
+use std::{
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+use tonik_mock::{DEFAULT_PASSWORD, MESSAGES_STATUS, MockRouter};
+
+fn tonik_sms(router: &MockRouter, args: &[&str], stdin: Option<&str>) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_tonik-sms"))
+        .args(["--host", &router.host()])
+        .args(["--port", &router.port().to_string()])
+        .args(["--http", "--password", DEFAULT_PASSWORD])
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let mut pipe = child.stdin.take().unwrap();
+    if let Some(stdin) = stdin {
+        pipe.write_all(stdin.as_bytes()).unwrap();
+    }
+    drop(pipe);
+
+    child.wait_with_output().unwrap()
+}
+
+fn json(output: &Output) -> serde_json::Value {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_list_filters() {
+    let router = MockRouter::start().await.unwrap();
+    router.receive_sms("+447700900456", "Are you aboard?");
+    router.receive_sms("+447700900789", "Engine serviced");
+
+    let all = json(&tonik_sms(&router, &["list", "--json"], None));
+    assert_eq!(all.as_array().unwrap().len(), 3);
+
+    let unread = json(&tonik_sms(&router, &["list", "--unread", "--json"], None));
+    assert_eq!(unread.as_array().unwrap().len(), 2);
+
+    let from = json(&tonik_sms(
+        &router,
+        &["list", "--unread", "--from", "+447700900789", "--json"],
+        None,
+    ));
+    assert_eq!(from[0]["message"], "Engine serviced");
+
+    let since = json(&tonik_sms(
+        &router,
+        &["list", "--since", "2025-03-20", "--json"],
+        None,
+    ));
+    assert!(since.as_array().unwrap().is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_read_marks_message_read() {
+    let router = MockRouter::start().await.unwrap();
+    let id = router.receive_sms("+447700900456", "Are you aboard?");
+
+    let read = json(&tonik_sms(&router, &["read", &id, "--json"], None));
+    assert_eq!(read[0]["message"], "Are you aboard?");
+    assert_eq!(read[0]["status"], "read");
+
+    let messages = router.fixture(MESSAGES_STATUS).unwrap();
+    let message = messages
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|message| message["id"] == id.as_str())
+        .unwrap();
+    assert_eq!(message["status"], "read");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_delete_messages() {
+    let router = MockRouter::start().await.unwrap();
+    let first = router.receive_sms("+447700900456", "One");
+    let second = router.receive_sms("+447700900456", "Two");
+
+    let output = tonik_sms(&router, &["delete", &first, &second], None);
+    assert!(output.status.success());
+
+    let messages = router.fixture(MESSAGES_STATUS).unwrap();
+    assert_eq!(messages.as_array().unwrap().len(), 1);
+
+    let output = tonik_sms(&router, &["delete", "does-not-exist"], None);
+    assert!(!output.status.success());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_ids_shared_by_modems() {
+    let router = MockRouter::start().await.unwrap();
+    let id = router.receive_sms("+447700900456", "On the first modem");
+    let mut messages = router.fixture(MESSAGES_STATUS).unwrap();
+    let mut other = messages[1].clone();
+    other["modem_id"] = "1-1".into();
+    other["message"] = "On the second modem".into();
+    messages.as_array_mut().unwrap().push(other);
+    router.set_fixture(MESSAGES_STATUS, messages);
+
+    let output = tonik_sms(&router, &["delete", &id], None);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("pick one with --modem"));
+
+    let read = json(&tonik_sms(
+        &router,
+        &["read", &id, "--modem", "1-1", "--json"],
+        None,
+    ));
+    assert_eq!(read.as_array().unwrap().len(), 1);
+    assert_eq!(read[0]["message"], "On the second modem");
+
+    let output = tonik_sms(&router, &["delete", &id, "--modem", "1-1"], None);
+    assert!(output.status.success());
+    let messages = router.fixture(MESSAGES_STATUS).unwrap();
+    let remaining: Vec<&serde_json::Value> = messages
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|message| message["id"] == id.as_str())
+        .collect();
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0]["modem_id"], "2-1");
+    assert_eq!(remaining[0]["status"], "unread");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_send_from_stdin() {
+    let router = MockRouter::start().await.unwrap();
+
+    let output = tonik_sms(
+        &router,
+        &["send", "--to", "+447700900123", "--message", "-"],
+        Some("Dinner at eight\n"),
+    );
+    assert!(output.status.success());
+
+    let sent = router.sent_messages();
+    assert_eq!(sent[0]["data"]["number"], "+447700900123");
+    assert_eq!(sent[0]["data"]["message"], "Dinner at eight");
+    assert_eq!(sent[0]["data"]["modem"], "2-1");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_missing_certificate() {
+    let router = MockRouter::start().await.unwrap();
+
+    let output = tonik_sms(
+        &router,
+        &["--cert", "/nonexistent/router.pem", "list"],
+        None,
+    );
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .starts_with("Error: failed to read /nonexistent/router.pem")
+    );
+}
@@ -0,0 +1,193 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    time::Duration,
+};
+
+use reqwest::Certificate;
+
+use crate::{Error, TeltonikaClient};
+
+/// URL scheme used to reach the router's API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Scheme {
+    Http,
+    #[default]
+    Https,
+}
+
+impl Display for Scheme {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Scheme::Http => write!(f, "http"),
+            Scheme::Https => write!(f, "https"),
+        }
+    }
+}
+
+/// Builder for a [`TeltonikaClient`].
+///
+/// Unlike [`TeltonikaClient::new`], certificates are validated by default. Either
+/// pin the router's certificate with [`ClientBuilder::add_root_certificate`] or
+/// opt out with [`ClientBuilder::danger_accept_invalid_certs`].
+#[derive(Debug)]
+pub struct ClientBuilder {
+    host: String,
+    scheme: Scheme,
+    port: Option<u16>,
+    root_certificates: Vec<Certificate>,
+    built_in_root_certs: bool,
+    accept_invalid_certs: bool,
+    accept_invalid_hostnames: bool,
+    connect_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    user_agent: Option<String>,
+    reqwest: Option<reqwest::Client>,
+    credentials: Option<(String, String)>,
+}
+
+impl ClientBuilder {
+    pub fn new(host: impl Into<String>) -> Self {
+        ClientBuilder {
+            host: host.into(),
+            scheme: Scheme::default(),
+            port: None,
+            root_certificates: Vec::new(),
+            built_in_root_certs: true,
+            accept_invalid_certs: false,
+            accept_invalid_hostnames: false,
+            connect_timeout: None,
+            timeout: None,
+            user_agent: None,
+            reqwest: None,
+            credentials: None,
+        }
+    }
+
+    /// Use `http` or `https` to reach the API. Defaults to `https`.
+    pub fn scheme(mut self, scheme: Scheme) -> Self {
+        self.scheme = scheme;
+        self
+    }
+
+    /// Connect on a non-standard port.
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    /// Trust an additional certificate, such as the router's self-signed one or a CA bundle entry.
+    pub fn add_root_certificate(mut self, certificate: Certificate) -> Self {
+        self.root_certificates.push(certificate);
+        self
+    }
+
+    /// Only trust certificates added with [`ClientBuilder::add_root_certificate`].
+    pub fn pin_certificates(mut self) -> Self {
+        self.built_in_root_certs = false;
+        self
+    }
+
+    /// Accept any certificate presented by the router.
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.accept_invalid_certs = accept;
+        self
+    }
+
+    /// Accept certificates whose hostname doesn't match, e.g. when connecting by IP address.
+    pub fn danger_accept_invalid_hostnames(mut self, accept: bool) -> Self {
+        self.accept_invalid_hostnames = accept;
+        self
+    }
+
+    /// Timeout for establishing a connection.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Timeout for a whole request, from connecting until the body has been read.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Use an existing `reqwest::Client`.
+    ///
+    /// TLS, timeout and user agent settings on this builder are ignored in favour
+    /// of the client's own configuration.
+    pub fn reqwest_client(mut self, client: reqwest::Client) -> Self {
+        self.reqwest = Some(client);
+        self
+    }
+
+    /// Remember credentials so the session is renewed automatically.
+    ///
+    /// See [`TeltonikaClient::with_credentials`].
+    pub fn credentials(mut self, username: &str, password: &str) -> Self {
+        self.credentials = Some((username.to_string(), password.to_string()));
+        self
+    }
+
+    pub fn build(self) -> Result<TeltonikaClient, Error> {
+        let base_url = match self.port {
+            Some(port) => format!("{}://{}:{}/api", self.scheme, self.host, port),
+            None => format!("{}://{}/api", self.scheme, self.host),
+        };
+
+        let reqwest = match self.reqwest {
+            Some(reqwest) => reqwest,
+            None => {
+                let mut builder = reqwest::Client::builder()
+                    .gzip(true)
+                    .tls_built_in_root_certs(self.built_in_root_certs)
+                    .danger_accept_invalid_certs(self.accept_invalid_certs)
+                    .danger_accept_invalid_hostnames(self.accept_invalid_hostnames);
+
+                for certificate in self.root_certificates {
+                    builder = builder.add_root_certificate(certificate);
+                }
+                if let Some(timeout) = self.connect_timeout {
+                    builder = builder.connect_timeout(timeout);
+                }
+                if let Some(timeout) = self.timeout {
+                    builder = builder.timeout(timeout);
+                }
+                if let Some(user_agent) = self.user_agent {
+                    builder = builder.user_agent(user_agent);
+                }
+
+                builder.build().map_err(Error::Client)?
+            }
+        };
+
+        let client = TeltonikaClient::from_parts(base_url, reqwest);
+
+        Ok(match self.credentials {
+            Some((username, password)) => client.with_credentials(&username, &password),
+            None => client,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base_url() {
+        let client = ClientBuilder::new("192.168.7.1").build().unwrap();
+        assert_eq!(client.base_url(), "https://192.168.7.1/api");
+
+        let client = ClientBuilder::new("router.local")
+            .scheme(Scheme::Http)
+            .port(8080)
+            .build()
+            .unwrap();
+        assert_eq!(client.base_url(), "http://router.local:8080/api");
+    }
+}
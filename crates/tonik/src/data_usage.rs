@@ -0,0 +1,499 @@
+//! Mobile data usage per SIM, and the data limits the router enforces.
+
+use std::fmt::{self, Display, Formatter};
+
+use chrono::{Datelike, Days, Local, Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::{de, network::bytes, Error, Response, TeltonikaClient};
+
+/// Bytes in a megabyte, the unit data limits are configured in.
+const MEGABYTE: u64 = 1_000_000;
+
+/// How often a data limit resets.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum UsagePeriod {
+    Day,
+    Week,
+    #[default]
+    Month,
+}
+
+impl UsagePeriod {
+    /// The period containing `date`, from its first day up to but excluding the
+    /// first day of the next one.
+    ///
+    /// `start_day` is the day of the month a monthly period starts on, clamped
+    /// to the length of shorter months, or the weekday a weekly one starts on,
+    /// 1 being Monday.
+    pub fn range(self, date: NaiveDate, start_day: u32) -> (NaiveDate, NaiveDate) {
+        match self {
+            UsagePeriod::Day => (date, date + Days::new(1)),
+            UsagePeriod::Week => {
+                let weekday = date.weekday().number_from_monday();
+                let since_start = (weekday + 7 - start_day.clamp(1, 7)) % 7;
+                let start = date - Days::new(since_start.into());
+                (start, start + Days::new(7))
+            }
+            UsagePeriod::Month => {
+                let first = date.with_day(1).unwrap_or(date);
+                let mut start = on_day(first, start_day);
+                if start > date {
+                    start = on_day(first - Months::new(1), start_day);
+                }
+                let next = start.with_day(1).unwrap_or(start) + Months::new(1);
+                (start, on_day(next, start_day))
+            }
+        }
+    }
+}
+
+impl Display for UsagePeriod {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(match self {
+            UsagePeriod::Day => "day",
+            UsagePeriod::Week => "week",
+            UsagePeriod::Month => "month",
+        })
+    }
+}
+
+/// `day` of the month of `date`, or the month's last day if it is shorter.
+fn on_day(date: NaiveDate, day: u32) -> NaiveDate {
+    (1..=day.clamp(1, 31))
+        .rev()
+        .find_map(|day| date.with_day(day))
+        .unwrap_or(date)
+}
+
+/// Data sent and received through one SIM on one day, as reported by
+/// `/data_usage/status`.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct DataUsage {
+    /// Modem the SIM is in, e.g. `2-1`.
+    pub modem: String,
+    /// SIM slot, starting from 1.
+    #[serde(deserialize_with = "de::from_str")]
+    pub sim: u8,
+    pub date: NaiveDate,
+    #[serde(deserialize_with = "de::from_str")]
+    pub rx_bytes: u64,
+    #[serde(deserialize_with = "de::from_str")]
+    pub tx_bytes: u64,
+}
+
+impl DataUsage {
+    pub fn total(&self) -> u64 {
+        self.rx_bytes + self.tx_bytes
+    }
+}
+
+/// A SIM's usage totalled over a day, week or month.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct UsageTotal {
+    pub modem: String,
+    pub sim: u8,
+    pub start: NaiveDate,
+    /// First day after the period.
+    pub end: NaiveDate,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+}
+
+impl UsageTotal {
+    /// Total daily usage by SIM and period, with periods starting as described
+    /// in [`UsagePeriod::range`]. Sorted by SIM, then most recent period first.
+    pub fn by_period(records: &[DataUsage], period: UsagePeriod, start_day: u32) -> Vec<Self> {
+        let mut totals: Vec<Self> = Vec::new();
+        for record in records {
+            let (start, end) = period.range(record.date, start_day);
+            let existing = totals.iter_mut().find(|total| {
+                total.modem == record.modem && total.sim == record.sim && total.start == start
+            });
+            match existing {
+                Some(total) => {
+                    total.rx_bytes += record.rx_bytes;
+                    total.tx_bytes += record.tx_bytes;
+                }
+                None => totals.push(UsageTotal {
+                    modem: record.modem.clone(),
+                    sim: record.sim,
+                    start,
+                    end,
+                    rx_bytes: record.rx_bytes,
+                    tx_bytes: record.tx_bytes,
+                }),
+            }
+        }
+
+        totals.sort_by(|a, b| (&a.modem, a.sim, b.start).cmp(&(&b.modem, b.sim, a.start)));
+        totals
+    }
+
+    pub fn total(&self) -> u64 {
+        self.rx_bytes + self.tx_bytes
+    }
+}
+
+impl Display for UsageTotal {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "SIM {} ({}) {}: {}, rx {}, tx {}",
+            self.sim,
+            self.modem,
+            self.start,
+            bytes(self.total()),
+            bytes(self.rx_bytes),
+            bytes(self.tx_bytes)
+        )
+    }
+}
+
+/// A data limit for one SIM, as configured in `/data_usage/limits/config`.
+///
+/// Once the limit is reached the router stops the mobile connection until the
+/// period resets.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct DataLimit {
+    /// Config section ID.
+    pub id: String,
+    pub modem: String,
+    #[serde(deserialize_with = "de::from_str")]
+    pub sim: u8,
+    #[serde(default, deserialize_with = "de::flag")]
+    pub enabled: bool,
+    /// Data allowed per period, in megabytes.
+    #[serde(
+        default,
+        rename = "data_limit",
+        deserialize_with = "de::optional_from_str"
+    )]
+    pub limit: Option<u64>,
+    #[serde(default)]
+    pub period: UsagePeriod,
+    /// Day of the month a monthly period starts on, or weekday of a weekly one,
+    /// 1 being Monday.
+    #[serde(
+        default = "first_day",
+        rename = "day",
+        deserialize_with = "de::from_str"
+    )]
+    pub start_day: u32,
+    /// Send an SMS warning once usage reaches `warning_limit`.
+    #[serde(default, rename = "enable_warning", deserialize_with = "de::flag")]
+    pub warning: bool,
+    /// Usage in megabytes to warn at.
+    #[serde(default, deserialize_with = "de::optional_from_str")]
+    pub warning_limit: Option<u64>,
+}
+
+impl DataLimit {
+    /// The limit in bytes, if it is enabled.
+    pub fn limit_bytes(&self) -> Option<u64> {
+        self.limit
+            .filter(|_| self.enabled)
+            .map(|limit| limit.saturating_mul(MEGABYTE))
+    }
+
+    /// The billing period containing `date`.
+    pub fn billing_period(&self, date: NaiveDate) -> (NaiveDate, NaiveDate) {
+        self.period.range(date, self.start_day)
+    }
+}
+
+impl Display for DataLimit {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "SIM {} ({}): ", self.sim, self.modem)?;
+        match self.limit {
+            Some(limit) => write!(
+                f,
+                "{} per {}",
+                bytes(limit.saturating_mul(MEGABYTE)),
+                self.period
+            )?,
+            None => write!(f, "no limit")?,
+        }
+        if self.period != UsagePeriod::Day {
+            write!(f, " from day {}", self.start_day)?;
+        }
+        if let (true, Some(warning)) = (self.warning, self.warning_limit) {
+            write!(
+                f,
+                ", warning at {}",
+                bytes(warning.saturating_mul(MEGABYTE))
+            )?;
+        }
+        if !self.enabled {
+            write!(f, " (disabled)")?;
+        }
+        Ok(())
+    }
+}
+
+fn first_day() -> u32 {
+    1
+}
+
+/// A SIM's usage in its current billing period, against its data limit.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct BillingPeriodUsage {
+    pub modem: String,
+    pub sim: u8,
+    /// First day of the billing period.
+    pub start: NaiveDate,
+    /// First day of the next billing period.
+    pub end: NaiveDate,
+    /// Bytes used so far in the billing period.
+    pub used: u64,
+    /// Bytes used today.
+    pub today: u64,
+    /// Bytes allowed in the billing period, if a limit is enabled.
+    pub limit: Option<u64>,
+    /// Bytes that will have been used by the end of the billing period if usage
+    /// carries on at the same daily rate.
+    pub projected: u64,
+}
+
+impl BillingPeriodUsage {
+    /// Usage of every SIM with usage records or a data limit, as of `today`.
+    ///
+    /// SIMs without a data limit are billed by calendar month. Sorted by SIM.
+    pub fn summarize(records: &[DataUsage], limits: &[DataLimit], today: NaiveDate) -> Vec<Self> {
+        let mut sims: Vec<(&str, u8)> = records
+            .iter()
+            .map(|record| (record.modem.as_str(), record.sim))
+            .chain(limits.iter().map(|limit| (limit.modem.as_str(), limit.sim)))
+            .collect();
+        sims.sort();
+        sims.dedup();
+
+        sims.into_iter()
+            .map(|(modem, sim)| {
+                let limit = limits
+                    .iter()
+                    .find(|limit| limit.modem == modem && limit.sim == sim);
+                let (start, end) = match limit {
+                    Some(limit) => limit.billing_period(today),
+                    None => UsagePeriod::Month.range(today, 1),
+                };
+                let usage = |from: NaiveDate| -> u64 {
+                    records
+                        .iter()
+                        .filter(|record| record.modem == modem && record.sim == sim)
+                        .filter(|record| record.date >= from && record.date <= today)
+                        .map(DataUsage::total)
+                        .sum()
+                };
+                let used = usage(start);
+
+                BillingPeriodUsage {
+                    modem: modem.to_string(),
+                    sim,
+                    start,
+                    end,
+                    used,
+                    today: usage(today),
+                    limit: limit.and_then(DataLimit::limit_bytes),
+                    projected: project(used, start, end, today),
+                }
+            })
+            .collect()
+    }
+
+    /// Fetch the usage records and data limits and summarize them as of today,
+    /// in the local timezone.
+    pub async fn fetch(client: &TeltonikaClient) -> Result<Vec<Self>, Error> {
+        let (records, limits) = futures::join!(client.data_usage(), client.list_data_limits());
+
+        let records = records.and_then(Response::into_result)?;
+        let limits = limits.and_then(Response::into_result)?;
+
+        Ok(Self::summarize(
+            &records,
+            &limits,
+            Local::now().date_naive(),
+        ))
+    }
+
+    /// Percentage of the limit used, if there is one.
+    pub fn percent_used(&self) -> Option<f64> {
+        self.limit
+            .filter(|limit| *limit > 0)
+            .map(|limit| self.used as f64 * 100.0 / limit as f64)
+    }
+
+    /// Whether usage is heading over the limit by the end of the billing period.
+    pub fn projected_over(&self) -> bool {
+        self.limit.is_some_and(|limit| self.projected > limit)
+    }
+
+    /// Last day of the billing period.
+    pub fn last_day(&self) -> NaiveDate {
+        self.end - Days::new(1)
+    }
+}
+
+impl Display for BillingPeriodUsage {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "SIM {} ({}): {}", self.sim, self.modem, bytes(self.used))?;
+        if let (Some(limit), Some(percent)) = (self.limit, self.percent_used()) {
+            write!(f, " of {} ({:.0}%)", bytes(limit), percent)?;
+        }
+        write!(
+            f,
+            " since {}, {} today, projected {} by {}",
+            self.start,
+            bytes(self.today),
+            bytes(self.projected),
+            self.last_day()
+        )?;
+        if self.projected_over() {
+            write!(f, " (over the limit)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Extrapolate `used` bytes over the days of the period so far, counting today,
+/// to the whole period.
+fn project(used: u64, start: NaiveDate, end: NaiveDate, today: NaiveDate) -> u64 {
+    let elapsed = (today - start).num_days() + 1;
+    let days = (end - start).num_days();
+    if elapsed <= 0 || days <= 0 {
+        return used;
+    }
+
+    (used as u128 * days as u128 / elapsed as u128) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn date(date: &str) -> NaiveDate {
+        date.parse().unwrap()
+    }
+
+    fn record(sim: u8, day: &str, total: u64) -> DataUsage {
+        DataUsage {
+            modem: "2-1".to_string(),
+            sim,
+            date: date(day),
+            rx_bytes: total * 3 / 4,
+            tx_bytes: total / 4,
+        }
+    }
+
+    #[test]
+    fn test_range() {
+        let range = |period: UsagePeriod, day: &str, start_day| {
+            let (start, end) = period.range(date(day), start_day);
+            (start.to_string(), end.to_string())
+        };
+        let expect = |start: &str, end: &str| (start.to_string(), end.to_string());
+
+        assert_eq!(
+            range(UsagePeriod::Month, "2026-10-17", 1),
+            expect("2026-10-01", "2026-11-01")
+        );
+        assert_eq!(
+            range(UsagePeriod::Month, "2026-10-17", 20),
+            expect("2026-09-20", "2026-10-20")
+        );
+        assert_eq!(
+            range(UsagePeriod::Month, "2026-03-05", 31),
+            expect("2026-02-28", "2026-03-31")
+        );
+        // Friday, with weeks starting on Monday and on Saturday.
+        assert_eq!(
+            range(UsagePeriod::Week, "2026-10-16", 1),
+            expect("2026-10-12", "2026-10-19")
+        );
+        assert_eq!(
+            range(UsagePeriod::Week, "2026-10-16", 6),
+            expect("2026-10-10", "2026-10-17")
+        );
+        assert_eq!(
+            range(UsagePeriod::Day, "2026-10-17", 1),
+            expect("2026-10-17", "2026-10-18")
+        );
+    }
+
+    #[test]
+    fn test_by_period() {
+        let records = [
+            record(1, "2026-09-30", 100),
+            record(1, "2026-10-01", 200),
+            record(1, "2026-10-02", 400),
+            record(2, "2026-10-02", 40),
+        ];
+
+        let totals: Vec<(u8, String, u64)> = UsageTotal::by_period(&records, UsagePeriod::Month, 1)
+            .iter()
+            .map(|total| (total.sim, total.start.to_string(), total.total()))
+            .collect();
+        assert_eq!(
+            totals,
+            [
+                (1, "2026-10-01".to_string(), 600),
+                (1, "2026-09-01".to_string(), 100),
+                (2, "2026-10-01".to_string(), 40),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_summarize() {
+        let limit: DataLimit = serde_json::from_value(json!({
+            "id": "cfg01",
+            "modem": "2-1",
+            "sim": "1",
+            "enabled": "1",
+            "data_limit": "20000",
+            "period": "month",
+            "day": "1",
+        }))
+        .unwrap();
+        let gigabyte = 1_000_000_000;
+        let records: Vec<DataUsage> = (1..=10)
+            .map(|day| record(1, &format!("2026-10-{:02}", day), gigabyte))
+            .chain([record(2, "2026-10-10", gigabyte)])
+            .collect();
+
+        let usage = BillingPeriodUsage::summarize(&records, &[limit], date("2026-10-10"));
+
+        assert_eq!(usage.len(), 2);
+        assert_eq!(usage[0].used, 10 * gigabyte);
+        assert_eq!(usage[0].projected, 31 * gigabyte);
+        assert_eq!(usage[0].percent_used(), Some(50.0));
+        assert_eq!(
+            usage[0].to_string(),
+            "SIM 1 (2-1): 10.0 GB of 20.0 GB (50%) since 2026-10-01, 1.0 GB today, \
+             projected 31.0 GB by 2026-10-31 (over the limit)"
+        );
+        assert_eq!(usage[1].limit, None);
+        assert!(!usage[1].projected_over());
+    }
+
+    #[test]
+    fn test_huge_limit() {
+        let limit: DataLimit = serde_json::from_value(json!({
+            "id": "cfg01",
+            "modem": "2-1",
+            "sim": "1",
+            "enabled": "1",
+            "data_limit": u64::MAX.to_string(),
+            "period": "day",
+            "enable_warning": "1",
+            "warning_limit": u64::MAX.to_string(),
+        }))
+        .unwrap();
+
+        assert_eq!(limit.limit_bytes(), Some(u64::MAX));
+        assert!(limit.to_string().starts_with("SIM 1 (2-1): "));
+    }
+}
@@ -0,0 +1,207 @@
+//! Tolerant deserializers for the router's loosely typed JSON.
+
+use std::{fmt::Display, str::FromStr};
+
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use serde::{de, Deserialize, Deserializer};
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ValueOrString<T> {
+    Value(T),
+    String(String),
+}
+
+/// Deserialize a value that may be encoded natively or as a string, e.g. `7` or `"7"`.
+pub(crate) fn from_str<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr + Deserialize<'de>,
+    T::Err: Display,
+{
+    match ValueOrString::<T>::deserialize(deserializer)? {
+        ValueOrString::Value(value) => Ok(value),
+        ValueOrString::String(string) => string.trim().parse().map_err(de::Error::custom),
+    }
+}
+
+/// Like [`from_str`], but empty strings, placeholders such as `"N/A"` and
+/// missing values become `None`.
+pub(crate) fn optional_from_str<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr + Deserialize<'de>,
+{
+    Ok(
+        match Option::<ValueOrString<T>>::deserialize(deserializer)? {
+            Some(ValueOrString::Value(value)) => Some(value),
+            Some(ValueOrString::String(string)) => string.trim().parse().ok(),
+            None => None,
+        },
+    )
+}
+
+/// Deserialize a number that may be encoded as a JSON number or a string.
+pub(crate) fn optional_number<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    optional_from_str(deserializer)
+}
+
+/// Deserialize an optional string, with an empty one as `None`.
+pub(crate) fn non_empty<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(Option::<String>::deserialize(deserializer)?.filter(|value| !value.is_empty()))
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FlagValue {
+    Bool(bool),
+    Number(i64),
+    String(String),
+}
+
+/// Deserialize a flag encoded as a bool, `0`/`1` or a string such as `"1"` or `"true"`.
+pub(crate) fn flag<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<FlagValue>::deserialize(deserializer)? {
+        Some(FlagValue::Bool(value)) => Ok(value),
+        Some(FlagValue::Number(value)) => Ok(value != 0),
+        Some(FlagValue::String(value)) => match value.trim().to_ascii_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => Ok(true),
+            "" | "0" | "false" | "no" | "off" => Ok(false),
+            other => Err(de::Error::custom(format!("invalid flag: {}", other))),
+        },
+        None => Ok(false),
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ListOrString {
+    List(Vec<String>),
+    String(String),
+}
+
+/// Deserialize a list sent either as an array or as one space separated string,
+/// e.g. `["tcp", "udp"]` or `"tcp udp"`.
+pub(crate) fn words<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match Option::<ListOrString>::deserialize(deserializer)? {
+        Some(ListOrString::List(list)) => list,
+        Some(ListOrString::String(string)) => {
+            string.split_whitespace().map(str::to_string).collect()
+        }
+        None => Vec::new(),
+    })
+}
+
+/// Formats the router uses for dates, e.g. `Wed Mar 19 10:21:53 2025`.
+const DATE_FORMATS: &[&str] = &["%a %b %e %H:%M:%S %Y", "%Y-%m-%d %H:%M:%S"];
+
+/// Parse a date as sent by the router.
+///
+/// Accepts RFC 3339, Unix timestamps and the router's own formats. Dates
+/// without a timezone are interpreted as UTC.
+pub(crate) fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date.with_timezone(&Utc));
+    }
+    if let Ok(timestamp) = value.parse::<i64>() {
+        return Utc.timestamp_opt(timestamp, 0).single();
+    }
+
+    DATE_FORMATS.iter().find_map(|format| {
+        NaiveDateTime::parse_from_str(value, format)
+            .ok()
+            .map(|date| date.and_utc())
+    })
+}
+
+/// Deserialize a date using [`parse_date`].
+pub(crate) fn date<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match ValueOrString::<i64>::deserialize(deserializer)? {
+        ValueOrString::Value(timestamp) => Utc
+            .timestamp_opt(timestamp, 0)
+            .single()
+            .ok_or_else(|| de::Error::custom(format!("invalid timestamp: {}", timestamp))),
+        ValueOrString::String(string) => parse_date(&string)
+            .ok_or_else(|| de::Error::custom(format!("invalid date: {}", string))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Flag(#[serde(deserialize_with = "flag")] bool);
+
+    #[derive(Deserialize)]
+    struct Words(#[serde(deserialize_with = "words")] Vec<String>);
+
+    #[derive(Deserialize)]
+    struct Date(#[serde(deserialize_with = "date")] DateTime<Utc>);
+
+    #[test]
+    fn test_flag() {
+        for (json, expected) in [
+            ("true", true),
+            ("1", true),
+            (r#""1""#, true),
+            (r#""true""#, true),
+            ("false", false),
+            ("0", false),
+            (r#""0""#, false),
+            (r#""""#, false),
+            ("null", false),
+        ] {
+            let Flag(value) = serde_json::from_str(json).unwrap();
+            assert_eq!(value, expected, "{}", json);
+        }
+
+        assert!(serde_json::from_str::<Flag>(r#""maybe""#).is_err());
+    }
+
+    #[test]
+    fn test_words() {
+        for json in [r#"["tcp", "udp"]"#, r#""tcp udp""#, r#"" tcp  udp ""#] {
+            let Words(value) = serde_json::from_str(json).unwrap();
+            assert_eq!(value, ["tcp", "udp"], "{}", json);
+        }
+
+        let Words(value) = serde_json::from_str("null").unwrap();
+        assert!(value.is_empty());
+    }
+
+    #[test]
+    fn test_date() {
+        let expected = Utc.with_ymd_and_hms(2025, 3, 19, 10, 21, 53).unwrap();
+
+        for json in [
+            r#""Wed Mar 19 10:21:53 2025""#,
+            r#""2025-03-19 10:21:53""#,
+            r#""2025-03-19T10:21:53Z""#,
+            "1742379713",
+            r#""1742379713""#,
+        ] {
+            let Date(value) = serde_json::from_str(json).unwrap();
+            assert_eq!(value, expected, "{}", json);
+        }
+    }
+}
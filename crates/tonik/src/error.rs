@@ -0,0 +1,94 @@
+use std::fmt::{self, Display, Formatter};
+
+use crate::ApiError;
+
+/// API error code returned by RutOS when a request is made without a valid session.
+pub const UNAUTHORIZED_ACCESS_CODE: i32 = 118;
+
+/// Errors returned by [`TeltonikaClient`](crate::TeltonikaClient).
+#[derive(Debug)]
+pub enum Error {
+    /// The HTTP client could not be built.
+    Client(reqwest::Error),
+    /// The request could not be sent or the response could not be read.
+    Transport(reqwest::Error),
+    /// The router responded with a body that could not be decoded.
+    Decode {
+        source: serde_json::Error,
+        body: String,
+    },
+    /// The session token is missing, invalid or has expired.
+    Unauthorized(Vec<ApiError>),
+    /// The router answered `success: false`.
+    Api(Vec<ApiError>),
+    /// The router answered `success: true` but did not include any data.
+    MissingData,
+}
+
+impl Error {
+    /// Build an error from the `errors` field of an unsuccessful response.
+    pub fn from_api_errors(errors: Vec<ApiError>) -> Self {
+        if errors
+            .iter()
+            .any(|error| error.code == UNAUTHORIZED_ACCESS_CODE)
+        {
+            Error::Unauthorized(errors)
+        } else {
+            Error::Api(errors)
+        }
+    }
+
+    /// Whether the error was caused by a missing or expired session.
+    pub fn is_unauthorized(&self) -> bool {
+        matches!(self, Error::Unauthorized(_))
+    }
+
+    /// The API errors reported by the router, if any.
+    pub fn api_errors(&self) -> &[ApiError] {
+        match self {
+            Error::Unauthorized(errors) | Error::Api(errors) => errors,
+            _ => &[],
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Error::Client(err) => write!(f, "failed to build HTTP client: {}", err),
+            Error::Transport(err) => write!(f, "transport error: {}", err),
+            Error::Decode { source, body } => {
+                write!(f, "failed to decode response: {} (body: {})", source, body)
+            }
+            Error::Unauthorized(errors) if errors.is_empty() => write!(f, "unauthorized"),
+            Error::Unauthorized(errors) => write!(f, "unauthorized: {}", join(errors)),
+            Error::Api(errors) if errors.is_empty() => write!(f, "request was unsuccessful"),
+            Error::Api(errors) => write!(f, "api error: {}", join(errors)),
+            Error::MissingData => write!(f, "response did not contain any data"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Client(err) | Error::Transport(err) => Some(err),
+            Error::Decode { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        Error::Transport(err)
+    }
+}
+
+fn join(errors: &[ApiError]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
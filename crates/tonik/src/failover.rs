@@ -0,0 +1,177 @@
+//! WAN failover and load balancing, provided by mwan3 on the router.
+
+use std::fmt::{self, Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+use crate::de;
+
+/// An interface taking part in failover, as configured in `/failover/interfaces/config`.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct FailoverMember {
+    /// Config section ID.
+    pub id: String,
+    /// Network interface, e.g. `wan` or `mob1s1a1`.
+    pub interface: String,
+    #[serde(default, deserialize_with = "de::flag")]
+    pub enabled: bool,
+    /// Priority: traffic goes through the online member with the lowest metric.
+    #[serde(default, deserialize_with = "de::optional_from_str")]
+    pub metric: Option<u32>,
+    /// Hosts pinged to check the interface is online.
+    #[serde(default, deserialize_with = "de::words")]
+    pub track_ip: Vec<String>,
+    /// Number of hosts that must answer for the interface to be online.
+    #[serde(default, deserialize_with = "de::optional_from_str")]
+    pub reliability: Option<u32>,
+    /// Seconds between health checks.
+    #[serde(default, deserialize_with = "de::optional_from_str")]
+    pub interval: Option<u32>,
+    /// Failed checks in a row before the interface is marked offline.
+    #[serde(default, deserialize_with = "de::optional_from_str")]
+    pub down: Option<u32>,
+    /// Successful checks in a row before the interface is marked online again.
+    #[serde(default, deserialize_with = "de::optional_from_str")]
+    pub up: Option<u32>,
+}
+
+impl FailoverMember {
+    /// Make `interface` the preferred member, keeping the order of the others
+    /// behind it. Metrics become 1, 2, 3 and so on, and the preferred member is
+    /// enabled.
+    ///
+    /// Returns the members whose config changed, or `None` if `interface` isn't
+    /// a member.
+    pub fn prefer(members: &[Self], interface: &str) -> Option<Vec<Self>> {
+        let preferred = members
+            .iter()
+            .find(|member| member.interface == interface)?;
+
+        let mut others: Vec<&Self> = members
+            .iter()
+            .filter(|member| member.id != preferred.id)
+            .collect();
+        others.sort_by_key(|member| member.metric.unwrap_or(u32::MAX));
+
+        let changed = std::iter::once(preferred)
+            .chain(others)
+            .zip(1..)
+            .filter_map(|(member, metric)| {
+                let enabled = member.enabled || member.id == preferred.id;
+                let updated = FailoverMember {
+                    metric: Some(metric),
+                    enabled,
+                    ..member.clone()
+                };
+                (updated != *member).then_some(updated)
+            })
+            .collect();
+
+        Some(changed)
+    }
+}
+
+/// Whether a failover interface is usable, according to its health checks.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FailoverState {
+    Online,
+    Offline,
+    Disabled,
+    #[serde(other)]
+    Unknown,
+}
+
+impl Display for FailoverState {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(match self {
+            FailoverState::Online => "online",
+            FailoverState::Offline => "offline",
+            FailoverState::Disabled => "disabled",
+            FailoverState::Unknown => "unknown",
+        })
+    }
+}
+
+/// Current state of a failover interface, as reported by `/failover/status`.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct FailoverStatus {
+    pub interface: String,
+    pub status: FailoverState,
+    /// Seconds since the interface was last marked online.
+    #[serde(default, deserialize_with = "de::optional_from_str")]
+    pub uptime: Option<u64>,
+    #[serde(default)]
+    pub track_ip: Vec<TrackedHost>,
+}
+
+/// Result of the health checks against one host.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct TrackedHost {
+    pub ip: String,
+    /// `up` or `down`.
+    pub status: String,
+    /// Round trip time in milliseconds.
+    #[serde(default, deserialize_with = "de::optional_number")]
+    pub latency: Option<f64>,
+    /// Percentage of pings lost.
+    #[serde(default, deserialize_with = "de::optional_number")]
+    pub packetloss: Option<f64>,
+}
+
+impl FailoverStatus {
+    pub fn is_online(&self) -> bool {
+        self.status == FailoverState::Online
+    }
+}
+
+impl Display for FailoverStatus {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.interface, self.status)?;
+        for host in &self.track_ip {
+            write!(f, ", {} {}", host.ip, host.status)?;
+            if let Some(latency) = host.latency {
+                write!(f, " {} ms", latency)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(id: &str, interface: &str, metric: u32, enabled: bool) -> FailoverMember {
+        FailoverMember {
+            id: id.to_string(),
+            interface: interface.to_string(),
+            enabled,
+            metric: Some(metric),
+            track_ip: vec!["1.1.1.1".to_string()],
+            reliability: None,
+            interval: None,
+            down: None,
+            up: None,
+        }
+    }
+
+    #[test]
+    fn test_prefer() {
+        let members = [
+            member("cfg01", "wan", 1, true),
+            member("cfg02", "mob1s1a1", 3, true),
+            member("cfg03", "wwan", 2, false),
+        ];
+
+        let changed = FailoverMember::prefer(&members, "wwan").unwrap();
+        let metrics: Vec<(&str, Option<u32>, bool)> = changed
+            .iter()
+            .map(|member| (member.interface.as_str(), member.metric, member.enabled))
+            .collect();
+        assert_eq!(metrics, [("wwan", Some(1), true), ("wan", Some(2), true)]);
+
+        assert!(FailoverMember::prefer(&members, "wan").unwrap().is_empty());
+        assert!(FailoverMember::prefer(&members, "lan").is_none());
+    }
+}
@@ -0,0 +1,308 @@
+//! Firewall port forwards, traffic rules and zones.
+//!
+//! Each is a config section on the router, identified by an ID such as
+//! `cfg0a92bd` that the router assigns when the section is created.
+
+use std::fmt::{self, Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+use crate::de;
+
+/// The ID of a config section, as returned when one is deleted.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct SectionId {
+    pub id: String,
+}
+
+/// Traffic arriving in the `src` zone on port `src_dport` is forwarded to
+/// `dest_ip`, on `dest_port` or else the same port.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct PortForward {
+    /// Config section ID. Empty until the port forward is created.
+    #[serde(default)]
+    pub id: String,
+    #[serde(default, deserialize_with = "de::non_empty")]
+    pub name: Option<String>,
+    #[serde(default = "enabled", deserialize_with = "de::flag")]
+    pub enabled: bool,
+    /// Protocols to forward, e.g. `tcp` and `udp`.
+    #[serde(default, deserialize_with = "de::words")]
+    pub proto: Vec<String>,
+    /// Zone the traffic arrives from, usually `wan`.
+    #[serde(default)]
+    pub src: String,
+    /// Only forward traffic from this address or subnet.
+    #[serde(default, deserialize_with = "de::non_empty")]
+    pub src_ip: Option<String>,
+    /// External port or range, e.g. `8080` or `8000-8010`.
+    #[serde(default)]
+    pub src_dport: String,
+    #[serde(default)]
+    pub dest: String,
+    #[serde(default)]
+    pub dest_ip: String,
+    #[serde(default, deserialize_with = "de::non_empty")]
+    pub dest_port: Option<String>,
+}
+
+impl PortForward {
+    /// Forward TCP and UDP on `port` of the WAN to the same port of a LAN host.
+    pub fn new(port: &str, dest_ip: &str) -> Self {
+        PortForward {
+            id: String::new(),
+            name: None,
+            enabled: true,
+            proto: vec!["tcp".to_string(), "udp".to_string()],
+            src: "wan".to_string(),
+            src_ip: None,
+            src_dport: port.to_string(),
+            dest: "lan".to_string(),
+            dest_ip: dest_ip.to_string(),
+            dest_port: None,
+        }
+    }
+
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    /// Forward to this port of the host instead of the external one.
+    pub fn dest_port(mut self, port: &str) -> Self {
+        self.dest_port = Some(port.to_string());
+        self
+    }
+
+    pub fn proto(mut self, proto: &[&str]) -> Self {
+        self.proto = proto.iter().map(|proto| proto.to_string()).collect();
+        self
+    }
+}
+
+impl Display for PortForward {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {}: {} {} -> {}:{}",
+            self.id,
+            self.name.as_deref().unwrap_or("-"),
+            self.proto.join("/"),
+            self.src_dport,
+            self.dest_ip,
+            self.dest_port.as_deref().unwrap_or(&self.src_dport),
+        )?;
+        if !self.enabled {
+            write!(f, " (disabled)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Accepts, rejects or drops traffic matching zones, addresses and ports.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct TrafficRule {
+    /// Config section ID. Empty until the rule is created.
+    #[serde(default)]
+    pub id: String,
+    #[serde(default, deserialize_with = "de::non_empty")]
+    pub name: Option<String>,
+    #[serde(default = "enabled", deserialize_with = "de::flag")]
+    pub enabled: bool,
+    #[serde(default, deserialize_with = "de::words")]
+    pub proto: Vec<String>,
+    /// Zone the traffic comes from, or `*` for any.
+    #[serde(default, deserialize_with = "de::non_empty")]
+    pub src: Option<String>,
+    #[serde(default, deserialize_with = "de::non_empty")]
+    pub src_ip: Option<String>,
+    #[serde(default, deserialize_with = "de::non_empty")]
+    pub src_port: Option<String>,
+    /// Zone the traffic goes to. Rules without one apply to the router itself.
+    #[serde(default, deserialize_with = "de::non_empty")]
+    pub dest: Option<String>,
+    #[serde(default, deserialize_with = "de::non_empty")]
+    pub dest_ip: Option<String>,
+    #[serde(default, deserialize_with = "de::non_empty")]
+    pub dest_port: Option<String>,
+    /// `ACCEPT`, `REJECT` or `DROP`.
+    #[serde(default)]
+    pub target: String,
+    /// `ipv4`, `ipv6` or `any`.
+    #[serde(default, deserialize_with = "de::non_empty")]
+    pub family: Option<String>,
+}
+
+impl TrafficRule {
+    /// A rule applying `target`, e.g. `ACCEPT`, to all traffic until narrowed down.
+    pub fn new(name: &str, target: &str) -> Self {
+        TrafficRule {
+            id: String::new(),
+            name: Some(name.to_string()),
+            enabled: true,
+            proto: Vec::new(),
+            src: None,
+            src_ip: None,
+            src_port: None,
+            dest: None,
+            dest_ip: None,
+            dest_port: None,
+            target: target.to_string(),
+            family: None,
+        }
+    }
+}
+
+impl Display for TrafficRule {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let endpoint = |zone: &Option<String>, ip: &Option<String>, port: &Option<String>| {
+            let mut endpoint = zone.clone().unwrap_or_else(|| "device".to_string());
+            if let Some(ip) = ip {
+                endpoint += &format!(" {}", ip);
+            }
+            if let Some(port) = port {
+                endpoint += &format!(" port {}", port);
+            }
+            endpoint
+        };
+
+        write!(
+            f,
+            "{} {}: {} {} from {} to {}",
+            self.id,
+            self.name.as_deref().unwrap_or("-"),
+            self.target,
+            if self.proto.is_empty() {
+                "any".to_string()
+            } else {
+                self.proto.join("/")
+            },
+            endpoint(&self.src, &self.src_ip, &self.src_port),
+            endpoint(&self.dest, &self.dest_ip, &self.dest_port),
+        )?;
+        if !self.enabled {
+            write!(f, " (disabled)")?;
+        }
+        Ok(())
+    }
+}
+
+/// A group of networks sharing default policies, e.g. `lan` or `wan`.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct Zone {
+    /// Config section ID. Empty until the zone is created.
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    /// Network interfaces in the zone.
+    #[serde(default, deserialize_with = "de::words")]
+    pub network: Vec<String>,
+    /// Policy for traffic to the router, `ACCEPT`, `REJECT` or `DROP`.
+    #[serde(default)]
+    pub input: String,
+    /// Policy for traffic from the router.
+    #[serde(default)]
+    pub output: String,
+    /// Policy for traffic between networks of the zone.
+    #[serde(default)]
+    pub forward: String,
+    /// Masquerade outgoing traffic, as for a WAN.
+    #[serde(default, deserialize_with = "de::flag")]
+    pub masq: bool,
+    #[serde(default, deserialize_with = "de::flag")]
+    pub mtu_fix: bool,
+}
+
+impl Zone {
+    /// A zone accepting traffic to and from the router and rejecting forwarded traffic.
+    pub fn new(name: &str) -> Self {
+        Zone {
+            id: String::new(),
+            name: name.to_string(),
+            network: Vec::new(),
+            input: "ACCEPT".to_string(),
+            output: "ACCEPT".to_string(),
+            forward: "REJECT".to_string(),
+            masq: false,
+            mtu_fix: false,
+        }
+    }
+}
+
+impl Display for Zone {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {}: {}, input {}, output {}, forward {}",
+            self.id,
+            self.name,
+            self.network.join(" "),
+            self.input,
+            self.output,
+            self.forward
+        )?;
+        if self.masq {
+            write!(f, ", masquerading")?;
+        }
+        Ok(())
+    }
+}
+
+fn enabled() -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::section_body;
+
+    #[test]
+    fn test_port_forward_body() {
+        let forward = PortForward::new("8080", "192.168.7.20")
+            .name("contractor")
+            .dest_port("80");
+
+        assert_eq!(
+            section_body(&forward),
+            json!({
+                "data": {
+                    "name": "contractor",
+                    "enabled": "1",
+                    "proto": ["tcp", "udp"],
+                    "src": "wan",
+                    "src_dport": "8080",
+                    "dest": "lan",
+                    "dest_ip": "192.168.7.20",
+                    "dest_port": "80",
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_deserialize_port_forward() {
+        let forward: PortForward = serde_json::from_value(json!({
+            "id": "cfg0a92bd",
+            "name": "camera",
+            "enabled": "0",
+            "proto": "tcp udp",
+            "src": "wan",
+            "src_dport": "8080",
+            "dest": "lan",
+            "dest_ip": "192.168.7.20",
+            "dest_port": "80",
+            "target": "DNAT",
+        }))
+        .unwrap();
+
+        assert!(!forward.enabled);
+        assert_eq!(forward.proto, ["tcp", "udp"]);
+        assert_eq!(
+            forward.to_string(),
+            "cfg0a92bd camera: tcp/udp 8080 -> 192.168.7.20:80 (disabled)"
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use std::fmt::{self, Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+use crate::de;
+
+/// Firmware offered by the FOTA server, as reported by `/firmware/device/updates/status`.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct FirmwareUpdatesStatus {
+    pub device: FirmwareUpdate,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct FirmwareUpdate {
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Size of the image in bytes.
+    #[serde(default, deserialize_with = "de::optional_from_str")]
+    pub size: Option<u64>,
+}
+
+impl FirmwareUpdate {
+    /// The version on offer, unless the server reported none.
+    pub fn version(&self) -> Option<&str> {
+        self.version
+            .as_deref()
+            .map(str::trim)
+            .filter(|version| !version.is_empty() && *version != "N/A")
+    }
+
+    /// Whether the version on offer differs from `current`.
+    pub fn differs_from(&self, current: &str) -> bool {
+        self.version().is_some_and(|version| version != current)
+    }
+}
+
+/// State of a firmware download.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FirmwareProcess {
+    Started,
+    Succeeded,
+    Failed,
+    /// No download is in progress.
+    #[serde(other)]
+    Idle,
+}
+
+/// Progress of a firmware download, as reported by `/firmware/device/progress/status`.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub struct FirmwareProgress {
+    pub process: FirmwareProcess,
+    #[serde(deserialize_with = "de::from_str")]
+    pub percents: u8,
+}
+
+impl FirmwareProgress {
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self.process,
+            FirmwareProcess::Succeeded | FirmwareProcess::Failed
+        )
+    }
+}
+
+impl Display for FirmwareProgress {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self.process {
+            FirmwareProcess::Started => write!(f, "Downloading: {}%", self.percents),
+            FirmwareProcess::Succeeded => write!(f, "Downloaded"),
+            FirmwareProcess::Failed => write!(f, "Download failed"),
+            FirmwareProcess::Idle => write!(f, "Not downloading"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_firmware_update() {
+        let update: FirmwareUpdatesStatus =
+            serde_json::from_value(json!({ "device": { "version": "N/A", "size": "" } })).unwrap();
+        assert_eq!(update.device.version(), None);
+        assert!(!update.device.differs_from("RUTX_R_00.07.06.3"));
+
+        let update: FirmwareUpdatesStatus = serde_json::from_value(json!({
+            "device": { "version": "RUTX_R_00.07.07.1", "size": "21495808" }
+        }))
+        .unwrap();
+        assert_eq!(update.device.size, Some(21495808));
+        assert!(update.device.differs_from("RUTX_R_00.07.06.3"));
+    }
+
+    #[test]
+    fn test_firmware_progress() {
+        let progress: FirmwareProgress =
+            serde_json::from_value(json!({ "process": "started", "percents": "42" })).unwrap();
+        assert_eq!(progress.to_string(), "Downloading: 42%");
+        assert!(!progress.is_finished());
+
+        let progress: FirmwareProgress =
+            serde_json::from_value(json!({ "process": "", "percents": 0 })).unwrap();
+        assert_eq!(progress.process, FirmwareProcess::Idle);
+    }
+}
@@ -1,88 +1,341 @@
 use std::{
     collections::HashMap,
     fmt::{self, Display, Formatter},
+    net::IpAddr,
+    time::{Duration, Instant},
 };
 
+use chrono::{DateTime, Utc};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::json;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, OnceCell, RwLock};
+
+mod builder;
+mod data_usage;
+mod de;
+mod error;
+mod failover;
+mod firewall;
+mod firmware;
+mod mac;
+mod modem;
+mod network;
+#[cfg(feature = "oui")]
+mod oui;
+pub mod presence;
+#[cfg(feature = "profiles")]
+pub mod profile;
+mod sim;
+pub mod sms;
+pub mod table;
+mod wireless;
+
+pub use builder::{ClientBuilder, Scheme};
+pub use data_usage::{BillingPeriodUsage, DataLimit, DataUsage, UsagePeriod, UsageTotal};
+pub use error::{Error, UNAUTHORIZED_ACCESS_CODE};
+pub use failover::{FailoverMember, FailoverState, FailoverStatus, TrackedHost};
+pub use firewall::{PortForward, SectionId, TrafficRule, Zone};
+pub use firmware::{FirmwareProcess, FirmwareProgress, FirmwareUpdate, FirmwareUpdatesStatus};
+pub use mac::{MacAddress, ParseMacAddressError};
+pub use modem::ModemStatus;
+pub use network::NetworkInterfaceStatus;
+pub use reqwest::Certificate;
+pub use sim::{SimCard, SimSlot, SimSwitchRule};
+pub use wireless::{SignalQuality, WirelessClient};
+
+/// How long before the session token expires that it is proactively renewed.
+const SESSION_RENEWAL_MARGIN: Duration = Duration::from_secs(30);
+
+/// How long a firmware upload may take, in place of the client's timeout, which is
+/// sized for small API requests rather than a whole image over a mobile link.
+const FIRMWARE_UPLOAD_TIMEOUT: Duration = Duration::from_secs(60 * 60);
 
 pub struct TeltonikaClient {
-    host: String,
+    base_url: String,
     reqwest: reqwest::Client,
-    auth: RwLock<Option<LoginData>>,
+    auth: RwLock<Option<Session>>,
+    credentials: Option<Credentials>,
+    renewal: Mutex<()>,
+    primary_modem: OnceCell<String>,
+}
+
+struct Credentials {
+    username: String,
+    password: String,
+}
+
+struct Session {
+    data: LoginData,
+    expires_at: Instant,
+}
+
+impl Session {
+    fn new(data: LoginData) -> Self {
+        let lifetime = Duration::from_secs(data.expires.max(0) as u64);
+        Session {
+            data,
+            expires_at: Instant::now() + lifetime,
+        }
+    }
+
+    fn needs_renewal(&self) -> bool {
+        Instant::now() + SESSION_RENEWAL_MARGIN >= self.expires_at
+    }
 }
 
 impl TeltonikaClient {
+    /// Create a client for `https://{host}/api` that accepts any certificate.
+    ///
+    /// Use [`TeltonikaClient::builder`] to validate certificates or change the
+    /// scheme, port and timeouts.
     pub fn new(host: String) -> Self {
+        Self::builder(host)
+            .danger_accept_invalid_certs(true)
+            .build()
+            .expect("Failed to build HTTP client")
+    }
+
+    pub fn builder(host: impl Into<String>) -> ClientBuilder {
+        ClientBuilder::new(host)
+    }
+
+    fn from_parts(base_url: String, reqwest: reqwest::Client) -> Self {
         TeltonikaClient {
-            host,
-            reqwest: reqwest::Client::builder()
-                .gzip(true)
-                .danger_accept_invalid_certs(true)
-                .build()
-                .unwrap(),
+            base_url,
+            reqwest,
             auth: RwLock::new(None),
+            credentials: None,
+            renewal: Mutex::new(()),
+            primary_modem: OnceCell::new(),
         }
     }
 
+    /// The URL all API paths are relative to, e.g. `https://192.168.1.1/api`.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Remember credentials so the session is renewed automatically.
+    ///
+    /// The client re-authenticates shortly before the session token expires and
+    /// retries a request once if the router reports the session as unauthorized.
+    pub fn with_credentials(mut self, username: &str, password: &str) -> Self {
+        self.credentials = Some(Credentials {
+            username: username.to_string(),
+            password: password.to_string(),
+        });
+        self
+    }
+
     pub async fn authenticate(
         &self,
         username: &str,
         password: &str,
-    ) -> Result<Response<LoginData>, reqwest::Error> {
+    ) -> Result<Response<LoginData>, Error> {
         let response = self.login(username, password).await?;
-        *self.auth.write().await = response.data.clone();
+        *self.auth.write().await = response.data.clone().map(Session::new);
         Ok(response)
     }
 
+    /// Time remaining until the current session token expires.
+    pub async fn session_expires_in(&self) -> Option<Duration> {
+        self.auth
+            .read()
+            .await
+            .as_ref()
+            .map(|session| session.expires_at.saturating_duration_since(Instant::now()))
+    }
+
+    /// The current session token, e.g. to cache it with [`session_expires_in`]
+    /// and reuse it with [`restore_session`].
+    ///
+    /// [`session_expires_in`]: TeltonikaClient::session_expires_in
+    /// [`restore_session`]: TeltonikaClient::restore_session
+    pub async fn session_token(&self) -> Option<String> {
+        self.current_token().await
+    }
+
+    /// Use a token from an earlier session instead of logging in.
+    ///
+    /// The token isn't checked; call [`session_status`](TeltonikaClient::session_status)
+    /// to find out whether the router still accepts it.
+    pub async fn restore_session(&self, token: String, expires_in: Duration) {
+        let username = self
+            .credentials
+            .as_ref()
+            .map(|credentials| credentials.username.clone())
+            .unwrap_or_default();
+        let data = LoginData {
+            username,
+            token,
+            expires: expires_in.as_secs() as i64,
+        };
+
+        *self.auth.write().await = Some(Session::new(data));
+    }
+
     /// Send a POST request to the router.
-    pub async fn post<R, T>(
-        &self,
-        path: &str,
-        body: Option<R>,
-    ) -> Result<Response<T>, reqwest::Error>
+    pub async fn post<R, T>(&self, path: &str, body: Option<R>) -> Result<Response<T>, Error>
     where
         R: Serialize,
         T: DeserializeOwned,
     {
         let mut request = self
             .reqwest
-            .post(format!("https://{}/api{}", self.host, path).as_str());
-
-        if let Some(auth) = self.auth.read().await.as_ref() {
-            println!("Adding AUTH");
-            request = request.bearer_auth(auth.token.as_str());
-        }
+            .post(format!("{}{}", self.base_url, path).as_str());
 
         if let Some(body) = body {
             request = request.json(&body);
         }
 
-        let response = request.send().await?.json::<Response<T>>().await?;
+        self.send(request).await
+    }
+
+    /// Send a PUT request to the router.
+    pub async fn put<R, T>(&self, path: &str, body: R) -> Result<Response<T>, Error>
+    where
+        R: Serialize,
+        T: DeserializeOwned,
+    {
+        let request = self
+            .reqwest
+            .put(format!("{}{}", self.base_url, path).as_str())
+            .json(&body);
 
-        Ok(response)
+        self.send(request).await
+    }
+
+    /// Send a DELETE request to the router.
+    pub async fn delete<T>(&self, path: &str) -> Result<Response<T>, Error>
+    where
+        T: DeserializeOwned,
+    {
+        let request = self
+            .reqwest
+            .delete(format!("{}{}", self.base_url, path).as_str());
+
+        self.send(request).await
     }
 
     /// Send a GET request to the router.
-    pub async fn get<T>(&self, path: &str) -> Result<Response<T>, reqwest::Error>
+    pub async fn get<T>(&self, path: &str) -> Result<Response<T>, Error>
     where
         T: DeserializeOwned,
     {
-        let mut request = self
+        let request = self
             .reqwest
-            .get(format!("https://{}/api{}", self.host, path).as_str());
+            .get(format!("{}{}", self.base_url, path).as_str());
+
+        self.send(request).await
+    }
 
-        if let Some(auth) = self.auth.read().await.as_ref() {
-            request = request.bearer_auth(auth.token.as_str());
+    /// Send a request, renewing the session before or after it as required.
+    async fn send<T>(&self, request: reqwest::RequestBuilder) -> Result<Response<T>, Error>
+    where
+        T: DeserializeOwned,
+    {
+        if self.credentials.is_none() {
+            return self.execute(request).await.map(|(_, response)| response);
         }
 
-        let response = request.send().await?.json::<Response<T>>().await?;
+        let needs_renewal = match self.auth.read().await.as_ref() {
+            Some(session) => session.needs_renewal(),
+            None => true,
+        };
+        if needs_renewal {
+            self.renew_session(None).await?;
+        }
 
-        Ok(response)
+        let Some(retry) = request.try_clone() else {
+            return self.execute(request).await.map(|(_, response)| response);
+        };
+
+        let stale_token = match self.execute(request).await {
+            Ok((_, response)) if !is_unauthorized(&response) => return Ok(response),
+            Ok((token, _)) => token,
+            Err(Error::Unauthorized(_)) => self.current_token().await,
+            Err(err) => return Err(err),
+        };
+
+        self.renew_session(stale_token).await?;
+        self.execute(retry).await.map(|(_, response)| response)
+    }
+
+    /// Send a request with the current session token, returning the token used.
+    async fn execute<T>(
+        &self,
+        mut request: reqwest::RequestBuilder,
+    ) -> Result<(Option<String>, Response<T>), Error>
+    where
+        T: DeserializeOwned,
+    {
+        let token = self.current_token().await;
+        if let Some(token) = &token {
+            request = request.bearer_auth(token);
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        let body = response.text().await?;
+
+        if status == reqwest::StatusCode::UNAUTHORIZED {
+            let errors = serde_json::from_str::<Response<serde_json::Value>>(&body)
+                .ok()
+                .and_then(|response| response.errors)
+                .unwrap_or_default();
+            return Err(Error::Unauthorized(errors));
+        }
+
+        let response =
+            serde_json::from_str(&body).map_err(|source| Error::Decode { source, body })?;
+
+        Ok((token, response))
+    }
+
+    async fn current_token(&self) -> Option<String> {
+        self.auth
+            .read()
+            .await
+            .as_ref()
+            .map(|session| session.data.token.clone())
+    }
+
+    /// Log in again with the remembered credentials.
+    ///
+    /// When `stale_token` is given the session is only renewed if it is still the
+    /// current one, so concurrent requests don't each trigger a login.
+    async fn renew_session(&self, stale_token: Option<String>) -> Result<(), Error> {
+        let Some(credentials) = &self.credentials else {
+            return Ok(());
+        };
+
+        let _guard = self.renewal.lock().await;
+
+        let current = self.auth.read().await;
+        let still_valid = match (current.as_ref(), &stale_token) {
+            (Some(session), Some(stale)) => session.data.token != *stale,
+            (Some(session), None) => !session.needs_renewal(),
+            (None, _) => false,
+        };
+        drop(current);
+
+        if still_valid {
+            return Ok(());
+        }
+
+        let request = self
+            .reqwest
+            .post(format!("{}/login", self.base_url).as_str())
+            .json(&login_body(&credentials.username, &credentials.password));
+        let (_, response) = self.execute::<LoginData>(request).await?;
+        let data = response.into_result()?;
+
+        *self.auth.write().await = Some(Session::new(data));
+
+        Ok(())
     }
 
-    pub async fn session_status(&self) -> Result<Response<SessionStatus>, reqwest::Error> {
+    pub async fn session_status(&self) -> Result<Response<SessionStatus>, Error> {
         self.get("/session/status").await
     }
 
@@ -90,78 +343,498 @@ impl TeltonikaClient {
         &self,
         username: &str,
         password: &str,
-    ) -> Result<Response<LoginData>, reqwest::Error> {
-        self.post(
-            "/login",
-            Some(&json!({
-                "username": username,
-                "password": password,
-            })),
-        )
-        .await
+    ) -> Result<Response<LoginData>, Error> {
+        let request = self
+            .reqwest
+            .post(format!("{}/login", self.base_url).as_str())
+            .json(&login_body(username, password));
+
+        self.execute(request).await.map(|(_, response)| response)
     }
 
-    pub async fn dhcp_leases_ipv4_status(
-        &self,
-    ) -> Result<Response<Vec<DhcpLease>>, reqwest::Error> {
+    pub async fn dhcp_leases_ipv4_status(&self) -> Result<Response<Vec<DhcpLease>>, Error> {
         self.get("/dhcp/leases/ipv4/status").await
     }
 
-    pub async fn dhcp_leases_ipv6_status(
-        &self,
-    ) -> Result<Response<Vec<DhcpLeaseV6>>, reqwest::Error> {
+    pub async fn dhcp_leases_ipv6_status(&self) -> Result<Response<Vec<DhcpLeaseV6>>, Error> {
         self.get("/dhcp/leases/ipv6/status").await
     }
 
-    pub async fn firmware_device_status(
-        &self,
-    ) -> Result<Response<FirmwareDeviceStatus>, reqwest::Error> {
+    pub async fn firmware_device_status(&self) -> Result<Response<FirmwareDeviceStatus>, Error> {
         self.get("/firmware/device/status").await
     }
 
-    pub async fn firmware_actions_fota_download(&self) -> Result<Response<()>, reqwest::Error> {
+    /// Ask the FOTA server which firmware is available for the device.
+    pub async fn firmware_device_updates_status(
+        &self,
+    ) -> Result<Response<FirmwareUpdatesStatus>, Error> {
+        self.get("/firmware/device/updates/status").await
+    }
+
+    /// Start downloading the latest firmware from the FOTA server.
+    ///
+    /// Follow the download with [`TeltonikaClient::firmware_device_progress_status`].
+    pub async fn firmware_actions_fota_download(&self) -> Result<Response<()>, Error> {
         self.post("/firmware/actions/fota_download", None::<()>)
             .await
     }
 
-    pub async fn gps_position_status(&self) -> Result<Response<GpsPositionStatus>, reqwest::Error> {
+    pub async fn firmware_device_progress_status(
+        &self,
+    ) -> Result<Response<FirmwareProgress>, Error> {
+        self.get("/firmware/device/progress/status").await
+    }
+
+    /// Upload a firmware image, to be installed with [`TeltonikaClient::firmware_actions_upgrade`].
+    ///
+    /// The upload may take up to an hour, whatever timeout the client was built with.
+    pub async fn upload_device_firmware(
+        &self,
+        file_name: &str,
+        image: Vec<u8>,
+    ) -> Result<Response<()>, Error> {
+        let part = reqwest::multipart::Part::bytes(image).file_name(file_name.to_string());
+        let request = self
+            .reqwest
+            .post(format!("{}/firmware/actions/upload_device_firmware", self.base_url).as_str())
+            .multipart(reqwest::multipart::Form::new().part("file", part))
+            .timeout(FIRMWARE_UPLOAD_TIMEOUT);
+
+        self.send(request).await
+    }
+
+    /// Install the downloaded or uploaded firmware. The router reboots afterwards.
+    pub async fn firmware_actions_upgrade(
+        &self,
+        keep_settings: bool,
+    ) -> Result<Response<()>, Error> {
+        self.post(
+            "/firmware/actions/upgrade",
+            Some(json!({
+                "data": {
+                    "keep_settings": if keep_settings { "1" } else { "0" },
+                }
+            })),
+        )
+        .await
+    }
+
+    pub async fn gps_position_status(&self) -> Result<Response<GpsPositionStatus>, Error> {
         self.get("/gps/position/status").await
     }
 
     pub async fn wireless_devices_status(
         &self,
-    ) -> Result<Response<Vec<WirelessDeviceStatus>>, reqwest::Error> {
+    ) -> Result<Response<Vec<WirelessDeviceStatus>>, Error> {
         self.get("/wireless/devices/status").await
     }
 
     pub async fn wireless_interfaces_status(
         &self,
-    ) -> Result<Response<Vec<InterfaceStatus>>, reqwest::Error> {
+    ) -> Result<Response<Vec<InterfaceStatus>>, Error> {
         self.get("/wireless/interfaces/status").await
     }
 
+    /// Status of every network interface, including WANs such as mobile and Wi-Fi
+    /// uplinks.
+    pub async fn interfaces_status(&self) -> Result<Response<Vec<NetworkInterfaceStatus>>, Error> {
+        self.get("/interfaces/status").await
+    }
+
+    pub async fn interface_status(
+        &self,
+        id: &str,
+    ) -> Result<Response<NetworkInterfaceStatus>, Error> {
+        self.get(&format!("/interfaces/status/{}", id)).await
+    }
+
     pub async fn ip_neighbors_ipv4_status(
         &self,
-    ) -> Result<Response<Vec<IpNeighborStatusV4>>, reqwest::Error> {
+    ) -> Result<Response<Vec<IpNeighborStatusV4>>, Error> {
         self.get("/ip_neighbors/ipv4/status").await
     }
 
-    pub async fn list_sms_messages(&self) -> Result<Response<Vec<SmsMessage>>, reqwest::Error> {
+    /// Interfaces taking part in failover, with their priority and health checks.
+    pub async fn list_failover_members(&self) -> Result<Response<Vec<FailoverMember>>, Error> {
+        self.get("/failover/interfaces/config").await
+    }
+
+    pub async fn get_failover_member(&self, id: &str) -> Result<Response<FailoverMember>, Error> {
+        self.get(&format!("/failover/interfaces/config/{}", id))
+            .await
+    }
+
+    pub async fn update_failover_member(
+        &self,
+        id: &str,
+        member: &FailoverMember,
+    ) -> Result<Response<FailoverMember>, Error> {
+        self.put(
+            &format!("/failover/interfaces/config/{}", id),
+            section_replacement_body(member),
+        )
+        .await
+    }
+
+    /// Whether each failover interface is online, with its health check results.
+    pub async fn failover_status(&self) -> Result<Response<Vec<FailoverStatus>>, Error> {
+        self.get("/failover/status").await
+    }
+
+    /// Mobile data sent and received per SIM and day.
+    pub async fn data_usage(&self) -> Result<Response<Vec<DataUsage>>, Error> {
+        self.get("/data_usage/status").await
+    }
+
+    /// Data limits per SIM, with the billing period they reset on.
+    pub async fn list_data_limits(&self) -> Result<Response<Vec<DataLimit>>, Error> {
+        self.get("/data_usage/limits/config").await
+    }
+
+    pub async fn get_data_limit(&self, id: &str) -> Result<Response<DataLimit>, Error> {
+        self.get(&format!("/data_usage/limits/config/{}", id)).await
+    }
+
+    pub async fn update_data_limit(
+        &self,
+        id: &str,
+        limit: &DataLimit,
+    ) -> Result<Response<DataLimit>, Error> {
+        self.put(
+            &format!("/data_usage/limits/config/{}", id),
+            section_replacement_body(limit),
+        )
+        .await
+    }
+
+    pub async fn list_port_forwards(&self) -> Result<Response<Vec<PortForward>>, Error> {
+        self.get("/firewall/port_forwards/config").await
+    }
+
+    pub async fn get_port_forward(&self, id: &str) -> Result<Response<PortForward>, Error> {
+        self.get(&format!("/firewall/port_forwards/config/{}", id))
+            .await
+    }
+
+    /// Create a port forward, returning it with the ID the router assigned.
+    pub async fn create_port_forward(
+        &self,
+        forward: &PortForward,
+    ) -> Result<Response<PortForward>, Error> {
+        self.post(
+            "/firewall/port_forwards/config",
+            Some(section_body(forward)),
+        )
+        .await
+    }
+
+    pub async fn update_port_forward(
+        &self,
+        id: &str,
+        forward: &PortForward,
+    ) -> Result<Response<PortForward>, Error> {
+        self.put(
+            &format!("/firewall/port_forwards/config/{}", id),
+            section_replacement_body(forward),
+        )
+        .await
+    }
+
+    pub async fn delete_port_forward(&self, id: &str) -> Result<Response<SectionId>, Error> {
+        self.delete(&format!("/firewall/port_forwards/config/{}", id))
+            .await
+    }
+
+    pub async fn list_traffic_rules(&self) -> Result<Response<Vec<TrafficRule>>, Error> {
+        self.get("/firewall/traffic_rules/config").await
+    }
+
+    pub async fn get_traffic_rule(&self, id: &str) -> Result<Response<TrafficRule>, Error> {
+        self.get(&format!("/firewall/traffic_rules/config/{}", id))
+            .await
+    }
+
+    /// Create a traffic rule, returning it with the ID the router assigned.
+    pub async fn create_traffic_rule(
+        &self,
+        rule: &TrafficRule,
+    ) -> Result<Response<TrafficRule>, Error> {
+        self.post("/firewall/traffic_rules/config", Some(section_body(rule)))
+            .await
+    }
+
+    pub async fn update_traffic_rule(
+        &self,
+        id: &str,
+        rule: &TrafficRule,
+    ) -> Result<Response<TrafficRule>, Error> {
+        self.put(
+            &format!("/firewall/traffic_rules/config/{}", id),
+            section_replacement_body(rule),
+        )
+        .await
+    }
+
+    pub async fn delete_traffic_rule(&self, id: &str) -> Result<Response<SectionId>, Error> {
+        self.delete(&format!("/firewall/traffic_rules/config/{}", id))
+            .await
+    }
+
+    pub async fn list_zones(&self) -> Result<Response<Vec<Zone>>, Error> {
+        self.get("/firewall/zones/config").await
+    }
+
+    pub async fn get_zone(&self, id: &str) -> Result<Response<Zone>, Error> {
+        self.get(&format!("/firewall/zones/config/{}", id)).await
+    }
+
+    /// Create a zone, returning it with the ID the router assigned.
+    pub async fn create_zone(&self, zone: &Zone) -> Result<Response<Zone>, Error> {
+        self.post("/firewall/zones/config", Some(section_body(zone)))
+            .await
+    }
+
+    pub async fn update_zone(&self, id: &str, zone: &Zone) -> Result<Response<Zone>, Error> {
+        self.put(
+            &format!("/firewall/zones/config/{}", id),
+            section_replacement_body(zone),
+        )
+        .await
+    }
+
+    pub async fn delete_zone(&self, id: &str) -> Result<Response<SectionId>, Error> {
+        self.delete(&format!("/firewall/zones/config/{}", id)).await
+    }
+
+    /// List the modems on the device along with their status.
+    pub async fn modems_status(&self) -> Result<Response<Vec<ModemStatus>>, Error> {
+        self.get("/modems/status").await
+    }
+
+    /// ID of the primary modem, discovered from the router and cached.
+    ///
+    /// Falls back to the first modem if none is marked as primary.
+    pub async fn primary_modem(&self) -> Result<String, Error> {
+        self.primary_modem
+            .get_or_try_init(|| async {
+                let modems = self.modems_status().await?.into_result()?;
+                modems
+                    .iter()
+                    .find(|modem| modem.primary)
+                    .or_else(|| modems.first())
+                    .map(|modem| modem.id.clone())
+                    .ok_or(Error::MissingData)
+            })
+            .await
+            .cloned()
+    }
+
+    /// Every SIM slot of every modem, marking the slots in use.
+    pub async fn sim_status(&self) -> Result<Response<Vec<SimSlot>>, Error> {
+        self.get("/sim/status").await
+    }
+
+    /// The SIM slot `modem` is using.
+    pub async fn active_sim(&self, modem: &str) -> Result<u8, Error> {
+        let slots = self.sim_status().await?.into_result()?;
+        SimSlot::active(&slots, modem)
+            .map(|slot| slot.sim)
+            .ok_or(Error::MissingData)
+    }
+
+    /// Switch `modem` to the SIM in slot `sim`, returning the newly active slot.
+    ///
+    /// The mobile connection drops while the modem re-registers.
+    pub async fn switch_sim(&self, modem: &str, sim: u8) -> Result<Response<SimSlot>, Error> {
+        self.post(
+            "/sim/actions/switch",
+            Some(json!({
+                "data": {
+                    "modem": modem,
+                    "sim": sim.to_string(),
+                }
+            })),
+        )
+        .await
+    }
+
+    /// Enter the PIN of the SIM in slot `sim` of `modem` while it is waiting for
+    /// one, in the `sim pin` state, returning the unlocked slot.
+    ///
+    /// Several wrong PINs in a row lock the SIM until its PUK is entered.
+    pub async fn unlock_sim(
+        &self,
+        modem: &str,
+        sim: u8,
+        pin: &str,
+    ) -> Result<Response<SimSlot>, Error> {
+        self.post(
+            "/sim/actions/unlock",
+            Some(json!({
+                "data": {
+                    "modem": modem,
+                    "sim": sim.to_string(),
+                    "pin": pin,
+                }
+            })),
+        )
+        .await
+    }
+
+    /// Change the PIN of the SIM in slot `sim` of `modem` on the card itself.
+    ///
+    /// The PIN the router enters at startup is separate, see [`Self::set_sim_pin`].
+    pub async fn change_sim_pin(
+        &self,
+        modem: &str,
+        sim: u8,
+        current: &str,
+        new: &str,
+    ) -> Result<Response<SimSlot>, Error> {
+        self.post(
+            "/sim/actions/change_pin",
+            Some(json!({
+                "data": {
+                    "modem": modem,
+                    "sim": sim.to_string(),
+                    "pin": current,
+                    "new_pin": new,
+                }
+            })),
+        )
+        .await
+    }
+
+    /// Set the PIN the router enters when `modem` starts with the SIM in slot
+    /// `sim`, or stop entering one with `None`.
+    pub async fn set_sim_pin(
+        &self,
+        modem: &str,
+        sim: u8,
+        pin: Option<&str>,
+    ) -> Result<Response<SimCard>, Error> {
+        let cards = self.list_sim_cards().await?.into_result()?;
+        let card = cards
+            .into_iter()
+            .find(|card| card.modem == modem && card.sim == sim)
+            .ok_or(Error::MissingData)?;
+
+        let card = SimCard {
+            pin: pin.map(str::to_string),
+            ..card
+        };
+        self.update_sim_card(&card.id, &card).await
+    }
+
+    /// Rules for switching SIM automatically, e.g. when roaming.
+    pub async fn list_sim_switch_rules(&self) -> Result<Response<Vec<SimSwitchRule>>, Error> {
+        self.get("/sim/rules/config").await
+    }
+
+    pub async fn get_sim_switch_rule(&self, id: &str) -> Result<Response<SimSwitchRule>, Error> {
+        self.get(&format!("/sim/rules/config/{}", id)).await
+    }
+
+    pub async fn update_sim_switch_rule(
+        &self,
+        id: &str,
+        rule: &SimSwitchRule,
+    ) -> Result<Response<SimSwitchRule>, Error> {
+        self.put(
+            &format!("/sim/rules/config/{}", id),
+            section_replacement_body(rule),
+        )
+        .await
+    }
+
+    /// PIN and APN settings of every SIM slot.
+    pub async fn list_sim_cards(&self) -> Result<Response<Vec<SimCard>>, Error> {
+        self.get("/sim/cards/config").await
+    }
+
+    pub async fn get_sim_card(&self, id: &str) -> Result<Response<SimCard>, Error> {
+        self.get(&format!("/sim/cards/config/{}", id)).await
+    }
+
+    /// Replace the settings of a SIM slot, including its PIN and password.
+    pub async fn update_sim_card(
+        &self,
+        id: &str,
+        card: &SimCard,
+    ) -> Result<Response<SimCard>, Error> {
+        let mut body = section_replacement_body(card);
+        body["data"]["pincode"] = json!(card.pin.as_deref().unwrap_or_default());
+        body["data"]["password"] = json!(card.password.as_deref().unwrap_or_default());
+
+        self.put(&format!("/sim/cards/config/{}", id), body).await
+    }
+
+    pub async fn list_sms_messages(&self) -> Result<Response<Vec<SmsMessage>>, Error> {
         self.get("/messages/status").await
     }
 
+    /// Delete messages by ID from the given modem's storage.
+    pub async fn delete_sms_messages(
+        &self,
+        modem: &str,
+        ids: &[String],
+    ) -> Result<Response<()>, Error> {
+        self.post(
+            "/messages/actions/remove_messages",
+            Some(json!({
+                "data": {
+                    "modem_id": modem,
+                    "sms_id": ids,
+                }
+            })),
+        )
+        .await
+    }
+
+    /// Mark messages as read on the given modem.
+    pub async fn mark_sms_messages_read(
+        &self,
+        modem: &str,
+        ids: &[String],
+    ) -> Result<Response<()>, Error> {
+        self.post(
+            "/messages/actions/read_messages",
+            Some(json!({
+                "data": {
+                    "modem_id": modem,
+                    "sms_id": ids,
+                }
+            })),
+        )
+        .await
+    }
+
+    /// Send an SMS from the primary modem.
     pub async fn send_sms_message(
         &self,
         number: &str,
         message: &str,
-    ) -> Result<Response<SmsSendResponse>, reqwest::Error> {
+    ) -> Result<Response<SmsSendResponse>, Error> {
+        self.send_sms(&SmsSendRequest::new(number, message)).await
+    }
+
+    /// Send an SMS, using the primary modem if the request doesn't name one.
+    pub async fn send_sms(
+        &self,
+        request: &SmsSendRequest,
+    ) -> Result<Response<SmsSendResponse>, Error> {
+        let modem = match &request.modem {
+            Some(modem) => modem.clone(),
+            None => self.primary_modem().await?,
+        };
+
         self.post(
             "/messages/actions/send",
             Some(json!({
-                "data":{
-                    "number": number,
-                    "message": message,
-                    "modem": "2-1"
+                "data": {
+                    "number": request.number,
+                    "message": request.message,
+                    "modem": modem,
                 }
             })),
         )
@@ -169,6 +842,84 @@ impl TeltonikaClient {
     }
 }
 
+fn login_body(username: &str, password: &str) -> serde_json::Value {
+    json!({
+        "username": username,
+        "password": password,
+    })
+}
+
+/// The request body to create or update a section with: every set field but the
+/// ID, with flags as `"1"` or `"0"` as the router expects.
+fn section_body(section: &impl Serialize) -> serde_json::Value {
+    let mut data = section_fields(section);
+    if let serde_json::Value::Object(fields) = &mut data {
+        fields.retain(|_, value| !value.is_null());
+    }
+
+    json!({ "data": data })
+}
+
+/// Like [`section_body`], but unset fields are sent as empty strings so that the
+/// router clears them, for replacing a section with one that was read from it.
+fn section_replacement_body(section: &impl Serialize) -> serde_json::Value {
+    let mut data = section_fields(section);
+    if let serde_json::Value::Object(fields) = &mut data {
+        for value in fields.values_mut().filter(|value| value.is_null()) {
+            *value = json!("");
+        }
+    }
+
+    json!({ "data": data })
+}
+
+/// Every field of a section but the ID, with flags as `"1"` or `"0"`.
+fn section_fields(section: &impl Serialize) -> serde_json::Value {
+    let mut data = serde_json::to_value(section).unwrap_or(serde_json::Value::Null);
+    if let serde_json::Value::Object(fields) = &mut data {
+        fields.remove("id");
+        for value in fields.values_mut() {
+            if let serde_json::Value::Bool(flag) = value {
+                *value = json!(if *flag { "1" } else { "0" });
+            }
+        }
+    }
+
+    data
+}
+
+fn is_unauthorized<T>(response: &Response<T>) -> bool {
+    !response.success
+        && response
+            .errors
+            .iter()
+            .flatten()
+            .any(|error| error.code == UNAUTHORIZED_ACCESS_CODE)
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct SmsSendRequest {
+    pub number: String,
+    pub message: String,
+    /// Modem to send from, e.g. `2-1`. Defaults to the primary modem.
+    pub modem: Option<String>,
+}
+
+impl SmsSendRequest {
+    pub fn new(number: &str, message: &str) -> Self {
+        SmsSendRequest {
+            number: number.to_string(),
+            message: message.to_string(),
+            modem: None,
+        }
+    }
+
+    pub fn modem(mut self, modem: &str) -> Self {
+        self.modem = Some(modem.to_string());
+        self
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
 pub struct SmsSendResponse {
     pub sms_used: u64,
@@ -181,7 +932,26 @@ pub struct SmsMessage {
     pub id: String,
     pub modem_id: String,
     pub status: String,
-    pub date: String,
+    /// When the message was received. The router doesn't report a timezone, so
+    /// its local time is interpreted as UTC.
+    #[serde(deserialize_with = "de::date")]
+    pub date: DateTime<Utc>,
+}
+
+impl SmsMessage {
+    pub fn is_unread(&self) -> bool {
+        self.status == "unread"
+    }
+}
+
+impl Display for SmsMessage {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "ID: {}\nFrom: {}\nDate: {}\nStatus: {}\nModem: {}\nMessage: {}",
+            self.id, self.sender, self.date, self.status, self.modem_id, self.message
+        )
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
@@ -191,20 +961,31 @@ pub struct SessionStatus {
 
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
 pub struct IpNeighborStatusV4 {
-    pub proxy: String,
-    pub stale: String,
-    pub noarp: String,
-    pub incomplete: String,
-    pub delay: String,
+    #[serde(deserialize_with = "de::flag")]
+    pub proxy: bool,
+    #[serde(deserialize_with = "de::flag")]
+    pub stale: bool,
+    #[serde(deserialize_with = "de::flag")]
+    pub noarp: bool,
+    #[serde(deserialize_with = "de::flag")]
+    pub incomplete: bool,
+    #[serde(deserialize_with = "de::flag")]
+    pub delay: bool,
     pub family: String,
-    pub reachable: String,
-    pub mac: Option<String>,
+    #[serde(deserialize_with = "de::flag")]
+    pub reachable: bool,
+    #[serde(default, deserialize_with = "de::optional_from_str")]
+    pub mac: Option<MacAddress>,
     pub dev: String,
-    pub router: String,
-    pub dest: String,
-    pub probe: String,
-    pub failed: String,
-    pub permanent: String,
+    #[serde(deserialize_with = "de::flag")]
+    pub router: bool,
+    pub dest: IpAddr,
+    #[serde(deserialize_with = "de::flag")]
+    pub probe: bool,
+    #[serde(deserialize_with = "de::flag")]
+    pub failed: bool,
+    #[serde(deserialize_with = "de::flag")]
+    pub permanent: bool,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -224,7 +1005,7 @@ pub struct InterfaceStatus {
     // airtime
     // ...
     pub ssid: String,
-    pub assoclist: HashMap<String, InterfaceStatusAssoc>,
+    pub assoclist: HashMap<MacAddress, InterfaceStatusAssoc>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -234,10 +1015,10 @@ pub struct InterfaceStatusAssoc {
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct InterfaceStatusDevice {
-    device: String,
-    pending: bool,
-    name: String,
-    up: bool,
+    pub device: String,
+    pub pending: bool,
+    pub name: String,
+    pub up: bool,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -246,25 +1027,60 @@ pub struct WirelessDeviceStatus {
     pub quality_max: i64,
 }
 
+/// Where the router is. Without a fix the router sends empty strings, which are `None`.
 #[derive(Debug, Deserialize, Serialize)]
 pub struct GpsPositionStatus {
-    accuracy: String,
-    fix_status: String,
-    altitude: String,
-    timestamp: String,
-    satellites: String,
-    longitude: String,
-    latitude: String,
-    angle: String,
-    utc_timestamp: String,
+    #[serde(default, deserialize_with = "de::optional_from_str")]
+    pub accuracy: Option<f64>,
+    #[serde(deserialize_with = "de::from_str")]
+    pub fix_status: u32,
+    #[serde(default, deserialize_with = "de::optional_from_str")]
+    pub altitude: Option<f64>,
+    /// Unix timestamp of the fix.
+    #[serde(default, deserialize_with = "de::optional_from_str")]
+    pub timestamp: Option<i64>,
+    #[serde(default, deserialize_with = "de::optional_from_str")]
+    pub satellites: Option<u32>,
+    #[serde(default, deserialize_with = "de::optional_from_str")]
+    pub longitude: Option<f64>,
+    #[serde(default, deserialize_with = "de::optional_from_str")]
+    pub latitude: Option<f64>,
+    #[serde(default, deserialize_with = "de::optional_from_str")]
+    pub angle: Option<f64>,
+    /// Unix timestamp of the fix in UTC.
+    #[serde(default, deserialize_with = "de::optional_from_str")]
+    pub utc_timestamp: Option<i64>,
+}
+
+impl GpsPositionStatus {
+    /// Whether the router knows where it is.
+    pub fn has_fix(&self) -> bool {
+        self.latitude.is_some() && self.longitude.is_some()
+    }
 }
 
 impl Display for GpsPositionStatus {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if !self.has_fix() {
+            return write!(f, "No GPS fix (fix status {})", self.fix_status);
+        }
+
+        fn value<T: Display>(value: Option<T>) -> String {
+            value.map_or_else(|| "-".to_string(), |value| value.to_string())
+        }
+
         write!(
             f,
             "Accuracy: {}\nFix status: {}\nAltitude: {}\nTimestamp: {}\nSatellites: {}\nLongitude: {}\nLatitude: {}\nAngle: {}\nUTC timestamp: {}",
-            self.accuracy, self.fix_status, self.altitude, self.timestamp, self.satellites, self.longitude, self.latitude, self.angle, self.utc_timestamp
+            value(self.accuracy),
+            self.fix_status,
+            value(self.altitude),
+            value(self.timestamp),
+            value(self.satellites),
+            value(self.longitude),
+            value(self.latitude),
+            value(self.angle),
+            value(self.utc_timestamp),
         )
     }
 }
@@ -289,7 +1105,7 @@ impl Display for FirmwareDeviceStatus {
 #[derive(Debug, Deserialize, Serialize)]
 pub struct DhcpLease {
     pub expires: i64,
-    pub macaddr: String,
+    pub macaddr: MacAddress,
     pub ipaddr: String,
     pub hostname: Option<String>,
 }
@@ -351,6 +1167,7 @@ impl Display for DhcpLeaseV6 {
 pub struct LoginData {
     pub username: String,
     pub token: String,
+    /// Lifetime of the token in seconds.
     pub expires: i64,
 }
 
@@ -361,7 +1178,27 @@ pub struct Response<T> {
     pub errors: Option<Vec<ApiError>>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+impl<T> Response<T> {
+    /// Convert the response into its data, turning `success: false` into an [`Error`].
+    pub fn into_result(self) -> Result<T, Error> {
+        if !self.success {
+            return Err(Error::from_api_errors(self.errors.unwrap_or_default()));
+        }
+
+        self.data.ok_or(Error::MissingData)
+    }
+
+    /// Check that the response succeeded, for actions that don't return any data.
+    pub fn ensure_success(self) -> Result<(), Error> {
+        if !self.success {
+            return Err(Error::from_api_errors(self.errors.unwrap_or_default()));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
 pub struct ApiError {
     pub code: i32,
     pub error: String,
@@ -369,6 +1206,18 @@ pub struct ApiError {
     pub section: Option<String>,
 }
 
+impl Display for ApiError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{} ({}): {}", self.error, self.code, self.source)?;
+
+        if let Some(section) = &self.section {
+            write!(f, " [{}]", section)?;
+        }
+
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -381,7 +1230,7 @@ mod tests {
     }
 
     async fn create_authenticated_client() -> TeltonikaClient {
-        let mut client = create_client();
+        let client = create_client();
         let response = client
             .authenticate(
                 env::var("TELTONIKA_USERNAME")
@@ -401,11 +1250,13 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "requires a router, set TELTONIKA_HOST"]
     async fn test_login() {
         create_authenticated_client().await;
     }
 
     #[tokio::test]
+    #[ignore = "requires a router, set TELTONIKA_HOST"]
     async fn test_dhcp_leases_ipv4_status() {
         let client = create_authenticated_client().await;
         let response = client.dhcp_leases_ipv4_status().await.unwrap();
@@ -415,6 +1266,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "requires a router, set TELTONIKA_HOST"]
     async fn test_firmware_device_status() {
         let client = create_authenticated_client().await;
         let response = client.firmware_device_status().await.unwrap();
@@ -422,4 +1274,70 @@ mod tests {
         assert!(response.success);
         assert!(response.data.is_some());
     }
+
+    #[test]
+    fn test_into_result_success() {
+        let response: Response<SessionStatus> =
+            serde_json::from_str(r#"{"success":true,"data":{"active":true}}"#).unwrap();
+
+        assert_eq!(
+            response.into_result().unwrap(),
+            SessionStatus { active: true }
+        );
+    }
+
+    #[test]
+    fn test_into_result_api_error() {
+        let response: Response<SessionStatus> = serde_json::from_str(
+            r#"{"success":false,"errors":[{"code":103,"error":"Invalid options","source":"modem","section":null}]}"#,
+        )
+        .unwrap();
+
+        let err = response.into_result().unwrap_err();
+        assert!(!err.is_unauthorized());
+        assert_eq!(err.api_errors()[0].code, 103);
+    }
+
+    #[test]
+    fn test_into_result_unauthorized() {
+        let response: Response<SessionStatus> = serde_json::from_str(
+            r#"{"success":false,"errors":[{"code":118,"error":"Unauthorized access","source":"Unauthorized access","section":null}]}"#,
+        )
+        .unwrap();
+
+        assert!(response.into_result().unwrap_err().is_unauthorized());
+    }
+
+    #[test]
+    fn test_into_result_missing_data() {
+        let response: Response<SessionStatus> =
+            serde_json::from_str(r#"{"success":true}"#).unwrap();
+
+        assert!(matches!(response.into_result(), Err(Error::MissingData)));
+    }
+
+    #[test]
+    fn test_ensure_success() {
+        let response: Response<()> = serde_json::from_str(r#"{"success":true}"#).unwrap();
+        assert!(response.ensure_success().is_ok());
+
+        let response: Response<()> = serde_json::from_str(
+            r#"{"success":false,"errors":[{"code":103,"error":"Invalid options","source":"sms_id","section":null}]}"#,
+        )
+        .unwrap();
+        assert!(matches!(response.ensure_success(), Err(Error::Api(_))));
+    }
+
+    #[test]
+    fn test_session_needs_renewal() {
+        let login = |expires| LoginData {
+            username: "admin".to_string(),
+            token: "token".to_string(),
+            expires,
+        };
+
+        assert!(!Session::new(login(300)).needs_renewal());
+        assert!(Session::new(login(10)).needs_renewal());
+        assert!(Session::new(login(0)).needs_renewal());
+    }
 }
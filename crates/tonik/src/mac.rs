@@ -0,0 +1,203 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// A hardware address, e.g. `82:87:EE:2A:86:AE`.
+///
+/// Parses colon or hyphen separated (`82-87-ee-2a-86-ae`), Cisco dotted
+/// (`8287.ee2a.86ae`) and bare (`8287EE2A86AE`) forms. Addresses compare by
+/// value, so differences in case or format don't matter. They are displayed and
+/// serialized in upper case with colon separators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MacAddress([u8; 6]);
+
+impl MacAddress {
+    pub const fn new(octets: [u8; 6]) -> Self {
+        MacAddress(octets)
+    }
+
+    pub const fn octets(&self) -> [u8; 6] {
+        self.0
+    }
+
+    /// The organizationally unique identifier, i.e. the first three octets.
+    pub const fn oui(&self) -> [u8; 3] {
+        [self.0[0], self.0[1], self.0[2]]
+    }
+
+    /// Whether the address is locally administered rather than assigned by the
+    /// manufacturer. Phones use such addresses for Wi-Fi MAC randomization.
+    pub const fn is_locally_administered(&self) -> bool {
+        self.0[0] & 0x02 != 0
+    }
+
+    pub const fn is_multicast(&self) -> bool {
+        self.0[0] & 0x01 != 0
+    }
+
+    /// Name of the manufacturer, looked up in an embedded table of common vendors.
+    ///
+    /// Locally administered addresses have no manufacturer and return `None`.
+    #[cfg(feature = "oui")]
+    pub fn vendor(&self) -> Option<&'static str> {
+        if self.is_locally_administered() {
+            return None;
+        }
+
+        crate::oui::lookup(self.oui())
+    }
+}
+
+/// Error returned when a string is not a valid MAC address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseMacAddressError(String);
+
+impl Display for ParseMacAddressError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "invalid MAC address: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseMacAddressError {}
+
+impl FromStr for MacAddress {
+    type Err = ParseMacAddressError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseMacAddressError(s.to_string());
+        let trimmed = s.trim();
+
+        let mut octets = [0; 6];
+
+        if trimmed.contains([':', '-']) {
+            let parts: Vec<&str> = trimmed.split([':', '-']).collect();
+            if parts.len() != 6 {
+                return Err(error());
+            }
+            for (octet, part) in octets.iter_mut().zip(parts) {
+                if part.is_empty() || part.len() > 2 {
+                    return Err(error());
+                }
+                *octet = u8::from_str_radix(part, 16).map_err(|_| error())?;
+            }
+            return Ok(MacAddress(octets));
+        }
+
+        let digits: String = if trimmed.contains('.') {
+            let groups: Vec<&str> = trimmed.split('.').collect();
+            if groups.len() != 3 || groups.iter().any(|group| group.len() != 4) {
+                return Err(error());
+            }
+            groups.concat()
+        } else {
+            trimmed.to_string()
+        };
+
+        if digits.len() != 12 || !digits.is_ascii() {
+            return Err(error());
+        }
+        for (i, octet) in octets.iter_mut().enumerate() {
+            *octet = u8::from_str_radix(&digits[i * 2..i * 2 + 2], 16).map_err(|_| error())?;
+        }
+
+        Ok(MacAddress(octets))
+    }
+}
+
+impl Display for MacAddress {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+        write!(
+            f,
+            "{:02X}:{:02X}:{:02X}:{:02X}:{:02X}:{:02X}",
+            a, b, c, d, e, g
+        )
+    }
+}
+
+impl Serialize for MacAddress {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for MacAddress {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_is_case_insensitive() {
+        let upper: MacAddress = "82:87:EE:2A:86:AE".parse().unwrap();
+        let lower: MacAddress = "82:87:ee:2a:86:ae".parse().unwrap();
+
+        assert_eq!(upper, lower);
+        assert_eq!(lower.to_string(), "82:87:EE:2A:86:AE");
+    }
+
+    #[test]
+    fn test_parse_formats() {
+        let expected = MacAddress::new([0x82, 0x87, 0xEE, 0x2A, 0x86, 0xAE]);
+
+        for input in [
+            "82:87:EE:2A:86:AE",
+            "82-87-ee-2a-86-ae",
+            "8287.ee2a.86ae",
+            "8287EE2A86AE",
+            " 82:87:ee:2a:86:ae ",
+        ] {
+            assert_eq!(input.parse::<MacAddress>().unwrap(), expected, "{}", input);
+        }
+
+        assert_eq!(
+            "0:1b:21:a:b:c".parse::<MacAddress>().unwrap().to_string(),
+            "00:1B:21:0A:0B:0C"
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        for input in [
+            "",
+            "82:87:EE:2A:86",
+            "82:87:EE:2A:86:GG",
+            "82:87:EE:2A:86:AE:00",
+            "82:87:EE:2A::AE",
+            "8287.ee2a.86a",
+            "8287EE2A86A",
+            "828\u{e9}EE2A86AE",
+        ] {
+            assert!(input.parse::<MacAddress>().is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_locally_administered() {
+        let randomized: MacAddress = "82:87:EE:2A:86:AE".parse().unwrap();
+        let assigned: MacAddress = "B8:27:EB:FB:2A:45".parse().unwrap();
+
+        assert!(randomized.is_locally_administered());
+        assert!(!assigned.is_locally_administered());
+    }
+
+    #[cfg(feature = "oui")]
+    #[test]
+    fn test_vendor() {
+        let pi: MacAddress = "b8:27:eb:fb:2a:45".parse().unwrap();
+        let randomized: MacAddress = "82:87:EE:2A:86:AE".parse().unwrap();
+        let unknown: MacAddress = "00:00:00:00:00:01".parse().unwrap();
+
+        assert_eq!(pi.vendor(), Some("Raspberry Pi Foundation"));
+        assert_eq!(randomized.vendor(), None);
+        assert_eq!(unknown.vendor(), None);
+    }
+}
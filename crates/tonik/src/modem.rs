@@ -0,0 +1,96 @@
+use std::fmt::{self, Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+use crate::de;
+
+/// Status of a mobile modem, as reported by `/modems/status`.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct ModemStatus {
+    pub id: String,
+    pub name: Option<String>,
+    pub model: Option<String>,
+    pub imei: Option<String>,
+    pub iccid: Option<String>,
+    pub operator: Option<String>,
+    /// Network type, e.g. `LTE` or `5G-NSA`.
+    #[serde(rename = "conntype")]
+    pub network_type: Option<String>,
+    /// Received signal strength indicator in dBm.
+    #[serde(default, deserialize_with = "de::optional_number")]
+    pub rssi: Option<f64>,
+    /// Reference signal received power in dBm.
+    #[serde(default, deserialize_with = "de::optional_number")]
+    pub rsrp: Option<f64>,
+    /// Reference signal received quality in dB.
+    #[serde(default, deserialize_with = "de::optional_number")]
+    pub rsrq: Option<f64>,
+    /// Signal to interference plus noise ratio in dB.
+    #[serde(default, deserialize_with = "de::optional_number")]
+    pub sinr: Option<f64>,
+    #[serde(rename = "cellid")]
+    pub cell_id: Option<String>,
+    pub band: Option<String>,
+    #[serde(rename = "simstate")]
+    pub sim_state: Option<String>,
+    /// Whether this is the modem used by default, e.g. for sending SMS.
+    #[serde(default)]
+    pub primary: bool,
+    #[serde(default)]
+    pub builtin: bool,
+}
+
+impl Display for ModemStatus {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let text = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+        let metric = |value: Option<f64>, unit: &str| match value {
+            Some(value) => format!("{} {}", value, unit),
+            None => "-".to_string(),
+        };
+
+        let rows = [
+            ("Modem", self.id.clone()),
+            ("Name", text(&self.name)),
+            ("Model", text(&self.model)),
+            ("Operator", text(&self.operator)),
+            ("Network type", text(&self.network_type)),
+            ("RSSI", metric(self.rssi, "dBm")),
+            ("RSRP", metric(self.rsrp, "dBm")),
+            ("RSRQ", metric(self.rsrq, "dB")),
+            ("SINR", metric(self.sinr, "dB")),
+            ("Cell ID", text(&self.cell_id)),
+            ("Band", text(&self.band)),
+            ("IMEI", text(&self.imei)),
+            ("ICCID", text(&self.iccid)),
+            ("SIM state", text(&self.sim_state)),
+        ];
+
+        for (i, (label, value)) in rows.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{:<14}{}", format!("{}:", label), value)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_string_metrics() {
+        let status: ModemStatus = serde_json::from_str(
+            r#"{"id":"2-1","conntype":"LTE","rssi":"-67","rsrp":-95,"rsrq":"N/A","sinr":""}"#,
+        )
+        .unwrap();
+
+        assert_eq!(status.network_type.as_deref(), Some("LTE"));
+        assert_eq!(status.rssi, Some(-67.0));
+        assert_eq!(status.rsrp, Some(-95.0));
+        assert_eq!(status.rsrq, None);
+        assert_eq!(status.sinr, None);
+    }
+}
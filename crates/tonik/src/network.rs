@@ -0,0 +1,260 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    net::IpAddr,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::de;
+
+/// Status of a network interface, e.g. `lan`, `wan` or a mobile `mob1s1a1`, as
+/// reported by `/interfaces/status`.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(from = "RawInterfaceStatus")]
+pub struct NetworkInterfaceStatus {
+    pub id: String,
+    /// How the interface is configured, e.g. `static`, `dhcp` or `wwan`.
+    pub proto: String,
+    pub up: bool,
+    /// Seconds since the interface came up.
+    pub uptime: Option<u64>,
+    /// Linux network device carrying the interface's traffic, e.g. `eth1`.
+    pub device: Option<String>,
+    /// Route metric. The up interface with a default route and the lowest metric
+    /// carries traffic.
+    pub metric: Option<u32>,
+    /// Addresses with their prefix length, e.g. `192.168.7.1/24`.
+    pub ipv4_addresses: Vec<String>,
+    pub ipv6_addresses: Vec<String>,
+    /// Next hop of the interface's default route.
+    pub gateway: Option<IpAddr>,
+    pub dns_servers: Vec<IpAddr>,
+    pub rx_bytes: Option<u64>,
+    pub tx_bytes: Option<u64>,
+}
+
+impl NetworkInterfaceStatus {
+    /// Whether the interface has a default route, i.e. is a WAN.
+    pub fn is_wan(&self) -> bool {
+        self.gateway.is_some()
+    }
+
+    /// The WAN currently carrying traffic: the up interface with a default route
+    /// and the lowest metric.
+    pub fn active_wan(interfaces: &[Self]) -> Option<&Self> {
+        interfaces
+            .iter()
+            .filter(|interface| interface.up && interface.is_wan())
+            .min_by_key(|interface| interface.metric.unwrap_or_default())
+    }
+}
+
+impl Display for NetworkInterfaceStatus {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{} ({}", self.id, self.proto)?;
+        if let Some(device) = &self.device {
+            write!(f, " on {}", device)?;
+        }
+        write!(f, "): ")?;
+
+        if !self.up {
+            return write!(f, "down");
+        }
+        write!(f, "up")?;
+        if let Some(uptime) = self.uptime {
+            write!(f, " {}", duration(uptime))?;
+        }
+        for address in self.ipv4_addresses.iter().chain(&self.ipv6_addresses) {
+            write!(f, ", {}", address)?;
+        }
+        if let Some(gateway) = self.gateway {
+            write!(f, " via {}", gateway)?;
+        }
+        if let Some(metric) = self.metric {
+            write!(f, ", metric {}", metric)?;
+        }
+        if let (Some(rx), Some(tx)) = (self.rx_bytes, self.tx_bytes) {
+            write!(f, ", rx {}, tx {}", bytes(rx), bytes(tx))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A duration in seconds as e.g. `2d 3h`, `3h 12m` or `45s`.
+fn duration(seconds: u64) -> String {
+    let (days, hours, minutes) = (seconds / 86400, seconds / 3600 % 24, seconds / 60 % 60);
+    match (days, hours, minutes) {
+        (0, 0, 0) => format!("{}s", seconds),
+        (0, 0, minutes) => format!("{}m", minutes),
+        (0, hours, minutes) => format!("{}h {}m", hours, minutes),
+        (days, hours, _) => format!("{}d {}h", days, hours),
+    }
+}
+
+/// A byte count as e.g. `512 B`, `3.2 MB` or `1.4 GB`.
+pub(crate) fn bytes(bytes: u64) -> String {
+    const UNITS: &[&str] = &["kB", "MB", "GB", "TB"];
+
+    let mut value = bytes as f64;
+    let mut unit = "B";
+    for next in UNITS {
+        if value < 1000.0 {
+            break;
+        }
+        value /= 1000.0;
+        unit = next;
+    }
+
+    if unit == "B" {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, unit)
+    }
+}
+
+/// The interface as the router sends it, following `ubus call network.interface dump`.
+#[derive(Deserialize)]
+struct RawInterfaceStatus {
+    #[serde(alias = "interface")]
+    id: String,
+    #[serde(default)]
+    proto: String,
+    #[serde(default, deserialize_with = "de::flag")]
+    up: bool,
+    #[serde(default, deserialize_with = "de::optional_from_str")]
+    uptime: Option<u64>,
+    #[serde(default)]
+    l3_device: Option<String>,
+    #[serde(default, deserialize_with = "de::optional_from_str")]
+    metric: Option<u32>,
+    #[serde(default, rename = "ipv4-address")]
+    ipv4_address: Vec<RawAddress>,
+    #[serde(default, rename = "ipv6-address")]
+    ipv6_address: Vec<RawAddress>,
+    #[serde(default)]
+    route: Vec<RawRoute>,
+    #[serde(default, rename = "dns-server")]
+    dns_server: Vec<IpAddr>,
+    #[serde(default)]
+    statistics: Option<RawStatistics>,
+}
+
+#[derive(Deserialize)]
+struct RawAddress {
+    address: IpAddr,
+    mask: u8,
+}
+
+#[derive(Deserialize)]
+struct RawRoute {
+    target: IpAddr,
+    mask: u8,
+    nexthop: IpAddr,
+}
+
+#[derive(Deserialize)]
+struct RawStatistics {
+    #[serde(default, deserialize_with = "de::optional_from_str")]
+    rx_bytes: Option<u64>,
+    #[serde(default, deserialize_with = "de::optional_from_str")]
+    tx_bytes: Option<u64>,
+}
+
+impl From<RawInterfaceStatus> for NetworkInterfaceStatus {
+    fn from(raw: RawInterfaceStatus) -> Self {
+        let addresses = |addresses: Vec<RawAddress>| {
+            addresses
+                .into_iter()
+                .map(|address| format!("{}/{}", address.address, address.mask))
+                .collect()
+        };
+        let gateway = raw
+            .route
+            .iter()
+            .filter(|route| route.mask == 0 && route.target.is_unspecified())
+            .min_by_key(|route| route.target.is_ipv6())
+            .map(|route| route.nexthop);
+
+        NetworkInterfaceStatus {
+            id: raw.id,
+            proto: raw.proto,
+            up: raw.up,
+            uptime: raw.uptime,
+            device: raw.l3_device,
+            metric: raw.metric,
+            ipv4_addresses: addresses(raw.ipv4_address),
+            ipv6_addresses: addresses(raw.ipv6_address),
+            gateway,
+            dns_servers: raw.dns_server,
+            rx_bytes: raw.statistics.as_ref().and_then(|stats| stats.rx_bytes),
+            tx_bytes: raw.statistics.as_ref().and_then(|stats| stats.tx_bytes),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn interface(id: &str, up: bool, metric: u32, gateway: Option<&str>) -> NetworkInterfaceStatus {
+        let route: Vec<_> = gateway
+            .map(|gateway| json!({ "target": "0.0.0.0", "mask": 0, "nexthop": gateway }))
+            .into_iter()
+            .collect();
+
+        serde_json::from_value(json!({
+            "interface": id,
+            "up": up,
+            "proto": "dhcp",
+            "metric": metric,
+            "route": route,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let interface: NetworkInterfaceStatus = serde_json::from_value(json!({
+            "interface": "wan",
+            "up": true,
+            "uptime": 11520,
+            "l3_device": "eth1",
+            "proto": "dhcp",
+            "metric": 1,
+            "ipv4-address": [{ "address": "100.64.0.2", "mask": 10 }],
+            "ipv6-address": [],
+            "route": [
+                { "target": "100.64.0.0", "mask": 10, "nexthop": "0.0.0.0" },
+                { "target": "0.0.0.0", "mask": 0, "nexthop": "100.64.0.1" },
+            ],
+            "dns-server": ["1.1.1.1"],
+            "statistics": { "rx_bytes": 1234567890, "tx_bytes": 310500000 },
+        }))
+        .unwrap();
+
+        assert_eq!(interface.gateway, Some("100.64.0.1".parse().unwrap()));
+        assert_eq!(interface.ipv4_addresses, ["100.64.0.2/10"]);
+        assert_eq!(
+            interface.to_string(),
+            "wan (dhcp on eth1): up 3h 12m, 100.64.0.2/10 via 100.64.0.1, metric 1, \
+             rx 1.2 GB, tx 310.5 MB"
+        );
+    }
+
+    #[test]
+    fn test_active_wan() {
+        let interfaces = [
+            interface("lan", true, 0, None),
+            interface("wan", false, 1, Some("100.64.0.1")),
+            interface("wwan", true, 2, Some("10.20.0.1")),
+            interface("mob1s1a1", true, 3, Some("10.160.22.5")),
+        ];
+
+        let active = NetworkInterfaceStatus::active_wan(&interfaces).unwrap();
+        assert_eq!(active.id, "wwan");
+        assert!(NetworkInterfaceStatus::active_wan(&interfaces[..2]).is_none());
+    }
+}
@@ -0,0 +1,30 @@
+//! Embedded table of common hardware vendors, keyed by OUI.
+
+use std::sync::OnceLock;
+
+/// Tab separated `OUI<TAB>vendor` lines, sorted by OUI.
+const TABLE: &str = include_str!("oui.tsv");
+
+fn entries() -> &'static [([u8; 3], &'static str)] {
+    static ENTRIES: OnceLock<Vec<([u8; 3], &'static str)>> = OnceLock::new();
+
+    ENTRIES.get_or_init(|| {
+        TABLE
+            .lines()
+            .filter_map(|line| {
+                let (oui, vendor) = line.split_once('\t')?;
+                let oui = u32::from_str_radix(oui, 16).ok()?;
+                let [_, a, b, c] = oui.to_be_bytes();
+                Some(([a, b, c], vendor))
+            })
+            .collect()
+    })
+}
+
+pub(crate) fn lookup(oui: [u8; 3]) -> Option<&'static str> {
+    let entries = entries();
+    entries
+        .binary_search_by_key(&oui, |(key, _)| *key)
+        .ok()
+        .map(|index| entries[index].1)
+}
@@ -0,0 +1,431 @@
+//! Deciding which devices are on the network.
+//!
+//! A MAC address dropping out of the neighbour table isn't enough to say a device
+//! has left: phones put their Wi-Fi to sleep and reappear minutes later. The
+//! [`PresenceTracker`] combines the neighbour table with wireless association lists
+//! and DHCP leases, and only reports a departure once a device has gone unseen for a
+//! grace period. [`PresenceWatcher`] polls the router and yields the resulting
+//! [`PresenceEvent`]s as a stream.
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use futures::{stream, Stream};
+
+use crate::{DhcpLease, Error, IpNeighborStatusV4, MacAddress, Response, TeltonikaClient};
+
+#[cfg(feature = "history")]
+pub mod history;
+
+/// How strongly a poll suggests that a device is still around.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Evidence {
+    /// Failed or incomplete neighbour entry, or not listed at all.
+    Absent,
+    /// Stale neighbour entry or a DHCP lease: the device was here recently.
+    Lingering,
+    /// Reachable, delayed or probing neighbour entry, or associated to an access point.
+    Present,
+}
+
+impl Evidence {
+    fn of_neighbor(neighbor: &IpNeighborStatusV4) -> Self {
+        if neighbor.reachable || neighbor.delay || neighbor.probe || neighbor.permanent {
+            Evidence::Present
+        } else if neighbor.stale {
+            Evidence::Lingering
+        } else {
+            Evidence::Absent
+        }
+    }
+}
+
+/// One poll's worth of signals about which devices are connected.
+#[derive(Debug, Default)]
+pub struct Observations {
+    pub neighbors: Vec<IpNeighborStatusV4>,
+    /// MAC addresses associated to any wireless interface.
+    pub associated: HashSet<MacAddress>,
+    pub leases: Vec<DhcpLease>,
+}
+
+impl Observations {
+    /// Fetch the neighbour table, wireless association lists and DHCP leases.
+    ///
+    /// Only the neighbour table is required. Routers without Wi-Fi or a DHCP server
+    /// fail the other requests, in which case those signals are left empty.
+    pub async fn fetch(client: &TeltonikaClient) -> Result<Self, Error> {
+        let (neighbors, interfaces, leases) = futures::join!(
+            client.ip_neighbors_ipv4_status(),
+            client.wireless_interfaces_status(),
+            client.dhcp_leases_ipv4_status(),
+        );
+
+        let neighbors = neighbors.and_then(Response::into_result)?;
+        let associated = interfaces
+            .and_then(Response::into_result)
+            .map(|interfaces| {
+                interfaces
+                    .into_iter()
+                    .flat_map(|interface| interface.assoclist.into_keys())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let leases = leases.and_then(Response::into_result).unwrap_or_default();
+
+        Ok(Observations {
+            neighbors,
+            associated,
+            leases,
+        })
+    }
+
+    /// The strongest evidence for each MAC address mentioned by any signal.
+    pub fn evidence(&self) -> HashMap<MacAddress, Evidence> {
+        let mut evidence = HashMap::new();
+        let mut record = |mac: MacAddress, found: Evidence| {
+            let entry = evidence.entry(mac).or_insert(found);
+            *entry = (*entry).max(found);
+        };
+
+        for neighbor in &self.neighbors {
+            if let Some(mac) = neighbor.mac {
+                record(mac, Evidence::of_neighbor(neighbor));
+            }
+        }
+        for mac in &self.associated {
+            record(*mac, Evidence::Present);
+        }
+        for lease in self.leases.iter().filter(|lease| lease.expires > 0) {
+            record(lease.macaddr, Evidence::Lingering);
+        }
+
+        evidence
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PresenceEvent {
+    Arrived {
+        mac: MacAddress,
+        first_seen: DateTime<Utc>,
+    },
+    Departed {
+        mac: MacAddress,
+        first_seen: DateTime<Utc>,
+        last_seen: DateTime<Utc>,
+    },
+}
+
+impl PresenceEvent {
+    pub fn mac(&self) -> MacAddress {
+        match self {
+            PresenceEvent::Arrived { mac, .. } | PresenceEvent::Departed { mac, .. } => *mac,
+        }
+    }
+}
+
+/// When a present device arrived and when it was last seen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sighting {
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// Turns successive [`Observations`] into arrivals and departures.
+///
+/// A device arrives as soon as there is [`Evidence::Present`] for it. It departs
+/// once it hasn't been present for the departure grace period, or the longer
+/// lingering grace period while a stale neighbour entry or DHCP lease remains.
+#[derive(Debug)]
+pub struct PresenceTracker {
+    departure_grace: Duration,
+    lingering_grace: Duration,
+    sightings: HashMap<MacAddress, Sighting>,
+}
+
+impl Default for PresenceTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PresenceTracker {
+    pub fn new() -> Self {
+        PresenceTracker {
+            departure_grace: Duration::from_secs(3 * 60),
+            lingering_grace: Duration::from_secs(10 * 60),
+            sightings: HashMap::new(),
+        }
+    }
+
+    /// How long a device may go unseen before it departs. Defaults to three minutes.
+    pub fn departure_grace(mut self, grace: Duration) -> Self {
+        self.departure_grace = grace;
+        self
+    }
+
+    /// How long a device with only lingering evidence is kept. Defaults to ten minutes.
+    ///
+    /// This is never shorter than the departure grace period.
+    pub fn lingering_grace(mut self, grace: Duration) -> Self {
+        self.lingering_grace = grace;
+        self
+    }
+
+    /// Devices that are currently considered present.
+    pub fn present(&self) -> impl Iterator<Item = (&MacAddress, &Sighting)> {
+        self.sightings.iter()
+    }
+
+    pub fn sighting(&self, mac: &MacAddress) -> Option<&Sighting> {
+        self.sightings.get(mac)
+    }
+
+    /// Treat a device as present without reporting an arrival, e.g. after a restart.
+    pub fn restore(&mut self, mac: MacAddress, sighting: Sighting) {
+        self.sightings.insert(mac, sighting);
+    }
+
+    pub fn update(&mut self, observations: &Observations) -> Vec<PresenceEvent> {
+        self.update_at(Utc::now(), observations)
+    }
+
+    /// Like [`PresenceTracker::update`], with an explicit time for the observations.
+    pub fn update_at(
+        &mut self,
+        now: DateTime<Utc>,
+        observations: &Observations,
+    ) -> Vec<PresenceEvent> {
+        let evidence = observations.evidence();
+
+        let mut arrived = Vec::new();
+        for (mac, found) in &evidence {
+            if *found != Evidence::Present {
+                continue;
+            }
+
+            let sighting = self.sightings.entry(*mac).or_insert_with(|| {
+                arrived.push(PresenceEvent::Arrived {
+                    mac: *mac,
+                    first_seen: now,
+                });
+                Sighting {
+                    first_seen: now,
+                    last_seen: now,
+                }
+            });
+            sighting.last_seen = now;
+        }
+
+        let mut departed = Vec::new();
+        self.sightings.retain(|mac, sighting| {
+            let grace = match evidence.get(mac) {
+                Some(Evidence::Present) => return true,
+                Some(Evidence::Lingering) => self.lingering_grace.max(self.departure_grace),
+                _ => self.departure_grace,
+            };
+
+            let unseen = (now - sighting.last_seen).to_std().unwrap_or_default();
+            if unseen < grace {
+                return true;
+            }
+
+            departed.push(PresenceEvent::Departed {
+                mac: *mac,
+                first_seen: sighting.first_seen,
+                last_seen: sighting.last_seen,
+            });
+            false
+        });
+
+        arrived.sort_by_key(PresenceEvent::mac);
+        departed.sort_by_key(PresenceEvent::mac);
+        arrived.extend(departed);
+        arrived
+    }
+}
+
+/// Polls the router and yields each arrival and departure.
+pub struct PresenceWatcher {
+    client: Arc<TeltonikaClient>,
+    interval: Duration,
+    include_existing: bool,
+    tracker: PresenceTracker,
+    started: bool,
+}
+
+impl PresenceWatcher {
+    pub fn new(client: Arc<TeltonikaClient>) -> Self {
+        PresenceWatcher {
+            client,
+            interval: Duration::from_secs(5),
+            include_existing: false,
+            tracker: PresenceTracker::new(),
+            started: false,
+        }
+    }
+
+    /// How often the router is polled. Defaults to five seconds.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Report devices that were already present when the watcher started as arrivals.
+    pub fn include_existing(mut self, include: bool) -> Self {
+        self.include_existing = include;
+        self
+    }
+
+    /// Use a tracker with custom grace periods.
+    pub fn tracker(mut self, tracker: PresenceTracker) -> Self {
+        self.tracker = tracker;
+        self
+    }
+
+    /// Poll the router once, returning the arrivals and departures since the last poll.
+    pub async fn poll(&mut self) -> Result<Vec<PresenceEvent>, Error> {
+        let observations = Observations::fetch(&self.client).await?;
+        let events = self.tracker.update(&observations);
+
+        if self.started || self.include_existing {
+            self.started = true;
+            Ok(events)
+        } else {
+            self.started = true;
+            Ok(Vec::new())
+        }
+    }
+
+    /// Turn the watcher into a stream of arrivals and departures.
+    ///
+    /// A failed poll yields its error and is retried after the polling
+    /// interval; the stream itself never ends.
+    pub fn into_stream(self) -> impl Stream<Item = Result<PresenceEvent, Error>> {
+        let state = (self, VecDeque::new(), true);
+
+        stream::unfold(state, |(mut watcher, mut pending, mut first)| async move {
+            loop {
+                if let Some(event) = pending.pop_front() {
+                    return Some((Ok(event), (watcher, pending, first)));
+                }
+
+                if !first {
+                    tokio::time::sleep(watcher.interval).await;
+                }
+                first = false;
+
+                match watcher.poll().await {
+                    Ok(events) => pending.extend(events),
+                    Err(err) => return Some((Err(err), (watcher, pending, first))),
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    const PHONE: &str = "82:87:EE:2A:86:AE";
+
+    fn neighbor(mac: &str, state: &str) -> IpNeighborStatusV4 {
+        let mut neighbor = json!({
+            "proxy": "0", "stale": "0", "noarp": "0", "incomplete": "0", "delay": "0",
+            "family": "ipv4", "reachable": "0", "mac": mac, "dev": "br-lan", "router": "0",
+            "dest": "192.168.7.120", "probe": "0", "failed": "0", "permanent": "0",
+        });
+        neighbor[state] = json!("1");
+        serde_json::from_value(neighbor).unwrap()
+    }
+
+    fn observations(neighbors: Vec<IpNeighborStatusV4>) -> Observations {
+        Observations {
+            neighbors,
+            ..Default::default()
+        }
+    }
+
+    fn arrived(mac: MacAddress, first_seen: DateTime<Utc>) -> PresenceEvent {
+        PresenceEvent::Arrived { mac, first_seen }
+    }
+
+    #[test]
+    fn test_departure_after_grace_period() {
+        let mac: MacAddress = PHONE.parse().unwrap();
+        let start = Utc::now();
+        let mut tracker = PresenceTracker::new().departure_grace(Duration::from_secs(60));
+
+        let events = tracker.update_at(start, &observations(vec![neighbor(PHONE, "reachable")]));
+        assert_eq!(events, vec![arrived(mac, start)]);
+
+        // Gone from the neighbour table, but within the grace period.
+        let events = tracker.update_at(start + Duration::from_secs(30), &observations(vec![]));
+        assert!(events.is_empty());
+
+        // Back again, which restarts the grace period.
+        let events = tracker.update_at(
+            start + Duration::from_secs(45),
+            &observations(vec![neighbor(PHONE, "delay")]),
+        );
+        assert!(events.is_empty());
+
+        let events = tracker.update_at(
+            start + Duration::from_secs(100),
+            &observations(vec![neighbor(PHONE, "failed")]),
+        );
+        assert!(events.is_empty());
+
+        let events = tracker.update_at(start + Duration::from_secs(105), &observations(vec![]));
+        assert_eq!(
+            events,
+            vec![PresenceEvent::Departed {
+                mac,
+                first_seen: start,
+                last_seen: start + Duration::from_secs(45),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_lingering_evidence_extends_grace_period() {
+        let mac: MacAddress = PHONE.parse().unwrap();
+        let start = Utc::now();
+        let mut tracker = PresenceTracker::new()
+            .departure_grace(Duration::from_secs(60))
+            .lingering_grace(Duration::from_secs(600));
+
+        let associated = Observations {
+            associated: HashSet::from([mac]),
+            ..Default::default()
+        };
+        let events = tracker.update_at(start, &associated);
+        assert_eq!(events, vec![arrived(mac, start)]);
+
+        let stale = observations(vec![neighbor(PHONE, "stale")]);
+        assert!(tracker
+            .update_at(start + Duration::from_secs(300), &stale)
+            .is_empty());
+        assert_eq!(
+            tracker
+                .update_at(start + Duration::from_secs(600), &stale)
+                .iter()
+                .map(PresenceEvent::mac)
+                .collect::<Vec<_>>(),
+            vec![mac]
+        );
+
+        // Lingering evidence alone isn't an arrival.
+        assert!(tracker
+            .update_at(start + Duration::from_secs(660), &stale)
+            .is_empty());
+    }
+}
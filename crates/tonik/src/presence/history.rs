@@ -0,0 +1,271 @@
+//! Recording arrivals and departures in an SQLite database.
+
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use super::{PresenceEvent, PresenceTracker, Sighting};
+use crate::MacAddress;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS presence_events (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        mac TEXT NOT NULL,
+        event TEXT NOT NULL CHECK (event IN ('arrived', 'departed')),
+        first_seen INTEGER NOT NULL,
+        last_seen INTEGER
+    );
+    CREATE INDEX IF NOT EXISTS presence_events_mac ON presence_events (mac, id);
+";
+
+/// Every [`PresenceEvent`], kept across restarts.
+///
+/// Timestamps are stored as milliseconds since the Unix epoch.
+pub struct PresenceHistory {
+    connection: Connection,
+}
+
+impl PresenceHistory {
+    /// Open or create the database at `path`.
+    pub fn open(path: impl AsRef<Path>) -> rusqlite::Result<Self> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> rusqlite::Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(connection: Connection) -> rusqlite::Result<Self> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(PresenceHistory { connection })
+    }
+
+    pub fn record(&self, event: &PresenceEvent) -> rusqlite::Result<()> {
+        let (kind, first_seen, last_seen) = match *event {
+            PresenceEvent::Arrived { first_seen, .. } => ("arrived", first_seen, None),
+            PresenceEvent::Departed {
+                first_seen,
+                last_seen,
+                ..
+            } => ("departed", first_seen, Some(last_seen)),
+        };
+
+        self.connection.execute(
+            "INSERT INTO presence_events (mac, event, first_seen, last_seen)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                event.mac().to_string(),
+                kind,
+                first_seen.timestamp_millis(),
+                last_seen.map(|last_seen| last_seen.timestamp_millis()),
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Events in the order they happened, optionally limited to some devices and to
+    /// events since a point in time. An empty `macs` matches every device.
+    pub fn events(
+        &self,
+        macs: &[MacAddress],
+        since: Option<DateTime<Utc>>,
+    ) -> rusqlite::Result<Vec<PresenceEvent>> {
+        let since = since.map_or(i64::MIN, |since| since.timestamp_millis());
+        let mut statement = self.connection.prepare(
+            "SELECT mac, event, first_seen, last_seen FROM presence_events
+             WHERE COALESCE(last_seen, first_seen) >= ?1
+             ORDER BY id",
+        )?;
+
+        let events = statement
+            .query_map(params![since], event)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(events
+            .into_iter()
+            .filter(|event| macs.is_empty() || macs.contains(&event.mac()))
+            .collect())
+    }
+
+    /// Arrivals of devices that haven't departed since.
+    pub fn present(&self) -> rusqlite::Result<Vec<PresenceEvent>> {
+        let mut statement = self.connection.prepare(
+            "SELECT mac, event, first_seen, last_seen FROM presence_events AS latest
+             WHERE id = (SELECT MAX(id) FROM presence_events WHERE mac = latest.mac)
+               AND event = 'arrived'
+             ORDER BY first_seen",
+        )?;
+
+        let events = statement.query_map([], event)?.collect();
+        events
+    }
+
+    /// The most recent departure of any device.
+    pub fn last_departure(&self) -> rusqlite::Result<Option<PresenceEvent>> {
+        self.connection
+            .query_row(
+                "SELECT mac, event, first_seen, last_seen FROM presence_events
+                 WHERE event = 'departed'
+                 ORDER BY last_seen DESC LIMIT 1",
+                [],
+                event,
+            )
+            .optional()
+    }
+
+    /// When a device was last known to be around: when it departed, or when it
+    /// arrived if it is still present.
+    pub fn last_seen(&self, mac: &MacAddress) -> rusqlite::Result<Option<DateTime<Utc>>> {
+        let event = self
+            .connection
+            .query_row(
+                "SELECT mac, event, first_seen, last_seen FROM presence_events
+                 WHERE mac = ?1
+                 ORDER BY id DESC LIMIT 1",
+                params![mac.to_string()],
+                event,
+            )
+            .optional()?;
+
+        Ok(event.map(|event| match event {
+            PresenceEvent::Arrived { first_seen, .. } => first_seen,
+            PresenceEvent::Departed { last_seen, .. } => last_seen,
+        }))
+    }
+
+    /// Seed a tracker with the devices that were present when the history was last
+    /// written, so a restart doesn't report them as arriving again.
+    ///
+    /// The history doesn't know when they were last seen, so they are taken to be
+    /// seen at the restart: devices that left in the meantime depart once their
+    /// grace period has passed, with the restart as their last sighting.
+    pub fn restore(&self, tracker: &mut PresenceTracker) -> rusqlite::Result<()> {
+        let now = Utc::now();
+        for event in self.present()? {
+            if let PresenceEvent::Arrived { mac, first_seen } = event {
+                tracker.restore(
+                    mac,
+                    Sighting {
+                        first_seen,
+                        last_seen: now,
+                    },
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn event(row: &Row) -> rusqlite::Result<PresenceEvent> {
+    let mac: String = row.get(0)?;
+    let mac: MacAddress = mac.parse().map_err(|err| {
+        rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(err))
+    })?;
+    let kind: String = row.get(1)?;
+    let first_seen = timestamp(row.get(2)?);
+    let last_seen: Option<i64> = row.get(3)?;
+
+    Ok(match (kind.as_str(), last_seen) {
+        ("departed", Some(last_seen)) => PresenceEvent::Departed {
+            mac,
+            first_seen,
+            last_seen: timestamp(last_seen),
+        },
+        _ => PresenceEvent::Arrived { mac, first_seen },
+    })
+}
+
+fn timestamp(millis: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp_millis(millis).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::presence::Observations;
+
+    #[test]
+    fn test_history() {
+        let history = PresenceHistory::open_in_memory().unwrap();
+        let phone: MacAddress = "82:87:EE:2A:86:AE".parse().unwrap();
+        let laptop: MacAddress = "36:95:D8:AD:B3:C4".parse().unwrap();
+        let start = DateTime::from_timestamp(1_742_379_713, 0).unwrap();
+        let later = start + Duration::from_secs(3600);
+
+        let events = [
+            PresenceEvent::Arrived {
+                mac: phone,
+                first_seen: start,
+            },
+            PresenceEvent::Arrived {
+                mac: laptop,
+                first_seen: start,
+            },
+            PresenceEvent::Departed {
+                mac: phone,
+                first_seen: start,
+                last_seen: later,
+            },
+        ];
+        for event in &events {
+            history.record(event).unwrap();
+        }
+
+        assert_eq!(history.events(&[], None).unwrap(), events);
+        assert_eq!(history.events(&[phone], None).unwrap().len(), 2);
+        assert_eq!(
+            history.events(&[], Some(later)).unwrap(),
+            events[2..].to_vec()
+        );
+        assert_eq!(history.present().unwrap(), events[1..2].to_vec());
+        assert_eq!(history.last_departure().unwrap(), Some(events[2]));
+        assert_eq!(history.last_seen(&phone).unwrap(), Some(later));
+        assert_eq!(history.last_seen(&laptop).unwrap(), Some(start));
+
+        let mut tracker = PresenceTracker::new();
+        history.restore(&mut tracker).unwrap();
+        assert!(tracker.sighting(&laptop).is_some());
+        assert!(tracker.sighting(&phone).is_none());
+    }
+
+    #[test]
+    fn test_restored_device_departs() {
+        let history = PresenceHistory::open_in_memory().unwrap();
+        let laptop: MacAddress = "36:95:D8:AD:B3:C4".parse().unwrap();
+        let start = DateTime::from_timestamp(1_742_379_713, 0).unwrap();
+        history
+            .record(&PresenceEvent::Arrived {
+                mac: laptop,
+                first_seen: start,
+            })
+            .unwrap();
+
+        let restarted = Utc::now();
+        let mut tracker = PresenceTracker::new().departure_grace(Duration::from_secs(60));
+        history.restore(&mut tracker).unwrap();
+
+        // Within the grace period of the restart, not of the arrival.
+        let observations = Observations::default();
+        assert!(tracker
+            .update_at(restarted + Duration::from_secs(30), &observations)
+            .is_empty());
+
+        let events = tracker.update_at(restarted + Duration::from_secs(120), &observations);
+        let [PresenceEvent::Departed {
+            mac,
+            first_seen,
+            last_seen,
+        }] = events[..]
+        else {
+            panic!("expected a departure, got {:?}", events);
+        };
+        assert_eq!(mac, laptop);
+        assert_eq!(first_seen, start);
+        assert!(last_seen >= restarted);
+    }
+}
@@ -0,0 +1,271 @@
+//! Named connection profiles, shared by the tonik binaries.
+//!
+//! Profiles live in `~/.config/tonik/config.toml`:
+//!
+//! ```toml
+//! default_profile = "home"
+//!
+//! [profile.home]
+//! host = "192.168.7.1"
+//! password_cmd = "pass show routers/home"
+//!
+//! [profile.boat]
+//! host = "10.8.0.11"
+//! port = 8443
+//! username = "admin"
+//! password_file = "boat.age"
+//! ```
+
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use age::secrecy::SecretString;
+use serde::Deserialize;
+
+/// Environment variable holding the passphrase for `password_file`s. Without it the
+/// passphrase is asked for on the terminal.
+pub const PASSPHRASE_ENV: &str = "TONIK_PASSPHRASE";
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+    /// Profile used when none is named.
+    pub default_profile: Option<String>,
+    #[serde(default, rename = "profile")]
+    pub profiles: HashMap<String, Profile>,
+}
+
+/// How to reach and log in to a router. Anything left out falls back to the
+/// binary's own options.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct Profile {
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    /// Connect over plain HTTP instead of HTTPS.
+    pub http: Option<bool>,
+    /// PEM certificate to trust instead of accepting any certificate.
+    pub cert: Option<PathBuf>,
+    pub username: Option<String>,
+    /// The password in plain text. Prefer `password_cmd` or `password_file`.
+    pub password: Option<String>,
+    /// Shell command printing the password on its first line, e.g. `pass show boat`.
+    pub password_cmd: Option<String>,
+    /// File holding the password, encrypted with an `age` passphrase
+    /// (`age -p -o boat.age`). Relative paths are relative to the config file.
+    pub password_file: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+pub enum ProfileError {
+    Io(PathBuf, std::io::Error),
+    Toml(PathBuf, toml::de::Error),
+    UnknownProfile(String),
+    PasswordCommand(String, String),
+    Decrypt(PathBuf, age::DecryptError),
+}
+
+impl Display for ProfileError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ProfileError::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+            ProfileError::Toml(path, err) => write!(f, "{}: {}", path.display(), err),
+            ProfileError::UnknownProfile(name) => write!(f, "no profile named {:?}", name),
+            ProfileError::PasswordCommand(command, err) => {
+                write!(f, "password command {:?} failed: {}", command, err)
+            }
+            ProfileError::Decrypt(path, err) => {
+                write!(f, "failed to decrypt {}: {}", path.display(), err)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProfileError {}
+
+impl Config {
+    /// `config.toml` in the [`config_dir`].
+    pub fn default_path() -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join("config.toml"))
+    }
+
+    /// Load the config file, resolving relative `password_file`s against its
+    /// directory.
+    pub fn load(path: &Path) -> Result<Self, ProfileError> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|err| ProfileError::Io(path.to_path_buf(), err))?;
+        let mut config: Config =
+            toml::from_str(&contents).map_err(|err| ProfileError::Toml(path.to_path_buf(), err))?;
+
+        let dir = path.parent().unwrap_or(Path::new(""));
+        for profile in config.profiles.values_mut() {
+            profile.cert = profile.cert.take().map(|cert| resolve(dir, cert));
+            profile.password_file = profile.password_file.take().map(|file| resolve(dir, file));
+        }
+
+        Ok(config)
+    }
+
+    /// Load the config file at the default path, if there is one.
+    pub fn load_default() -> Result<Self, ProfileError> {
+        match Self::default_path() {
+            Some(path) if path.exists() => Self::load(&path),
+            _ => Ok(Config::default()),
+        }
+    }
+
+    /// The profile called `name`, or the default profile if no name is given.
+    pub fn profile(&self, name: Option<&str>) -> Result<Option<&Profile>, ProfileError> {
+        match name.or(self.default_profile.as_deref()) {
+            Some(name) => self
+                .profiles
+                .get(name)
+                .map(Some)
+                .ok_or_else(|| ProfileError::UnknownProfile(name.to_string())),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Profile {
+    /// The password, from `password`, `password_cmd` or `password_file` in that order.
+    pub fn password(&self) -> Result<Option<String>, ProfileError> {
+        if let Some(password) = &self.password {
+            return Ok(Some(password.clone()));
+        }
+        if let Some(command) = &self.password_cmd {
+            return run_password_command(command).map(Some);
+        }
+        if let Some(path) = &self.password_file {
+            return decrypt_password_file(path).map(Some);
+        }
+
+        Ok(None)
+    }
+}
+
+fn run_password_command(command: &str) -> Result<String, ProfileError> {
+    let failed = |err: String| ProfileError::PasswordCommand(command.to_string(), err);
+
+    #[cfg(windows)]
+    let output = Command::new("cmd").args(["/C", command]).output();
+    #[cfg(not(windows))]
+    let output = Command::new("sh").args(["-c", command]).output();
+
+    let output = output.map_err(|err| failed(err.to_string()))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(failed(format!("{}: {}", output.status, stderr.trim())));
+    }
+
+    let stdout = String::from_utf8(output.stdout).map_err(|err| failed(err.to_string()))?;
+    Ok(first_line(&stdout))
+}
+
+fn decrypt_password_file(path: &Path) -> Result<String, ProfileError> {
+    let ciphertext =
+        std::fs::read(path).map_err(|err| ProfileError::Io(path.to_path_buf(), err))?;
+
+    let passphrase = match std::env::var(PASSPHRASE_ENV) {
+        Ok(passphrase) => passphrase,
+        Err(_) => rpassword::prompt_password(format!("Passphrase for {}: ", path.display()))
+            .map_err(|err| ProfileError::Io(path.to_path_buf(), err))?,
+    };
+    let identity = age::scrypt::Identity::new(SecretString::from(passphrase));
+
+    let plaintext = age::decrypt(&identity, &ciphertext)
+        .map_err(|err| ProfileError::Decrypt(path.to_path_buf(), err))?;
+
+    Ok(first_line(&String::from_utf8_lossy(&plaintext)))
+}
+
+fn first_line(text: &str) -> String {
+    text.lines().next().unwrap_or_default().to_string()
+}
+
+/// Expand `~/` and make relative paths relative to `dir`.
+fn resolve(dir: &Path, path: PathBuf) -> PathBuf {
+    match (path.strip_prefix("~"), std::env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => dir.join(path),
+    }
+}
+
+/// `$XDG_CONFIG_HOME/tonik`, or `~/.config/tonik`.
+pub fn config_dir() -> Option<PathBuf> {
+    match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => Some(PathBuf::from(dir).join("tonik")),
+        _ => std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config/tonik")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(name: &str, contents: &[u8]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tonik-profile-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_load() {
+        let path = write(
+            "config.toml",
+            br#"
+                default_profile = "home"
+
+                [profile.home]
+                host = "192.168.7.1"
+                password = "secret"
+
+                [profile.boat]
+                host = "10.8.0.11"
+                port = 8443
+                password_cmd = "printf 'from-command\nurl: https://10.8.0.11'"
+                password_file = "boat.age"
+            "#,
+        );
+        let config = Config::load(&path).unwrap();
+
+        let home = config.profile(None).unwrap().unwrap();
+        assert_eq!(home.host.as_deref(), Some("192.168.7.1"));
+        assert_eq!(home.password().unwrap().as_deref(), Some("secret"));
+
+        let boat = config.profile(Some("boat")).unwrap().unwrap();
+        assert_eq!(boat.port, Some(8443));
+        assert_eq!(boat.password_file, Some(path.with_file_name("boat.age")));
+        #[cfg(not(windows))]
+        assert_eq!(boat.password().unwrap().as_deref(), Some("from-command"));
+
+        assert!(matches!(
+            config.profile(Some("office")),
+            Err(ProfileError::UnknownProfile(_))
+        ));
+    }
+
+    #[test]
+    fn test_password_file() {
+        let mut recipient = age::scrypt::Recipient::new(SecretString::from("hunter2".to_string()));
+        recipient.set_work_factor(2);
+        let path = write(
+            "boat.age",
+            &age::encrypt(&recipient, b"from-file\n").unwrap(),
+        );
+
+        std::env::set_var(PASSPHRASE_ENV, "hunter2");
+        assert_eq!(decrypt_password_file(&path).unwrap(), "from-file");
+
+        std::env::set_var(PASSPHRASE_ENV, "wrong");
+        assert!(matches!(
+            decrypt_password_file(&path),
+            Err(ProfileError::Decrypt(..))
+        ));
+        std::env::remove_var(PASSPHRASE_ENV);
+    }
+}
@@ -0,0 +1,210 @@
+//! SIM slots of dual-SIM modems: which one is in use, when the router switches
+//! between them, and the PIN and APN of each.
+
+use std::fmt::{self, Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+use crate::de;
+
+/// A SIM slot of a modem, as reported by `/sim/status`.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct SimSlot {
+    /// Modem the slot belongs to, e.g. `2-1`.
+    pub modem: String,
+    /// Slot number, starting from 1.
+    #[serde(deserialize_with = "de::from_str")]
+    pub sim: u8,
+    /// Whether the modem is using this slot. Only one slot of a modem is active.
+    #[serde(default, deserialize_with = "de::flag")]
+    pub active: bool,
+    #[serde(default, deserialize_with = "de::flag")]
+    pub inserted: bool,
+    /// e.g. `ready`, `sim pin` while waiting for the PIN, or `sim puk`.
+    #[serde(default)]
+    pub pin_state: Option<String>,
+    #[serde(default)]
+    pub iccid: Option<String>,
+    #[serde(default)]
+    pub operator: Option<String>,
+}
+
+impl SimSlot {
+    /// The active slot of `modem`.
+    pub fn active<'a>(slots: &'a [Self], modem: &str) -> Option<&'a Self> {
+        slots.iter().find(|slot| slot.modem == modem && slot.active)
+    }
+}
+
+impl Display for SimSlot {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "SIM {} ({}): ", self.sim, self.modem)?;
+        if !self.inserted {
+            return write!(f, "not inserted");
+        }
+        write!(f, "inserted")?;
+        if let Some(pin_state) = self.pin_state.as_deref().filter(|state| *state != "ready") {
+            write!(f, ", {}", pin_state)?;
+        }
+        if let Some(operator) = &self.operator {
+            write!(f, ", {}", operator)?;
+        }
+        if let Some(iccid) = &self.iccid {
+            write!(f, ", ICCID {}", iccid)?;
+        }
+        Ok(())
+    }
+}
+
+/// When the router switches a modem away from a SIM, as configured in
+/// `/sim/rules/config`.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct SimSwitchRule {
+    /// Config section ID.
+    pub id: String,
+    pub modem: String,
+    /// Slot the rule switches away from.
+    #[serde(deserialize_with = "de::from_str")]
+    pub sim: u8,
+    #[serde(default, deserialize_with = "de::flag")]
+    pub enabled: bool,
+    /// Switch when the SIM is roaming.
+    #[serde(default, deserialize_with = "de::flag")]
+    pub roaming: bool,
+    /// Switch when the SIM can't register to a network.
+    #[serde(default, deserialize_with = "de::flag")]
+    pub no_network: bool,
+    /// Switch when the SIM reaches its data limit.
+    #[serde(default, deserialize_with = "de::flag")]
+    pub data_limit: bool,
+    /// Switch when the signal is weaker than this many dBm.
+    #[serde(default, deserialize_with = "de::optional_from_str")]
+    pub signal_threshold: Option<i64>,
+}
+
+impl Display for SimSwitchRule {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let mut conditions = Vec::new();
+        if self.roaming {
+            conditions.push("when roaming".to_string());
+        }
+        if self.no_network {
+            conditions.push("with no network".to_string());
+        }
+        if self.data_limit {
+            conditions.push("at the data limit".to_string());
+        }
+        if let Some(threshold) = self.signal_threshold {
+            conditions.push(format!("below {} dBm", threshold));
+        }
+
+        write!(f, "Switch from SIM {} ({}) ", self.sim, self.modem)?;
+        match conditions.split_last() {
+            None => write!(f, "never")?,
+            Some((last, [])) => write!(f, "{}", last)?,
+            Some((last, rest)) => write!(f, "{} or {}", rest.join(", "), last)?,
+        }
+        if !self.enabled {
+            write!(f, " (disabled)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Settings of the SIM in one slot, as configured in `/sim/cards/config`.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct SimCard {
+    /// Config section ID.
+    pub id: String,
+    pub modem: String,
+    #[serde(deserialize_with = "de::from_str")]
+    pub sim: u8,
+    /// PIN entered when the modem starts with this SIM. Like the password, it is
+    /// left out when the card is serialized, and only sent to the router by
+    /// [`TeltonikaClient::update_sim_card`](crate::TeltonikaClient::update_sim_card).
+    #[serde(
+        default,
+        rename = "pincode",
+        deserialize_with = "de::non_empty",
+        skip_serializing
+    )]
+    pub pin: Option<String>,
+    /// Access point name. Empty to use the one the operator provides.
+    #[serde(default)]
+    pub apn: String,
+    /// APN authentication: `none`, `pap` or `chap`.
+    #[serde(default, rename = "auth_mode", deserialize_with = "de::non_empty")]
+    pub auth: Option<String>,
+    #[serde(default, deserialize_with = "de::non_empty")]
+    pub username: Option<String>,
+    #[serde(default, deserialize_with = "de::non_empty", skip_serializing)]
+    pub password: Option<String>,
+}
+
+impl Display for SimCard {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "SIM {} ({}): ", self.sim, self.modem)?;
+        if self.apn.is_empty() {
+            write!(f, "automatic APN")?;
+        } else {
+            write!(f, "APN {}", self.apn)?;
+        }
+        if let Some(auth) = self.auth.as_deref().filter(|auth| *auth != "none") {
+            write!(f, ", {}", auth)?;
+            if let Some(username) = &self.username {
+                write!(f, " as {}", username)?;
+            }
+        }
+        if self.pin.is_some() {
+            write!(f, ", PIN set")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_rule_display() {
+        let rule: SimSwitchRule = serde_json::from_value(json!({
+            "id": "cfg01",
+            "modem": "2-1",
+            "sim": "1",
+            "enabled": "1",
+            "roaming": "1",
+            "no_network": "1",
+            "data_limit": "0",
+            "signal_threshold": "-110",
+        }))
+        .unwrap();
+
+        assert_eq!(
+            rule.to_string(),
+            "Switch from SIM 1 (2-1) when roaming, with no network or below -110 dBm"
+        );
+    }
+
+    #[test]
+    fn test_card_display() {
+        let card: SimCard = serde_json::from_value(json!({
+            "id": "cfg02",
+            "modem": "2-1",
+            "sim": "2",
+            "pincode": "1234",
+            "apn": "everywhere",
+            "auth_mode": "pap",
+            "username": "eesecure",
+            "password": "secure",
+        }))
+        .unwrap();
+
+        assert_eq!(
+            card.to_string(),
+            "SIM 2 (2-1): APN everywhere, pap as eesecure, PIN set"
+        );
+    }
+}
@@ -0,0 +1,167 @@
+//! Watching for incoming SMS messages.
+
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
+
+use futures::{stream, Stream};
+
+use crate::{Error, Response, SmsMessage, TeltonikaClient};
+
+/// Polls `/messages/status` and yields each message once.
+///
+/// Messages are identified by their modem and ID. IDs that disappear from the
+/// router are forgotten, so a reused ID is reported as a new message.
+pub struct SmsWatcher {
+    client: Arc<TeltonikaClient>,
+    interval: Duration,
+    delete_after_read: bool,
+    include_existing: bool,
+    seen: Option<HashSet<(String, String)>>,
+    /// A failed delete, reported by the next poll once the messages that were
+    /// deleted have been returned.
+    delete_error: Option<Error>,
+}
+
+impl SmsWatcher {
+    pub fn new(client: Arc<TeltonikaClient>) -> Self {
+        SmsWatcher {
+            client,
+            interval: Duration::from_secs(5),
+            delete_after_read: false,
+            include_existing: false,
+            seen: None,
+            delete_error: None,
+        }
+    }
+
+    /// How often the router is polled. Defaults to five seconds.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Delete messages from the router once they have been yielded.
+    pub fn delete_after_read(mut self, delete: bool) -> Self {
+        self.delete_after_read = delete;
+        self
+    }
+
+    /// Also yield messages that were already stored when the watcher started.
+    pub fn include_existing(mut self, include: bool) -> Self {
+        self.include_existing = include;
+        self
+    }
+
+    /// Poll the router once, returning messages that haven't been seen before.
+    ///
+    /// When deleting after reading, messages that couldn't be deleted are left
+    /// out and returned by a later poll. If others were deleted, the error is
+    /// returned by the next poll instead, so those aren't lost.
+    pub async fn poll(&mut self) -> Result<Vec<SmsMessage>, Error> {
+        if let Some(err) = self.delete_error.take() {
+            return Err(err);
+        }
+
+        let messages = self
+            .client
+            .list_sms_messages()
+            .await
+            .and_then(Response::into_result)?;
+
+        let current: HashSet<(String, String)> = messages.iter().map(key).collect();
+        let mut new_messages: Vec<SmsMessage> = match &self.seen {
+            Some(seen) => messages
+                .into_iter()
+                .filter(|message| !seen.contains(&key(message)))
+                .collect(),
+            None if self.include_existing => messages,
+            None => Vec::new(),
+        };
+        self.seen = Some(current);
+
+        if self.delete_after_read && !new_messages.is_empty() {
+            let mut failures = self.delete(&new_messages).await;
+
+            // Whether or not they were deleted, these IDs are no longer tracked:
+            // on success they are free to be reused, on failure they are retried.
+            if let Some(seen) = &mut self.seen {
+                for message in &new_messages {
+                    seen.remove(&key(message));
+                }
+            }
+
+            if !failures.is_empty() {
+                new_messages.retain(|message| {
+                    !failures.iter().any(|(modem, _)| *modem == message.modem_id)
+                });
+                let (_, err) = failures.remove(0);
+                if new_messages.is_empty() {
+                    return Err(err);
+                }
+                self.delete_error = Some(err);
+            }
+        }
+
+        Ok(new_messages)
+    }
+
+    /// Delete messages modem by modem, returning the modems they couldn't be
+    /// deleted from.
+    async fn delete(&self, messages: &[SmsMessage]) -> Vec<(String, Error)> {
+        let mut modems: Vec<&str> = messages.iter().map(|m| m.modem_id.as_str()).collect();
+        modems.sort();
+        modems.dedup();
+
+        let mut failures = Vec::new();
+        for modem in modems {
+            let ids: Vec<String> = messages
+                .iter()
+                .filter(|message| message.modem_id == modem)
+                .map(|message| message.id.clone())
+                .collect();
+            let result = self
+                .client
+                .delete_sms_messages(modem, &ids)
+                .await
+                .and_then(Response::ensure_success);
+            if let Err(err) = result {
+                failures.push((modem.to_string(), err));
+            }
+        }
+
+        failures
+    }
+
+    /// Turn the watcher into a stream of new messages.
+    ///
+    /// A failed poll yields its error and is retried after the polling
+    /// interval; the stream itself never ends.
+    pub fn into_stream(self) -> impl Stream<Item = Result<SmsMessage, Error>> {
+        let state = (self, VecDeque::new(), true);
+
+        stream::unfold(state, |(mut watcher, mut pending, mut first)| async move {
+            loop {
+                if let Some(message) = pending.pop_front() {
+                    return Some((Ok(message), (watcher, pending, first)));
+                }
+
+                if !first {
+                    tokio::time::sleep(watcher.interval).await;
+                }
+                first = false;
+
+                match watcher.poll().await {
+                    Ok(messages) => pending.extend(messages),
+                    Err(err) => return Some((Err(err), (watcher, pending, first))),
+                }
+            }
+        })
+    }
+}
+
+fn key(message: &SmsMessage) -> (String, String) {
+    (message.modem_id.clone(), message.id.clone())
+}
@@ -0,0 +1,107 @@
+//! Showing responses as rows and columns.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// One row of a table, keyed by column name.
+pub type Row = BTreeMap<String, Value>;
+
+/// A response type that can be shown as rows of a table, e.g. as CSV.
+///
+/// Every serialized field is a column. Fields of nested objects are named
+/// `parent.child`; lists stay as they are.
+pub trait Tabular: Serialize {
+    /// The columns shown unless others are chosen, in order.
+    const COLUMNS: &'static [&'static str];
+
+    fn row(&self) -> Row {
+        flatten(&serde_json::to_value(self).unwrap_or(Value::Null))
+    }
+}
+
+/// Flatten a JSON value into a row. Anything but an object becomes a single
+/// `value` column.
+pub fn flatten(value: &Value) -> Row {
+    let mut row = Row::new();
+    match value {
+        Value::Object(_) => flatten_into(&mut row, None, value),
+        value => {
+            row.insert("value".to_string(), value.clone());
+        }
+    }
+    row
+}
+
+fn flatten_into(row: &mut Row, prefix: Option<&str>, value: &Value) {
+    match value {
+        Value::Object(fields) => {
+            for (name, value) in fields {
+                let name = match prefix {
+                    Some(prefix) => format!("{}.{}", prefix, name),
+                    None => name.clone(),
+                };
+                flatten_into(row, Some(&name), value);
+            }
+        }
+        value => {
+            row.insert(prefix.unwrap_or("value").to_string(), value.clone());
+        }
+    }
+}
+
+macro_rules! tabular {
+    ($($type:ty => [$($column:literal),* $(,)?]),* $(,)?) => {
+        $(
+            impl Tabular for $type {
+                const COLUMNS: &'static [&'static str] = &[$($column),*];
+            }
+        )*
+    };
+}
+
+tabular! {
+    crate::BillingPeriodUsage => ["modem", "sim", "start", "end", "used", "today", "limit", "projected"],
+    crate::DataLimit => ["id", "modem", "sim", "enabled", "data_limit", "period", "day", "warning_limit"],
+    crate::DhcpLease => ["macaddr", "ipaddr", "hostname", "expires"],
+    crate::DhcpLeaseV6 => ["duid", "ipv6addr", "hostname", "interface", "expires"],
+    crate::FirmwareDeviceStatus => ["version", "kernel_version", "build_date"],
+    crate::FirmwareProgress => ["process", "percents"],
+    crate::GpsPositionStatus => ["latitude", "longitude", "altitude", "accuracy", "satellites", "fix_status", "utc_timestamp"],
+    crate::InterfaceStatus => ["ifname", "ssid", "device.name", "up", "status", "quality", "noise", "bitrate"],
+    crate::IpNeighborStatusV4 => ["dest", "mac", "dev", "reachable", "stale", "permanent"],
+    crate::ModemStatus => ["id", "name", "operator", "network_type", "rssi", "rsrp", "rsrq", "sinr", "sim_state"],
+    crate::NetworkInterfaceStatus => ["id", "proto", "up", "device", "ipv4_addresses", "gateway", "metric", "uptime"],
+    crate::PortForward => ["id", "name", "enabled", "proto", "src_dport", "dest_ip", "dest_port"],
+    crate::SimCard => ["id", "modem", "sim", "apn", "auth_mode", "username"],
+    crate::SimSlot => ["modem", "sim", "active", "inserted", "pin_state", "operator", "iccid"],
+    crate::SimSwitchRule => ["id", "modem", "sim", "enabled", "roaming", "no_network", "data_limit", "signal_threshold"],
+    crate::SmsMessage => ["id", "sender", "date", "status", "message"],
+    crate::TrafficRule => ["id", "name", "enabled", "target", "proto", "src", "src_ip", "dest", "dest_ip", "dest_port"],
+    crate::UsageTotal => ["modem", "sim", "start", "end", "rx_bytes", "tx_bytes"],
+    crate::WirelessClient => ["mac", "hostname", "ipaddr", "ifname", "ssid", "signal", "quality"],
+    crate::Zone => ["id", "name", "network", "input", "output", "forward", "masq"],
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_flatten() {
+        let row = flatten(&json!({
+            "ifname": "wlan0",
+            "device": { "name": "radio0", "up": true },
+            "ipv6addr": ["fd00::1"],
+        }));
+
+        assert_eq!(row["ifname"], "wlan0");
+        assert_eq!(row["device.name"], "radio0");
+        assert_eq!(row["device.up"], true);
+        assert_eq!(row["ipv6addr"], json!(["fd00::1"]));
+        assert_eq!(flatten(&json!(42))["value"], 42);
+    }
+}
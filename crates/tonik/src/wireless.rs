@@ -0,0 +1,195 @@
+//! Wireless clients and their signal strength.
+
+use std::fmt::{self, Display, Formatter};
+
+use serde::Serialize;
+
+use crate::{DhcpLease, Error, InterfaceStatus, MacAddress, Response, TeltonikaClient};
+
+/// How good a received signal strength is, for Wi-Fi.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignalQuality {
+    Poor,
+    Fair,
+    Good,
+    Excellent,
+}
+
+impl SignalQuality {
+    /// Classify a signal in dBm: -50 and above is excellent, -60 good, -70 fair
+    /// and anything weaker poor.
+    pub fn of_signal(signal: i64) -> Self {
+        match signal {
+            s if s >= -50 => SignalQuality::Excellent,
+            s if s >= -60 => SignalQuality::Good,
+            s if s >= -70 => SignalQuality::Fair,
+            _ => SignalQuality::Poor,
+        }
+    }
+}
+
+impl Display for SignalQuality {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(match self {
+            SignalQuality::Poor => "poor",
+            SignalQuality::Fair => "fair",
+            SignalQuality::Good => "good",
+            SignalQuality::Excellent => "excellent",
+        })
+    }
+}
+
+/// A device associated to a wireless interface, named from its DHCP lease.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WirelessClient {
+    pub mac: MacAddress,
+    pub ifname: String,
+    pub ssid: String,
+    /// Received signal strength in dBm.
+    pub signal: i64,
+    /// Noise floor of the interface in dBm.
+    pub noise: i64,
+    pub quality: SignalQuality,
+    pub hostname: Option<String>,
+    pub ipaddr: Option<String>,
+}
+
+impl WirelessClient {
+    /// Every associated client of the interfaces, with the hostname and address of
+    /// its lease if it has one. Sorted by interface, then strongest signal first.
+    pub fn join(interfaces: &[InterfaceStatus], leases: &[DhcpLease]) -> Vec<Self> {
+        let mut clients: Vec<Self> = interfaces
+            .iter()
+            .flat_map(|interface| {
+                interface.assoclist.iter().map(|(mac, assoc)| {
+                    let lease = leases.iter().find(|lease| lease.macaddr == *mac);
+                    WirelessClient {
+                        mac: *mac,
+                        ifname: interface.ifname.clone(),
+                        ssid: interface.ssid.clone(),
+                        signal: assoc.signal,
+                        noise: interface.noise,
+                        quality: SignalQuality::of_signal(assoc.signal),
+                        hostname: lease.and_then(|lease| lease.hostname.clone()),
+                        ipaddr: lease.map(|lease| lease.ipaddr.clone()),
+                    }
+                })
+            })
+            .collect();
+
+        clients.sort_by(|a, b| (&a.ifname, b.signal, a.mac).cmp(&(&b.ifname, a.signal, b.mac)));
+        clients
+    }
+
+    /// Fetch the wireless interfaces and DHCP leases and join them.
+    ///
+    /// Clients are still listed if the router has no DHCP server, just without
+    /// hostnames.
+    pub async fn fetch(client: &TeltonikaClient) -> Result<Vec<Self>, Error> {
+        let (interfaces, leases) = futures::join!(
+            client.wireless_interfaces_status(),
+            client.dhcp_leases_ipv4_status(),
+        );
+
+        let interfaces = interfaces.and_then(Response::into_result)?;
+        let leases = leases.and_then(Response::into_result).unwrap_or_default();
+
+        Ok(Self::join(&interfaces, &leases))
+    }
+
+    /// Signal to noise ratio in dB.
+    pub fn snr(&self) -> i64 {
+        self.signal - self.noise
+    }
+
+    /// The hostname, or the MAC address if there is none.
+    pub fn name(&self) -> String {
+        self.hostname
+            .clone()
+            .unwrap_or_else(|| self.mac.to_string())
+    }
+}
+
+impl Display for WirelessClient {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} dBm ({}) on {}",
+            self.mac,
+            self.hostname.as_deref().unwrap_or("-"),
+            self.signal,
+            self.quality,
+            self.ssid
+        )
+    }
+}
+
+impl Display for InterfaceStatus {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {:?} on {}, {}, {} clients",
+            self.ifname,
+            self.ssid,
+            self.device.name,
+            if self.up { "up" } else { "down" },
+            self.assoclist.len()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_signal_quality() {
+        assert_eq!(SignalQuality::of_signal(-42), SignalQuality::Excellent);
+        assert_eq!(SignalQuality::of_signal(-50), SignalQuality::Excellent);
+        assert_eq!(SignalQuality::of_signal(-59), SignalQuality::Good);
+        assert_eq!(SignalQuality::of_signal(-70), SignalQuality::Fair);
+        assert_eq!(SignalQuality::of_signal(-71), SignalQuality::Poor);
+    }
+
+    #[test]
+    fn test_join() {
+        let interfaces: Vec<InterfaceStatus> = serde_json::from_value(json!([{
+            "ifname": "wlan0",
+            "disabled": false,
+            "op_class": 81,
+            "status": "enabled",
+            "quality": 54,
+            "noise": -95,
+            "up": true,
+            "device": { "device": "radio0", "pending": false, "name": "radio0", "up": true },
+            "txpoweroff": 0,
+            "bitrate": 144400,
+            "name": "default_radio0",
+            "ssid": "Boat",
+            "assoclist": {
+                "82:87:EE:2A:86:AE": { "signal": -48 },
+                "B8:27:EB:FB:2A:45": { "signal": -67 },
+            },
+        }]))
+        .unwrap();
+        let leases: Vec<DhcpLease> = serde_json::from_value(json!([{
+            "expires": 43200,
+            "macaddr": "82:87:EE:2A:86:AE",
+            "ipaddr": "192.168.7.120",
+            "hostname": "Bens-iPhone",
+        }]))
+        .unwrap();
+
+        let clients = WirelessClient::join(&interfaces, &leases);
+
+        assert_eq!(clients.len(), 2);
+        assert_eq!(clients[0].hostname.as_deref(), Some("Bens-iPhone"));
+        assert_eq!(clients[0].ipaddr.as_deref(), Some("192.168.7.120"));
+        assert_eq!(clients[0].snr(), 47);
+        assert_eq!(clients[1].name(), "B8:27:EB:FB:2A:45");
+        assert_eq!(clients[1].quality, SignalQuality::Fair);
+    }
+}
@@ -0,0 +1,776 @@
+use std::{sync::Arc, time::Duration};
+
+use futures::StreamExt;
+use tonik::{
+    presence::PresenceWatcher, BillingPeriodUsage, DataLimit, Error, FailoverMember, FailoverState,
+    FirmwareProcess, MacAddress, NetworkInterfaceStatus, PortForward, Response, Scheme, SimCard,
+    SimSwitchRule, SmsSendRequest, TeltonikaClient,
+};
+use tonik_mock::{MockRouter, DEFAULT_PASSWORD, DEFAULT_USERNAME};
+
+fn client(router: &MockRouter) -> TeltonikaClient {
+    TeltonikaClient::builder(router.host())
+        .scheme(Scheme::Http)
+        .port(router.port())
+        .build()
+        .unwrap()
+}
+
+async fn authenticated_client(router: &MockRouter) -> TeltonikaClient {
+    let client = client(router);
+    client
+        .authenticate(DEFAULT_USERNAME, DEFAULT_PASSWORD)
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    client
+}
+
+#[tokio::test]
+async fn test_login() {
+    let router = MockRouter::start().await.unwrap();
+    let client = client(&router);
+
+    let login = client
+        .authenticate(DEFAULT_USERNAME, DEFAULT_PASSWORD)
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+
+    assert_eq!(login.username, DEFAULT_USERNAME);
+    assert_eq!(router.login_count(), 1);
+}
+
+#[tokio::test]
+async fn test_login_with_wrong_password() {
+    let router = MockRouter::start().await.unwrap();
+    let client = client(&router);
+
+    let err = client
+        .authenticate(DEFAULT_USERNAME, "wrong")
+        .await
+        .and_then(Response::into_result)
+        .unwrap_err();
+
+    assert!(err.is_unauthorized());
+}
+
+#[tokio::test]
+async fn test_request_without_session() {
+    let router = MockRouter::start().await.unwrap();
+    let client = client(&router);
+
+    let err = client.dhcp_leases_ipv4_status().await.unwrap_err();
+
+    assert!(matches!(err, Error::Unauthorized(_)));
+}
+
+#[tokio::test]
+async fn test_status_endpoints() {
+    let router = MockRouter::start().await.unwrap();
+    let client = authenticated_client(&router).await;
+
+    assert!(
+        client
+            .session_status()
+            .await
+            .and_then(Response::into_result)
+            .unwrap()
+            .active
+    );
+    assert_eq!(
+        client
+            .dhcp_leases_ipv4_status()
+            .await
+            .and_then(Response::into_result)
+            .unwrap()
+            .len(),
+        2
+    );
+    assert_eq!(
+        client
+            .dhcp_leases_ipv6_status()
+            .await
+            .and_then(Response::into_result)
+            .unwrap()
+            .len(),
+        1
+    );
+    client
+        .firmware_device_status()
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    client
+        .gps_position_status()
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    client
+        .wireless_devices_status()
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    client
+        .wireless_interfaces_status()
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    client
+        .list_sms_messages()
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_ip_neighbors_follow_router_state() {
+    let router = MockRouter::start().await.unwrap();
+    let client = authenticated_client(&router).await;
+
+    let mac: MacAddress = "AA:BB:CC:DD:EE:FF".parse().unwrap();
+
+    router.add_neighbor("AA:BB:CC:DD:EE:FF", "192.168.7.200");
+    let neighbors = client
+        .ip_neighbors_ipv4_status()
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    assert!(neighbors.iter().any(|n| n.mac == Some(mac)));
+
+    router.remove_neighbor("aa:bb:cc:dd:ee:ff");
+    let neighbors = client
+        .ip_neighbors_ipv4_status()
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    assert!(!neighbors.iter().any(|n| n.mac == Some(mac)));
+}
+
+#[tokio::test]
+async fn test_presence_stream_yields_errors() {
+    let router = MockRouter::start().await.unwrap();
+    let stream = PresenceWatcher::new(Arc::new(client(&router)))
+        .interval(Duration::from_millis(20))
+        .include_existing(true)
+        .into_stream();
+    let mut stream = Box::pin(stream);
+
+    // Without a session every poll fails.
+    let next = tokio::time::timeout(Duration::from_secs(5), stream.next())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(next, Err(Error::Unauthorized(_))));
+}
+
+#[tokio::test]
+async fn test_send_sms_message() {
+    let router = MockRouter::start().await.unwrap();
+    let client = authenticated_client(&router).await;
+
+    let response = client
+        .send_sms_message("+447700900123", "Hello")
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+
+    assert_eq!(response.sms_used, 1);
+    assert_eq!(router.sent_messages()[0]["data"]["message"], "Hello");
+    assert_eq!(router.sent_messages()[0]["data"]["modem"], "2-1");
+}
+
+#[tokio::test]
+async fn test_send_sms_with_explicit_modem() {
+    let router = MockRouter::start().await.unwrap();
+    let client = authenticated_client(&router).await;
+
+    client
+        .send_sms(&SmsSendRequest::new("+447700900123", "Hello").modem("1-1"))
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    assert_eq!(router.sent_messages()[0]["data"]["modem"], "1-1");
+
+    let err = client
+        .send_sms(&SmsSendRequest::new("+447700900123", "Hello").modem("9-9"))
+        .await
+        .and_then(Response::into_result)
+        .unwrap_err();
+    assert!(matches!(err, Error::Api(_)));
+}
+
+#[tokio::test]
+async fn test_primary_modem() {
+    let router = MockRouter::start().await.unwrap();
+    let client = authenticated_client(&router).await;
+
+    assert_eq!(client.primary_modem().await.unwrap(), "2-1");
+}
+
+#[tokio::test]
+async fn test_session_renewed_before_expiry() {
+    let router = MockRouter::start().await.unwrap();
+    router.set_token_lifetime(Duration::from_secs(5));
+    let client = client(&router).with_credentials(DEFAULT_USERNAME, DEFAULT_PASSWORD);
+
+    client
+        .dhcp_leases_ipv4_status()
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    client
+        .dhcp_leases_ipv4_status()
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+
+    // The token expires within the renewal margin, so every request logs in again.
+    assert_eq!(router.login_count(), 2);
+}
+
+#[tokio::test]
+async fn test_session_renewed_after_unauthorized() {
+    let router = MockRouter::start().await.unwrap();
+    let client = client(&router).with_credentials(DEFAULT_USERNAME, DEFAULT_PASSWORD);
+
+    client
+        .dhcp_leases_ipv4_status()
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    assert_eq!(router.login_count(), 1);
+
+    router.expire_tokens();
+
+    client
+        .dhcp_leases_ipv4_status()
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    assert_eq!(router.login_count(), 2);
+}
+
+#[tokio::test]
+async fn test_restore_session() {
+    let router = MockRouter::start().await.unwrap();
+    let first = authenticated_client(&router).await;
+    let token = first.session_token().await.unwrap();
+    let expires_in = first.session_expires_in().await.unwrap();
+
+    let second = client(&router);
+    second.restore_session(token, expires_in).await;
+    let status = second
+        .session_status()
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    assert!(status.active);
+    assert_eq!(router.login_count(), 1);
+
+    router.expire_tokens();
+    assert!(second
+        .session_status()
+        .await
+        .and_then(Response::into_result)
+        .unwrap_err()
+        .is_unauthorized());
+}
+
+#[tokio::test]
+async fn test_modems_status() {
+    let router = MockRouter::start().await.unwrap();
+    let client = authenticated_client(&router).await;
+
+    let modems = client
+        .modems_status()
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+
+    assert_eq!(modems.len(), 2);
+    let modem = modems.iter().find(|modem| modem.primary).unwrap();
+    assert_eq!(modem.id, "2-1");
+    assert_eq!(modem.operator.as_deref(), Some("Vodafone UK"));
+    assert_eq!(modem.rsrp, Some(-95.0));
+}
+
+#[tokio::test]
+async fn test_mark_read_and_delete_sms_messages() {
+    let router = MockRouter::start().await.unwrap();
+    let client = authenticated_client(&router).await;
+    let id = router.receive_sms("+447700900456", "Are you aboard?");
+
+    client
+        .mark_sms_messages_read("2-1", std::slice::from_ref(&id))
+        .await
+        .and_then(Response::ensure_success)
+        .unwrap();
+    let messages = client
+        .list_sms_messages()
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    assert!(!messages.iter().find(|m| m.id == id).unwrap().is_unread());
+
+    client
+        .delete_sms_messages("2-1", std::slice::from_ref(&id))
+        .await
+        .and_then(Response::ensure_success)
+        .unwrap();
+    let messages = client
+        .list_sms_messages()
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    assert!(!messages.iter().any(|m| m.id == id));
+
+    let err = client
+        .delete_sms_messages("2-1", std::slice::from_ref(&id))
+        .await
+        .and_then(Response::ensure_success)
+        .unwrap_err();
+    assert!(matches!(err, Error::Api(_)));
+}
+
+#[tokio::test]
+async fn test_typed_fields() {
+    let router = MockRouter::start().await.unwrap();
+    let client = authenticated_client(&router).await;
+
+    let gps = client
+        .gps_position_status()
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    assert_eq!(gps.latitude, Some(51.589495));
+    assert_eq!(gps.satellites, Some(7));
+    assert!(gps.has_fix());
+
+    let neighbors = client
+        .ip_neighbors_ipv4_status()
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    assert!(neighbors[0].reachable);
+    assert!(!neighbors[0].stale);
+    assert_eq!(neighbors[0].dest.to_string(), "192.168.7.120");
+    assert!(neighbors[1].stale);
+
+    let messages = client
+        .list_sms_messages()
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    assert_eq!(messages[0].date.to_rfc3339(), "2025-03-19T10:21:53+00:00");
+}
+
+#[tokio::test]
+async fn test_firmware_fota_upgrade() {
+    let router = MockRouter::start().await.unwrap();
+    let client = authenticated_client(&router).await;
+
+    let current = client
+        .firmware_device_status()
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    let updates = client
+        .firmware_device_updates_status()
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    assert!(updates.device.differs_from(&current.version));
+
+    // Nothing has been downloaded yet.
+    let err = client
+        .firmware_actions_upgrade(true)
+        .await
+        .and_then(Response::ensure_success)
+        .unwrap_err();
+    assert!(matches!(err, Error::Api(_)));
+
+    client
+        .firmware_actions_fota_download()
+        .await
+        .and_then(Response::ensure_success)
+        .unwrap();
+    loop {
+        let progress = client
+            .firmware_device_progress_status()
+            .await
+            .and_then(Response::into_result)
+            .unwrap();
+        if progress.is_finished() {
+            assert_eq!(progress.process, FirmwareProcess::Succeeded);
+            break;
+        }
+    }
+
+    client
+        .firmware_actions_upgrade(true)
+        .await
+        .and_then(Response::ensure_success)
+        .unwrap();
+    assert_eq!(router.firmware_upgrades()[0]["data"]["keep_settings"], "1");
+}
+
+#[tokio::test]
+async fn test_upload_device_firmware() {
+    let router = MockRouter::start().await.unwrap();
+    let client = authenticated_client(&router).await;
+
+    client
+        .upload_device_firmware("RUTX_R_00.07.07.1_WEBUI.bin", vec![0x27, 0x05, 0x19, 0x56])
+        .await
+        .and_then(Response::ensure_success)
+        .unwrap();
+    assert!(router.has_staged_firmware());
+
+    client
+        .firmware_actions_upgrade(false)
+        .await
+        .and_then(Response::ensure_success)
+        .unwrap();
+    assert_eq!(router.firmware_upgrades()[0]["data"]["keep_settings"], "0");
+}
+
+#[tokio::test]
+async fn test_upload_device_firmware_outlasts_timeout() {
+    let router = MockRouter::start().await.unwrap();
+    router.delay_firmware_uploads(Duration::from_millis(500));
+    let client = TeltonikaClient::builder(router.host())
+        .scheme(Scheme::Http)
+        .port(router.port())
+        .timeout(Duration::from_millis(100))
+        .build()
+        .unwrap();
+    client
+        .authenticate(DEFAULT_USERNAME, DEFAULT_PASSWORD)
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+
+    client
+        .upload_device_firmware("RUTX_R_00.07.07.1_WEBUI.bin", vec![0x27, 0x05, 0x19, 0x56])
+        .await
+        .and_then(Response::ensure_success)
+        .unwrap();
+    assert!(router.has_staged_firmware());
+}
+
+#[tokio::test]
+async fn test_port_forward_crud() {
+    let router = MockRouter::start().await.unwrap();
+    let client = authenticated_client(&router).await;
+
+    let forward = PortForward::new("8080", "192.168.7.20")
+        .name("contractor")
+        .dest_port("80")
+        .proto(&["tcp"]);
+    let created = client
+        .create_port_forward(&forward)
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    assert!(!created.id.is_empty());
+    assert_eq!(created.dest_port.as_deref(), Some("80"));
+
+    let forwards = client
+        .list_port_forwards()
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    assert_eq!(forwards.len(), 2);
+    assert_eq!(forwards[1], created);
+
+    let disabled = PortForward {
+        enabled: false,
+        dest_port: None,
+        ..created.clone()
+    };
+    client
+        .update_port_forward(&created.id, &disabled)
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    let updated = client
+        .get_port_forward(&created.id)
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    assert!(!updated.enabled);
+    assert_eq!(updated.dest_port, None);
+    assert_eq!(updated.name.as_deref(), Some("contractor"));
+
+    let deleted = client
+        .delete_port_forward(&created.id)
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    assert_eq!(deleted.id, created.id);
+
+    let err = client
+        .delete_port_forward(&created.id)
+        .await
+        .and_then(Response::into_result)
+        .unwrap_err();
+    assert_eq!(err.api_errors()[0].code, 103);
+}
+
+#[tokio::test]
+async fn test_traffic_rules_and_zones() {
+    let router = MockRouter::start().await.unwrap();
+    let client = authenticated_client(&router).await;
+
+    let rules = client
+        .list_traffic_rules()
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    assert_eq!(rules[2].name.as_deref(), Some("Block-SSH-WAN"));
+    assert!(!rules[2].enabled);
+
+    let zones = client
+        .list_zones()
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    assert_eq!(zones[1].name, "wan");
+    assert!(zones[1].masq);
+    assert_eq!(zones[1].network, ["wan", "wan6", "mob1s1a1"]);
+}
+
+#[tokio::test]
+async fn test_interfaces_status() {
+    let router = MockRouter::start().await.unwrap();
+    let client = authenticated_client(&router).await;
+
+    let interfaces = client
+        .interfaces_status()
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    assert_eq!(interfaces.len(), 4);
+    assert!(!interfaces[0].is_wan());
+    let active = NetworkInterfaceStatus::active_wan(&interfaces).unwrap();
+    assert_eq!(active.id, "wan");
+    assert_eq!(active.rx_bytes, Some(1234567890));
+
+    router.set_interface_up("wan", false);
+    let wan = client
+        .interface_status("wan")
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    assert!(!wan.up);
+    let interfaces = client
+        .interfaces_status()
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    let active = NetworkInterfaceStatus::active_wan(&interfaces).unwrap();
+    assert_eq!(active.id, "wwan");
+}
+
+#[tokio::test]
+async fn test_failover() {
+    let router = MockRouter::start().await.unwrap();
+    let client = authenticated_client(&router).await;
+
+    let members = client
+        .list_failover_members()
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    assert_eq!(members[0].interface, "wan");
+    assert_eq!(members[0].metric, Some(1));
+    assert_eq!(members[0].track_ip, ["1.1.1.1", "8.8.8.8"]);
+
+    for member in FailoverMember::prefer(&members, "wwan").unwrap() {
+        client
+            .update_failover_member(&member.id, &member)
+            .await
+            .and_then(Response::into_result)
+            .unwrap();
+    }
+    let wwan = client
+        .get_failover_member(&members[1].id)
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    assert_eq!(wwan.metric, Some(1));
+
+    router.set_failover_status("mob1s1a1", "offline");
+    let status = client
+        .failover_status()
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    assert!(status[0].is_online());
+    assert_eq!(status[2].status, FailoverState::Offline);
+    assert_eq!(status[1].track_ip[0].latency, Some(12.0));
+}
+
+#[tokio::test]
+async fn test_data_usage() {
+    let router = MockRouter::start().await.unwrap();
+    let client = authenticated_client(&router).await;
+
+    let records = client
+        .data_usage()
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    assert_eq!(records.len(), 120);
+
+    let limits = client
+        .list_data_limits()
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    assert_eq!(limits[0].limit_bytes(), Some(20_000_000_000));
+    assert_eq!(limits[1].limit_bytes(), None);
+
+    let limit = DataLimit {
+        limit: Some(30000),
+        ..limits[0].clone()
+    };
+    client
+        .update_data_limit(&limit.id, &limit)
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+
+    router.add_data_usage(1, 2_000_000_000);
+    let usage = BillingPeriodUsage::fetch(&client).await.unwrap();
+    assert_eq!(usage.len(), 2);
+    assert_eq!(usage[0].limit, Some(30_000_000_000));
+    assert_eq!(usage[0].today, 3_000_000_000);
+    assert!(usage[0].used >= usage[0].today);
+    assert!(usage[0].projected >= usage[0].used);
+}
+
+#[tokio::test]
+async fn test_sim_switching() {
+    let router = MockRouter::start().await.unwrap();
+    let client = authenticated_client(&router).await;
+
+    assert_eq!(client.active_sim("2-1").await.unwrap(), 1);
+    assert!(matches!(
+        client.active_sim("1-1").await,
+        Err(Error::MissingData)
+    ));
+
+    let active = client
+        .switch_sim("2-1", 2)
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    assert_eq!(active.sim, 2);
+    assert_eq!(active.operator.as_deref(), Some("EE"));
+    assert_eq!(client.active_sim("2-1").await.unwrap(), 2);
+    assert!(client
+        .switch_sim("2-1", 3)
+        .await
+        .and_then(Response::into_result)
+        .is_err());
+
+    let rules = client
+        .list_sim_switch_rules()
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    assert!(rules[0].roaming);
+    assert_eq!(rules[0].signal_threshold, Some(-110));
+    assert_eq!(rules[1].signal_threshold, None);
+
+    // Cleared fields are sent so that the router clears them too.
+    let rule = SimSwitchRule {
+        signal_threshold: None,
+        ..rules[0].clone()
+    };
+    client
+        .update_sim_switch_rule(&rule.id, &rule)
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    let updated = client
+        .get_sim_switch_rule(&rule.id)
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    assert_eq!(updated, rule);
+
+    let cards = client
+        .list_sim_cards()
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    let card = SimCard {
+        apn: "internet".to_string(),
+        pin: Some("0000".to_string()),
+        ..cards[0].clone()
+    };
+    client
+        .update_sim_card(&card.id, &card)
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    let updated = client
+        .get_sim_card(&card.id)
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    assert_eq!(updated.apn, "internet");
+    assert_eq!(updated.pin.as_deref(), Some("0000"));
+}
+
+#[tokio::test]
+async fn test_sim_pin() {
+    let router = MockRouter::start().await.unwrap();
+    let client = authenticated_client(&router).await;
+    router.lock_sim(2, "1234");
+
+    let err = client
+        .unlock_sim("2-1", 2, "0000")
+        .await
+        .and_then(Response::into_result)
+        .unwrap_err();
+    assert!(matches!(err, Error::Api(_)));
+
+    let slot = client
+        .unlock_sim("2-1", 2, "1234")
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    assert_eq!(slot.pin_state.as_deref(), Some("ready"));
+
+    client
+        .change_sim_pin("2-1", 2, "1234", "4321")
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    assert!(client
+        .change_sim_pin("2-1", 2, "1234", "5678")
+        .await
+        .and_then(Response::into_result)
+        .is_err());
+
+    let card = client
+        .set_sim_pin("2-1", 2, Some("4321"))
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    assert_eq!(card.pin.as_deref(), Some("4321"));
+    let card = client
+        .set_sim_pin("2-1", 2, None)
+        .await
+        .and_then(Response::into_result)
+        .unwrap();
+    assert_eq!(card.pin, None);
+    assert!(matches!(
+        client.set_sim_pin("2-1", 3, None).await,
+        Err(Error::MissingData)
+    ));
+}
@@ -0,0 +1,133 @@
+use std::{sync::Arc, time::Duration};
+
+use futures::StreamExt;
+use tonik::{sms::SmsWatcher, Scheme, TeltonikaClient};
+use tonik_mock::{MockRouter, DEFAULT_PASSWORD, DEFAULT_USERNAME, MESSAGES_STATUS};
+
+fn client(router: &MockRouter) -> Arc<TeltonikaClient> {
+    Arc::new(
+        TeltonikaClient::builder(router.host())
+            .scheme(Scheme::Http)
+            .port(router.port())
+            .credentials(DEFAULT_USERNAME, DEFAULT_PASSWORD)
+            .build()
+            .unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn test_poll_yields_new_messages_once() {
+    let router = MockRouter::start().await.unwrap();
+    let mut watcher = SmsWatcher::new(client(&router));
+
+    // Messages stored before the watcher started are skipped.
+    assert!(watcher.poll().await.unwrap().is_empty());
+
+    let id = router.receive_sms("+447700900456", "Are you aboard?");
+    let messages = watcher.poll().await.unwrap();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].id, id);
+
+    assert!(watcher.poll().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_include_existing() {
+    let router = MockRouter::start().await.unwrap();
+    let mut watcher = SmsWatcher::new(client(&router)).include_existing(true);
+
+    assert_eq!(watcher.poll().await.unwrap().len(), 1);
+    assert!(watcher.poll().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_delete_after_read() {
+    let router = MockRouter::start().await.unwrap();
+    let mut watcher = SmsWatcher::new(client(&router))
+        .include_existing(true)
+        .delete_after_read(true);
+
+    assert_eq!(watcher.poll().await.unwrap().len(), 1);
+    let messages = router.fixture(MESSAGES_STATUS).unwrap();
+    assert!(messages.as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_delete_after_read_keeps_deleted_messages() {
+    let router = MockRouter::start().await.unwrap();
+    let mut watcher = SmsWatcher::new(client(&router)).delete_after_read(true);
+    assert!(watcher.poll().await.unwrap().is_empty());
+
+    router.fail_message_deletes("1-1");
+    router.receive_sms("+447700900456", "Deleted");
+    let mut messages = router.fixture(MESSAGES_STATUS).unwrap();
+    let mut other = messages[1].clone();
+    other["id"] = "1".into();
+    other["modem_id"] = "1-1".into();
+    other["message"] = "Not deleted".into();
+    messages.as_array_mut().unwrap().push(other);
+    router.set_fixture(MESSAGES_STATUS, messages);
+
+    // The message that was deleted is returned, then the error for the other.
+    let messages = watcher.poll().await.unwrap();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].message, "Deleted");
+    assert!(watcher.poll().await.is_err());
+
+    // The other is retried until it can be deleted.
+    assert!(watcher.poll().await.is_err());
+}
+
+#[tokio::test]
+async fn test_stream() {
+    let router = MockRouter::start().await.unwrap();
+    let stream = SmsWatcher::new(client(&router))
+        .interval(Duration::from_millis(20))
+        .into_stream();
+    let mut stream = Box::pin(stream);
+
+    router.receive_sms("+447700900456", "One");
+    router.receive_sms("+447700900456", "Two");
+
+    // Messages received before the first poll count as existing.
+    let next = tokio::time::timeout(Duration::from_millis(200), stream.next()).await;
+    assert!(next.is_err());
+
+    router.receive_sms("+447700900789", "Three");
+    let message = tokio::time::timeout(Duration::from_secs(5), stream.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert_eq!(message.message, "Three");
+}
+
+#[tokio::test]
+async fn test_stream_yields_errors() {
+    let router = MockRouter::start().await.unwrap();
+    let stream = SmsWatcher::new(client(&router))
+        .interval(Duration::from_millis(20))
+        .into_stream();
+    let mut stream = Box::pin(stream);
+
+    let next = tokio::time::timeout(Duration::from_millis(200), stream.next()).await;
+    assert!(next.is_err());
+
+    router.set_credentials(DEFAULT_USERNAME, "changed");
+    router.expire_tokens();
+    let next = tokio::time::timeout(Duration::from_secs(5), stream.next())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(next.is_err());
+
+    // The stream keeps polling once the router is reachable again.
+    router.set_credentials(DEFAULT_USERNAME, DEFAULT_PASSWORD);
+    router.receive_sms("+447700900789", "Three");
+    let message = tokio::time::timeout(Duration::from_secs(5), stream.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert_eq!(message.message, "Three");
+}